@@ -1,30 +1,36 @@
 use crate::{
     config::{
-        SystemRequirementsChecker, TSystemRequirementsChecker, DOCKER_REQUIREMENT, HELM_REQUIREMENT, K8S_REQUIREMENT,
-        TERRAFORM_REQUIREMENT,
+        SystemRequirementsChecker, TSystemRequirementsChecker, AWS_REQUIREMENT, DOCKER_REQUIREMENT, HELM_REQUIREMENT,
+        K8S_REQUIREMENT, TERRAFORM_REQUIREMENT,
     },
     infrastructure::console::{print_info, style_spinner, Dialoguer, TDialoguer},
     AppContext,
 };
-use clap::ValueEnum;
+use clap::{Args, ValueEnum};
 use colored::*;
 use indicatif::ProgressBar;
 use opraas_core::{
     application::deployment::{
-        deploy_contracts::ContractsDeployerService, deploy_infra::InfraDeployerService,
+        deploy_contracts::ContractsDeployerService, deploy_infra::InfraDeployerService, dns::DeploymentDnsService,
         manager::DeploymentManagerService,
     },
     config::CoreConfig,
-    domain::{Deployment, DeploymentKind, DeploymentOptions, Project},
+    domain::{
+        Deployment, DeploymentKind, DeploymentOptions, DeploymentTls, DnsRecord, Project, TInfraDeployerProvider,
+    },
     infrastructure::{
         deployment::{
-            DockerContractsDeployer, InMemoryDeploymentArtifactsRepository, InMemoryDeploymentRepository,
-            TerraformDeployer,
+            DockerContractsDeployer, HelmDeployer, InMemoryDeploymentArtifactsRepository, InMemoryDeploymentRepository,
+            Route53DnsProvider, TerraformDeployer,
         },
+        kubernetes::KubeCluster,
         project::InMemoryProjectInfraRepository,
         release::{DockerReleaseRepository, DockerReleaseRunner},
     },
+    system,
 };
+use std::path::PathBuf;
+use tokio::sync::watch;
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum DeployTarget {
@@ -32,6 +38,68 @@ pub enum DeployTarget {
     Infra,
 }
 
+/// Where `deploy infra` installs the chart
+#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
+pub enum InfraProvider {
+    /// Existing cluster of the current kube context
+    Helm,
+    /// New EKS cluster provisioned from infra/aws
+    TerraformAws,
+}
+
+/// Whether `deploy infra` applies right away or goes through a reviewed plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfraStep {
+    Deploy,
+    /// Save a plan under the deployment directory without applying it
+    Plan,
+    /// Apply a plan saved earlier
+    ApplyPlan(PathBuf),
+}
+
+/// How the ingresses are served, shared by `deploy` and `template`
+#[derive(Debug, Clone, Args)]
+pub struct TlsArgs {
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["tls_email", "tls_staging", "tls_secret"],
+        help = "Serve the ingresses over plain http"
+    )]
+    pub no_tls: bool,
+
+    #[arg(long, help = "Contact for Let's Encrypt expiry notices")]
+    pub tls_email: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Use Let's Encrypt staging, untrusted certificates with higher rate limits to test the setup"
+    )]
+    pub tls_staging: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["tls_email", "tls_staging"],
+        help = "Existing kubernetes.io/tls secret in the release namespace covering every hostname, instead of Let's Encrypt"
+    )]
+    pub tls_secret: Option<String>,
+}
+
+impl TlsArgs {
+    /// Let's Encrypt can't issue certificates for localhost, it's always served over http
+    pub fn for_host(&self, host: &str) -> DeploymentTls {
+        match (&self.tls_secret, self.no_tls || host == "localhost") {
+            (_, true) => DeploymentTls::Disabled,
+            (Some(name), false) => DeploymentTls::Secret { name: name.clone() },
+            (None, false) => DeploymentTls::Acme {
+                email: self.tls_email.clone(),
+                staging: self.tls_staging,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
 pub enum DeployDeploymentKind {
     Sequencer,
@@ -46,6 +114,23 @@ impl From<DeployDeploymentKind> for DeploymentKind {
         }
     }
 }
+/// What `deploy` deploys and how, as given on the command line
+pub struct DeployOptions {
+    pub target: DeployTarget,
+    pub deployment_id: String,
+    pub deployment_name: String,
+    pub release_tag: String,
+    pub release_namespace: String,
+    pub deploy_deterministic_deployer: bool,
+    pub kind: DeployDeploymentKind,
+    pub sequencer_url: String,
+    pub storage_class_name: String,
+    /// Values file merged over the generated ones
+    pub values: Option<PathBuf>,
+    pub tls: TlsArgs,
+    pub infra_step: InfraStep,
+}
+
 pub struct DeployCommand {
     dialoguer: Dialoguer,
     contracts_deployer: ContractsDeployerService<
@@ -53,15 +138,39 @@ pub struct DeployCommand {
         InMemoryDeploymentArtifactsRepository,
         DockerContractsDeployer,
     >,
-    infra_deployer:
+    infra_provider: InfraProvider,
+    infra_deployer: InfraDeployerService<
+        Box<dyn TInfraDeployerProvider>,
+        InMemoryDeploymentRepository,
+        InMemoryProjectInfraRepository,
+    >,
+    infra_planner:
         InfraDeployerService<TerraformDeployer, InMemoryDeploymentRepository, InMemoryProjectInfraRepository>,
+    dns: DeploymentDnsService,
+    /// Records are applied to this hosted zone instead of only written out
+    route53_zone_id: Option<String>,
     system_requirement_checker: SystemRequirementsChecker,
     deployments_manager: DeploymentManagerService<InMemoryDeploymentRepository, InMemoryDeploymentArtifactsRepository>,
 }
 
 impl DeployCommand {
-    pub fn new() -> Self {
+    pub fn new(infra_provider: InfraProvider, route53_zone_id: Option<String>, cancel: watch::Receiver<bool>) -> Self {
         let project = Project::try_from(std::env::current_dir().unwrap()).unwrap();
+        let artifacts_repository = Box::new(InMemoryDeploymentArtifactsRepository::new(&project.root));
+        let infra_deployer: Box<dyn TInfraDeployerProvider> = match infra_provider {
+            InfraProvider::Helm => Box::new(
+                HelmDeployer::new(artifacts_repository, Box::new(KubeCluster::new())).with_cancel(cancel.clone()),
+            ),
+            InfraProvider::TerraformAws => {
+                Box::new(TerraformDeployer::new(artifacts_repository).with_cancel(cancel.clone()))
+            }
+        };
+        let dns = match &route53_zone_id {
+            Some(hosted_zone_id) => {
+                DeploymentDnsService::new().with_provider(Box::new(Route53DnsProvider::new(hosted_zone_id)))
+            }
+            None => DeploymentDnsService::new(),
+        };
 
         Self {
             dialoguer: Dialoguer::new(),
@@ -73,13 +182,22 @@ impl DeployCommand {
                     Box::new(DockerReleaseRunner::new()),
                 ),
             ),
+            infra_provider,
             infra_deployer: InfraDeployerService::new(
+                infra_deployer,
+                InMemoryDeploymentRepository::new(&project.root),
+                InMemoryProjectInfraRepository::new(),
+            ),
+            infra_planner: InfraDeployerService::new(
                 TerraformDeployer::new(Box::new(InMemoryDeploymentArtifactsRepository::new(
                     &project.root,
-                ))),
+                )))
+                .with_cancel(cancel),
                 InMemoryDeploymentRepository::new(&project.root),
                 InMemoryProjectInfraRepository::new(),
             ),
+            dns,
+            route53_zone_id,
             deployments_manager: DeploymentManagerService::new(
                 InMemoryDeploymentRepository::new(&project.root),
                 InMemoryDeploymentArtifactsRepository::new(&project.root),
@@ -88,26 +206,37 @@ impl DeployCommand {
         }
     }
 
-    pub async fn run(
-        &self,
-        ctx: &AppContext,
-        target: &DeployTarget,
-        deployment_id: &str,
-        deployment_name: &str,
-        deployment_release_tag: &str,
-        deployment_release_namespace: &str,
-        deploy_deterministic_deployer: bool,
-        kind: DeployDeploymentKind,
-        sequencer_url: &str,
-        storage_class_name: &str,
-        values: Option<String>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.system_requirement_checker.check(vec![
-            DOCKER_REQUIREMENT,
-            K8S_REQUIREMENT,
-            HELM_REQUIREMENT,
-            TERRAFORM_REQUIREMENT,
-        ])?;
+    pub async fn run(&self, ctx: &AppContext, opts: DeployOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let DeployOptions {
+            target,
+            deployment_id,
+            deployment_name,
+            release_tag: deployment_release_tag,
+            release_namespace: deployment_release_namespace,
+            deploy_deterministic_deployer,
+            kind,
+            sequencer_url,
+            storage_class_name,
+            values,
+            tls,
+            infra_step,
+        } = opts;
+        let deployment_id = deployment_id.as_str();
+
+        if infra_step != InfraStep::Deploy
+            && (!matches!(target, DeployTarget::Infra) || self.infra_provider != InfraProvider::TerraformAws)
+        {
+            return Err("Plans are only supported by `deploy infra` with the terraform-aws provider".into());
+        }
+
+        let mut requirements = vec![DOCKER_REQUIREMENT, K8S_REQUIREMENT, HELM_REQUIREMENT];
+        if self.infra_provider == InfraProvider::TerraformAws {
+            requirements.push(TERRAFORM_REQUIREMENT);
+        }
+        if self.route53_zone_id.is_some() {
+            requirements.push(AWS_REQUIREMENT);
+        }
+        self.system_requirement_checker.check(requirements)?;
 
         let project = Project::try_from(std::env::current_dir()?)?;
         let config = CoreConfig::new_from_toml(&project.config).unwrap();
@@ -116,20 +245,18 @@ impl DeployCommand {
         // dev is reserved for local deployments
         if deployment_id == "dev" {
             return Err("Deployment id cannot be 'dev'".into());
-        } else if deployment_id.contains(" ") {
-            return Err("Deployment id cannot contain spaces".into());
-        } else if deployment_id.trim().is_empty() {
-            return Err("Deployment id cannot be empty".into());
         }
+        Deployment::validate_id(deployment_id)?;
 
         let release_registry: String = self
             .dialoguer
             .prompt("Input Docker registry url (e.g. wakeuplabs) ");
         let release_tag: String = self.dialoguer.prompt("Input release tag (e.g. v1.0.0)");
 
-        if !self
-            .dialoguer
-            .confirm("This may involve some costs. Have you double-checked the configuration? Please review .env, config.toml, infra/helm/values.yaml to ensure it's what you expect. Help yourself with the README.md files if in doubt.")
+        if infra_step != InfraStep::Plan
+            && !self
+                .dialoguer
+                .confirm("This may involve some costs. Have you double-checked the configuration? Please review .env, config.toml, infra/helm/values.yaml to ensure it's what you expect. Help yourself with the README.md files if in doubt.")
         {
             return Ok(());
         }
@@ -156,7 +283,7 @@ impl DeployCommand {
 
             let mut deployment = Deployment::new(
                 deployment_id,
-                deployment_name,
+                &deployment_name,
                 &owner_id,
                 &release_tag,
                 &release_registry,
@@ -178,6 +305,8 @@ impl DeployCommand {
 
         // infra deployment ===========================================================
 
+        let mut dns_instructions = String::new();
+
         if matches!(target, DeployTarget::Infra) {
             let mut deployment = self
                 .deployments_manager
@@ -189,29 +318,76 @@ impl DeployCommand {
                 return Err("Sequencer url is empty".into());
             }
 
-            let infra_deployer_spinner = style_spinner(ProgressBar::new_spinner(), "Deploying stack infra...");
+            let opts = DeploymentOptions {
+                host: domain.clone(),
+                monitoring: enable_monitoring,
+                explorer: enable_explorer,
+                storage_class_name: storage_class_name.to_string(),
+                release_tag: deployment_release_tag.to_string(),
+                release_namespace: deployment_release_namespace.to_string(),
+                sequencer_url: Some(sequencer_url.to_string()),
+                kind: kind.into(),
+                values_path: values,
+                tls: tls.for_host(&domain),
+            };
+
+            match &infra_step {
+                InfraStep::Deploy => {
+                    let infra_deployer_spinner = style_spinner(ProgressBar::new_spinner(), "Deploying stack infra...");
+                    self.infra_deployer
+                        .deploy(&project, &mut deployment, &opts)
+                        .await?;
+                    infra_deployer_spinner.finish_with_message("✔️ Infra deployed, your chain is live!");
+                }
+                InfraStep::Plan => {
+                    let plan_spinner = style_spinner(ProgressBar::new_spinner(), "Planning stack infra...");
+                    let plan_file = project
+                        .root
+                        .join("deployments")
+                        .join(deployment_id)
+                        .join("infra.tfplan");
+                    let plan = self
+                        .infra_planner
+                        .plan(&project, &deployment, &opts, &plan_file)
+                        .await?;
+                    plan_spinner.finish_with_message("✔️ Infra plan saved, nothing was changed");
+
+                    println!("\n{}", plan.summary);
+                    print_info(&format!(
+                        "Review the plan at {}, then apply it with the same options and `--apply-plan {}`",
+                        plan.summary_file.display(),
+                        plan.plan_file.display()
+                    ));
+                    return Ok(());
+                }
+                InfraStep::ApplyPlan(plan_file) => {
+                    let infra_deployer_spinner =
+                        style_spinner(ProgressBar::new_spinner(), "Applying stack infra plan...");
+                    self.infra_planner
+                        .apply_plan(&project, &mut deployment, &opts, plan_file)
+                        .await?;
+                    infra_deployer_spinner.finish_with_message("✔️ Infra deployed, your chain is live!");
+                }
+            }
 
-            self.infra_deployer
-                .deploy(
-                    &project,
-                    &mut deployment,
-                    &DeploymentOptions {
-                        host: domain,
-                        monitoring: enable_monitoring,
-                        explorer: enable_explorer,
-                        storage_class_name: storage_class_name.to_string(),
-                        release_tag: deployment_release_tag.to_string(),
-                        release_namespace: deployment_release_namespace.to_string(),
-                        sequencer_url: Some(sequencer_url.to_string()),
-                        kind: kind.into(),
-                        values_path: values.map(std::path::PathBuf::from),
-                    },
+            let dns_spinner = style_spinner(ProgressBar::new_spinner(), "Generating DNS records...");
+            let records = self
+                .dns
+                .publish(
+                    &deployment,
+                    &opts,
+                    &project.root.join("deployments").join(deployment_id),
                 )
                 .await?;
+            dns_spinner.finish_with_message("✔️ DNS records generated");
 
-            infra_deployer_spinner.finish_with_message("✔️ Infra deployed, your chain is live!");
+            dns_instructions = self.dns_instructions(deployment_id, &records);
+            print_info(&format!("\n{}", dns_instructions));
+        }
 
-            print_info("\nFor https domain make sure to create an A record pointing to `elb_dnsname` as specified here: https://github.com/amcginlay/venafi-demos/tree/main/demos/01-eks-ingress-nginx-cert-manager#configure-route53");
+        // nothing was deployed, there are no artifacts to display
+        if system::is_dry_run() {
+            return Ok(());
         }
 
         // clear screen and display artifacts ===========================================================
@@ -260,13 +436,35 @@ impl DeployCommand {
             title = "What's Next?".bright_white().bold(),
             bin_name=env!("CARGO_BIN_NAME").blue(),
             command="inspect [contracts|infra] --deployment-id <deployment-id>".blue(),
-            note="NOTE: At the moment there's no way to remove a deployment, you'll need to manually go to `infra/aws` and run `terraform destroy`. For upgrades you'll also need to run them directly in helm.".yellow()
+            note=format!("NOTE: At the moment there's no way to remove a deployment, you'll need to manually go to `infra/aws`, select the deployment state with `terraform workspace select {deployment_id}` and run `terraform destroy`, or `helm uninstall` the release if you deployed with `--provider helm`. For upgrades you'll also need to run them directly in helm.").yellow()
         );
 
         if matches!(target, DeployTarget::Infra) {
-            println!("\n{}\n", "Make sure to create an A records for each subdomain pointing to `elb_dnsname` as specified here: https://github.com/amcginlay/venafi-demos/tree/main/demos/01-eks-ingress-nginx-cert-manager#configure-route53".yellow());
+            println!("\n{}\n", dns_instructions.yellow());
         }
 
         Ok(())
     }
+
+    fn dns_instructions(&self, deployment_id: &str, records: &[DnsRecord]) -> String {
+        if records.is_empty() {
+            return match self.infra_provider {
+                InfraProvider::Helm => "Make sure to create an A record for each subdomain pointing to the external ip of the ingress controller, see `kubectl get svc ingress-nginx-controller -n ingress-nginx`".to_string(),
+                InfraProvider::TerraformAws => "Make sure to create an A record for each subdomain pointing to `elb_dnsname` as specified here: https://github.com/amcginlay/venafi-demos/tree/main/demos/01-eks-ingress-nginx-cert-manager#configure-route53".to_string(),
+            };
+        }
+
+        match &self.route53_zone_id {
+            Some(hosted_zone_id) => format!(
+                "DNS records applied to the Route53 hosted zone {}:\n{}",
+                hosted_zone_id,
+                DnsRecord::to_zone(records)
+            ),
+            None => format!(
+                "Make sure to create these DNS records, also saved to ./deployments/{}/dns.zone and dns.json:\n{}",
+                deployment_id,
+                DnsRecord::to_zone(records)
+            ),
+        }
+    }
 }
@@ -63,6 +63,25 @@ impl InspectCommand {
                 ),
                 None => println!("No infra found"),
             }
+
+            if let Some(outputs) = &deployment.infra_outputs {
+                println!("Provisioned infra:");
+                if let Some(cluster_name) = &outputs.cluster_name {
+                    println!("- Cluster: {}", cluster_name);
+                }
+                if let Some(region) = &outputs.region {
+                    println!("- Region: {}", region);
+                }
+                if let Some(hostname) = &outputs.load_balancer_hostname {
+                    println!("- Load balancer: {} (point your A records here)", hostname);
+                }
+                if let (Some(cluster_name), Some(region)) = (&outputs.cluster_name, &outputs.region) {
+                    println!(
+                        "- Configure kubectl: aws eks --region {} update-kubeconfig --name {}",
+                        region, cluster_name
+                    );
+                }
+            }
         }
 
         Ok(())
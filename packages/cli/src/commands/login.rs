@@ -0,0 +1,77 @@
+use crate::{
+    config::Session,
+    infrastructure::console::{print_success, style_spinner, Dialoguer, TDialoguer},
+    AppContext,
+};
+use indicatif::ProgressBar;
+use opraas_console_client::{ConsoleClient, ConsoleError};
+use opraas_core::infrastructure::ethereum::{LocalWallet, SiweMessage, TWallet};
+use std::time::Duration;
+
+pub struct LoginCommand {
+    dialoguer: Dialoguer,
+}
+
+const SIGNATURE_TTL: Duration = Duration::from_secs(5 * 60);
+const SIWE_STATEMENT: &str = "Sign in with Ethereum to the app.";
+
+impl LoginCommand {
+    pub fn new() -> Self {
+        Self {
+            dialoguer: Dialoguer::new(),
+        }
+    }
+
+    pub async fn run(&self, _ctx: &AppContext, console_url: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+        let console_url = console_url
+            .or_else(|| std::env::var("OPRUAAS_CONSOLE_URL").ok())
+            .unwrap_or_else(|| {
+                self.dialoguer
+                    .prompt("Input console api url (e.g. https://api.console.example.com)")
+            });
+        let private_key = std::env::var("OPRUAAS_PRIVATE_KEY").unwrap_or_else(|_| {
+            self.dialoguer
+                .password("Input the private key of the wallet you use in the console")
+        });
+
+        let url = reqwest::Url::parse(&console_url)?;
+        let domain = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().ok_or("Invalid console url")?, port),
+            None => url.host_str().ok_or("Invalid console url")?.to_string(),
+        };
+
+        let login_spinner = style_spinner(ProgressBar::new_spinner(), "⏳ Signing in to console...");
+
+        // sign the console issued nonce once and exchange it for a session token
+        let client = ConsoleClient::new(console_url.as_str());
+        let wallet = LocalWallet::from_private_key(&private_key)?;
+        let message = SiweMessage::new(
+            domain,
+            wallet.address(),
+            SIWE_STATEMENT.to_string(),
+            console_url.clone(),
+            client.nonce().await?,
+            SIGNATURE_TTL,
+        );
+        let signature = wallet.sign_message(&message.to_string())?;
+        let session = client
+            .verify(&message.to_string(), &signature)
+            .await
+            .map_err(|e| match e {
+                ConsoleError::Status { body, .. } => format!("Console rejected the signed message. {}", body),
+                e => e.to_string(),
+            })?;
+
+        Session {
+            console_url,
+            address: wallet.address(),
+            token: session.token,
+        }
+        .save()?;
+
+        login_spinner.finish_with_message("✔️ Signed in...");
+        print_success(&format!("Logged in as {}", wallet.address()));
+
+        Ok(())
+    }
+}
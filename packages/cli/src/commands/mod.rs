@@ -2,16 +2,24 @@ pub mod build;
 pub mod deploy;
 pub mod init;
 pub mod inspect;
+pub mod login;
 pub mod monitor;
 pub mod new;
+pub mod pull;
+pub mod push;
 pub mod release;
 pub mod start;
+pub mod template;
 
 pub use build::BuildCommand;
 pub use deploy::DeployCommand;
 pub use init::InitCommand;
 pub use inspect::InspectCommand;
+pub use login::LoginCommand;
 pub use monitor::MonitorCommand;
 pub use new::NewCommand;
+pub use pull::PullCommand;
+pub use push::PushCommand;
 pub use release::ReleaseCommand;
 pub use start::StartCommand;
+pub use template::TemplateCommand;
@@ -0,0 +1,87 @@
+use crate::{
+    config::Session,
+    infrastructure::console::{print_info, style_spinner},
+    AppContext,
+};
+use indicatif::ProgressBar;
+use opraas_core::{
+    application::deployment::manager::DeploymentManagerService,
+    domain::{Project, TDeploymentArtifactsRepository},
+    infrastructure::deployment::{
+        HttpDeploymentArtifactsRepository, HttpDeploymentRepository, InMemoryDeploymentArtifactsRepository,
+        InMemoryDeploymentRepository,
+    },
+};
+use std::{fs, io, path::PathBuf};
+
+pub struct PullCommand {
+    deployments_manager: DeploymentManagerService<InMemoryDeploymentRepository, InMemoryDeploymentArtifactsRepository>,
+    project: Project,
+}
+
+/// Where the ETag of the console revision last pulled or pushed is kept, push sends it back
+/// so that it doesn't overwrite changes made in the console meanwhile
+pub(crate) fn console_etag_path(project: &Project, deployment_id: &str) -> PathBuf {
+    project
+        .root
+        .join("deployments")
+        .join(deployment_id)
+        .join("console.etag")
+}
+
+pub(crate) fn save_console_etag(project: &Project, deployment_id: &str, etag: Option<String>) -> io::Result<()> {
+    let path = console_etag_path(project, deployment_id);
+    match etag {
+        Some(etag) => fs::write(path, etag),
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+impl PullCommand {
+    pub fn new() -> Self {
+        let project = Project::try_from(std::env::current_dir().unwrap()).unwrap();
+
+        Self {
+            deployments_manager: DeploymentManagerService::new(
+                InMemoryDeploymentRepository::new(&project.root),
+                InMemoryDeploymentArtifactsRepository::new(&project.root),
+            ),
+            project,
+        }
+    }
+
+    pub async fn run(&self, _ctx: &AppContext, deployment_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let session = Session::current()?.ok_or("Not logged in, please run `login` first")?;
+        let console_repo = HttpDeploymentRepository::new(&session.console_url, &session.token);
+        let console_artifacts_repo = HttpDeploymentArtifactsRepository::new(&session.console_url, &session.token);
+
+        let pull_spinner = style_spinner(
+            ProgressBar::new_spinner(),
+            &format!("⏳ Pulling deployment {} from console...", deployment_id),
+        );
+
+        let (deployment, etag) = console_repo
+            .find_with_etag(deployment_id)
+            .await?
+            .ok_or("Deployment not found in console")?;
+
+        self.deployments_manager.save(&deployment).await?;
+        save_console_etag(&self.project, deployment_id, etag)?;
+        if let Some(artifact) = console_artifacts_repo.find_one(&deployment).await? {
+            self.deployments_manager
+                .save_artifact(&deployment, artifact)
+                .await?;
+        }
+
+        pull_spinner.finish_with_message("✔️ Deployment pulled...");
+        print_info(&format!(
+            "You can find your deployment artifacts at ./deployments/{}",
+            deployment_id
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use super::pull::{console_etag_path, save_console_etag};
+use crate::{
+    config::Session,
+    infrastructure::console::{print_info, style_spinner},
+    AppContext,
+};
+use indicatif::ProgressBar;
+use opraas_core::{
+    application::deployment::manager::DeploymentManagerService,
+    domain::{Project, TDeploymentArtifactsRepository},
+    infrastructure::deployment::{
+        HttpDeploymentArtifactsRepository, HttpDeploymentRepository, InMemoryDeploymentArtifactsRepository,
+        InMemoryDeploymentRepository,
+    },
+};
+
+pub struct PushCommand {
+    deployments_manager: DeploymentManagerService<InMemoryDeploymentRepository, InMemoryDeploymentArtifactsRepository>,
+    project: Project,
+}
+
+impl PushCommand {
+    pub fn new() -> Self {
+        let project = Project::try_from(std::env::current_dir().unwrap()).unwrap();
+
+        Self {
+            deployments_manager: DeploymentManagerService::new(
+                InMemoryDeploymentRepository::new(&project.root),
+                InMemoryDeploymentArtifactsRepository::new(&project.root),
+            ),
+            project,
+        }
+    }
+
+    pub async fn run(&self, _ctx: &AppContext, deployment_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let session = Session::current()?.ok_or("Not logged in, please run `login` first")?;
+        let console_repo = HttpDeploymentRepository::new(&session.console_url, &session.token);
+        let console_artifacts_repo = HttpDeploymentArtifactsRepository::new(&session.console_url, &session.token);
+
+        let deployment = self
+            .deployments_manager
+            .find_by_id(deployment_id)
+            .await?
+            .ok_or("Deployment not found")?;
+
+        let push_spinner = style_spinner(
+            ProgressBar::new_spinner(),
+            &format!("⏳ Pushing deployment {} to console...", deployment_id),
+        );
+
+        // never pulled deployments are created, the others only update the revision they were pulled at
+        let etag = std::fs::read_to_string(console_etag_path(&self.project, deployment_id)).ok();
+        let etag = console_repo
+            .save_if_match(&deployment, etag.as_deref())
+            .await?;
+        save_console_etag(&self.project, deployment_id, etag)?;
+        if let Some(artifact) = self.deployments_manager.find_artifact(&deployment).await? {
+            console_artifacts_repo.save(&deployment, artifact).await?;
+        }
+
+        push_spinner.finish_with_message("✔️ Deployment pushed...");
+        print_info(&format!(
+            "Deployment {} is now available in the console",
+            deployment_id
+        ));
+
+        Ok(())
+    }
+}
@@ -5,7 +5,6 @@ use crate::{
     infrastructure::console::{print_info, print_warning, style_spinner, Dialoguer, TDialoguer},
     AppContext,
 };
-use assert_cmd::Command;
 use clap::ValueEnum;
 use indicatif::ProgressBar;
 use opraas_core::{
@@ -13,25 +12,21 @@ use opraas_core::{
         deploy_contracts::ContractsDeployerService, manager::DeploymentManagerService, run::DeploymentRunnerService,
     },
     config::CoreConfig,
-    domain::{Deployment, DeploymentKind, DeploymentOptions, Project},
+    domain::{Deployment, DeploymentKind, DeploymentOptions, DeploymentTls, Project},
     infrastructure::{
         deployment::{
             DockerContractsDeployer, HelmDeploymentRunner, InMemoryDeploymentArtifactsRepository,
             InMemoryDeploymentRepository,
         },
         ethereum::{GethTestnetNode, TTestnetNode},
+        kubernetes::KubeCluster,
         project::InMemoryProjectInfraRepository,
         release::{DockerReleaseRepository, DockerReleaseRunner},
     },
+    system,
 };
-use std::{
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    thread,
-    time::Duration,
-};
+use std::process::Command;
+use tokio::sync::watch;
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum StartDeploymentKind {
@@ -61,13 +56,14 @@ pub struct StartCommand {
         InMemoryDeploymentArtifactsRepository,
         DockerContractsDeployer,
     >,
+    cancel: watch::Receiver<bool>,
 }
 
 const DEFAULT_REGISTRY: &str = "wakeuplabs";
 const DEFAULT_RELEASE_TAG: &str = "v1.0.0";
 
 impl StartCommand {
-    pub fn new() -> Self {
+    pub fn new(cancel: watch::Receiver<bool>) -> Self {
         let project = Project::try_from(std::env::current_dir().unwrap()).unwrap();
 
         Self {
@@ -77,9 +73,11 @@ impl StartCommand {
             dialoguer: Dialoguer::new(),
             l1_node: Box::new(GethTestnetNode::new()),
             deployment_runner: DeploymentRunnerService::new(
-                HelmDeploymentRunner::new(Box::new(InMemoryDeploymentArtifactsRepository::new(
-                    &project.root,
-                ))),
+                HelmDeploymentRunner::new(
+                    Box::new(InMemoryDeploymentArtifactsRepository::new(&project.root)),
+                    Box::new(KubeCluster::new()),
+                )
+                .with_cancel(cancel.clone()),
                 InMemoryProjectInfraRepository::new(),
             ),
             deployments_manager: DeploymentManagerService::new(
@@ -95,9 +93,11 @@ impl StartCommand {
                     Box::new(DockerReleaseRunner::new()),
                 ),
             ),
+            cancel,
         }
     }
 
+    /// Starts the stack and waits for Ctrl-C, the l1 node and the release are removed however it ends
     pub async fn run(
         &mut self,
         ctx: &AppContext,
@@ -106,6 +106,30 @@ impl StartCommand {
         sequencer_url: &str,
         default: bool,
         values: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self
+            .start(
+                ctx,
+                kind,
+                contracts_deployment_id,
+                sequencer_url,
+                default,
+                values,
+            )
+            .await;
+        self.cleanup().await;
+
+        result
+    }
+
+    async fn start(
+        &mut self,
+        ctx: &AppContext,
+        kind: StartDeploymentKind,
+        contracts_deployment_id: Option<String>,
+        sequencer_url: &str,
+        default: bool,
+        values: Option<String>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         self.system_requirement_checker
             .check(vec![DOCKER_REQUIREMENT, K8S_REQUIREMENT, HELM_REQUIREMENT])?;
@@ -121,11 +145,10 @@ impl StartCommand {
 
         // confirm kubernetes context point to local
 
-        let current_context_cmd = Command::new("kubectl")
-            .arg("config")
-            .arg("current-context")
-            .output()?;
-        let current_context = String::from_utf8_lossy(&current_context_cmd.stdout);
+        let current_context = system::execute_command(
+            Command::new("kubectl").arg("config").arg("current-context"),
+            true,
+        )?;
 
         if !self.dialoguer.confirm(&format!(
             "Confirm that your kubernetes context is pointing to local: {}",
@@ -275,12 +298,18 @@ impl StartCommand {
                     sequencer_url: Some(sequencer_url.to_string()),
                     storage_class_name: "".to_string(),
                     values_path: values.map(std::path::PathBuf::from),
+                    tls: DeploymentTls::Disabled,
                 },
             )
             .await?;
 
         infra_spinner.finish_with_message("✔️ Infra installed...");
 
+        // nothing is running, no need to wait
+        if system::is_dry_run() {
+            return Ok(());
+        }
+
         // inform results and wait for exit ===========================
 
         print_info("\n\n================================================\n\n");
@@ -319,25 +348,14 @@ impl StartCommand {
 
         print_warning("Press Ctrl + C to exit...");
 
-        let running = Arc::new(AtomicBool::new(true));
-        let running_clone = Arc::clone(&running);
-
-        ctrlc::set_handler(move || {
-            running_clone.store(false, Ordering::SeqCst);
-            print_warning("Cleaning up don't interrupt...");
-        })?;
-
-        // wait for exit
-        while running.load(Ordering::SeqCst) {
-            thread::sleep(Duration::from_secs(1));
-        }
+        // the sender lives as long as the cli, an error only means nobody can cancel anymore
+        let _ = self.cancel.wait_for(|cancelled| *cancelled).await;
+        print_warning("Cleaning up don't interrupt...");
 
         Ok(())
     }
-}
 
-impl Drop for StartCommand {
-    fn drop(&mut self) {
+    async fn cleanup(&mut self) {
         match self.l1_node.stop() {
             Ok(_) => {}
             Err(e) => {
@@ -346,7 +364,7 @@ impl Drop for StartCommand {
         }
 
         if let (Some(tag), Some(namespace)) = (self.release_tag.as_ref(), self.release_namespace.as_ref()) {
-            if let Err(e) = self.deployment_runner.stop(tag, namespace) {
+            if let Err(e) = self.deployment_runner.stop(tag, namespace).await {
                 print_warning(&format!("Failed to stop stack runner: {}", e));
             }
         } else {
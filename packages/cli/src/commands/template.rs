@@ -0,0 +1,102 @@
+use crate::{
+    config::{SystemRequirementsChecker, TSystemRequirementsChecker, HELM_REQUIREMENT},
+    infrastructure::console::{print_info, style_spinner},
+    AppContext,
+};
+use indicatif::ProgressBar;
+use opraas_core::{
+    application::deployment::{manager::DeploymentManagerService, template::DeploymentTemplateService},
+    domain::{DeploymentOptions, Project},
+    infrastructure::{
+        deployment::{HelmDeploymentRunner, InMemoryDeploymentArtifactsRepository, InMemoryDeploymentRepository},
+        kubernetes::KubeCluster,
+        project::InMemoryProjectInfraRepository,
+    },
+    system,
+};
+use std::{fs, path::Path};
+use tokio::sync::watch;
+
+pub struct TemplateCommand {
+    deployment_renderer: DeploymentTemplateService<HelmDeploymentRunner, InMemoryProjectInfraRepository>,
+    deployments_manager: DeploymentManagerService<InMemoryDeploymentRepository, InMemoryDeploymentArtifactsRepository>,
+    system_requirement_checker: SystemRequirementsChecker,
+}
+
+impl TemplateCommand {
+    pub fn new(cancel: watch::Receiver<bool>) -> Self {
+        let project = Project::try_from(std::env::current_dir().unwrap()).unwrap();
+
+        Self {
+            deployment_renderer: DeploymentTemplateService::new(
+                HelmDeploymentRunner::new(
+                    Box::new(InMemoryDeploymentArtifactsRepository::new(&project.root)),
+                    Box::new(KubeCluster::new()),
+                )
+                .with_cancel(cancel),
+                InMemoryProjectInfraRepository::new(),
+            ),
+            deployments_manager: DeploymentManagerService::new(
+                InMemoryDeploymentRepository::new(&project.root),
+                InMemoryDeploymentArtifactsRepository::new(&project.root),
+            ),
+            system_requirement_checker: SystemRequirementsChecker::new(),
+        }
+    }
+
+    pub async fn run(
+        &self,
+        _ctx: &AppContext,
+        deployment_id: &str,
+        output_dir: &str,
+        overwrite: bool,
+        opts: &DeploymentOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.system_requirement_checker
+            .check(vec![HELM_REQUIREMENT])?;
+
+        let project = Project::try_from(std::env::current_dir()?)?;
+        let deployment = self
+            .deployments_manager
+            .find_by_id(deployment_id)
+            .await?
+            .ok_or("Deployment not found")?;
+
+        // stale manifests of disabled components would otherwise stay around
+        let output_dir = Path::new(output_dir);
+        if output_dir.exists() && fs::read_dir(output_dir)?.next().is_some() {
+            if !overwrite {
+                return Err(format!(
+                    "{} is not empty, use --overwrite to replace its contents",
+                    output_dir.display()
+                )
+                .into());
+            }
+            if !system::is_dry_run() {
+                fs::remove_dir_all(output_dir)?;
+            }
+        }
+
+        let template_spinner = style_spinner(ProgressBar::new_spinner(), "Rendering manifests...");
+
+        let files = self
+            .deployment_renderer
+            .render(&project, &deployment, opts, output_dir)
+            .await?;
+
+        template_spinner.finish_with_message(format!(
+            "✔️ Rendered {} manifests to {}",
+            files.len(),
+            output_dir.display()
+        ));
+
+        for file in files {
+            print_info(&format!(
+                "- {}",
+                file.strip_prefix(output_dir).unwrap_or(&file).display()
+            ));
+        }
+
+        Ok(())
+    }
+}
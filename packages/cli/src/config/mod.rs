@@ -1,3 +1,5 @@
 pub mod requirements;
+pub mod session;
 
 pub use requirements::*;
+pub use session::*;
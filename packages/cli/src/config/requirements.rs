@@ -1,4 +1,5 @@
 use crate::infrastructure::system::{System, TSystem};
+use opraas_core::system::is_dry_run;
 use regex::Regex;
 use semver::Version;
 use std::{fmt, process::Command};
@@ -46,15 +47,22 @@ pub const K8S_REQUIREMENT: Requirement = Requirement {
 pub const HELM_REQUIREMENT: Requirement = Requirement {
     program: "helm",
     version_arg: "version",
-    required_version: "3.0.0",
+    required_version: "3.13.0",
     required_comparator: Comparison::GreaterThanOrEqual,
 };
+/// At least 1.4, deployments select their workspace with `-or-create`
 pub const TERRAFORM_REQUIREMENT: Requirement = Requirement {
     program: "terraform",
     version_arg: "-v",
     required_version: "1.9.8",
     required_comparator: Comparison::GreaterThanOrEqual,
 };
+pub const AWS_REQUIREMENT: Requirement = Requirement {
+    program: "aws",
+    version_arg: "--version",
+    required_version: "2.0.0",
+    required_comparator: Comparison::GreaterThanOrEqual,
+};
 pub const GIT_REQUIREMENT: Requirement = Requirement {
     program: "git",
     version_arg: "--version",
@@ -86,6 +94,11 @@ impl SystemRequirementsChecker {
 
 impl TSystemRequirementsChecker for SystemRequirementsChecker {
     fn check(&self, requirements: Vec<Requirement>) -> Result<(), String> {
+        // dry runs are reviewed where the tools may not be installed
+        if is_dry_run() {
+            return Ok(());
+        }
+
         for requirement in requirements.iter() {
             let output = self
                 .system
@@ -206,4 +219,19 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn terraform_without_workspace_or_create_fails() {
+        let mut mock_system = MockTSystem::new();
+        mock_system
+            .expect_execute_command()
+            .times(1)
+            .returning(|_| Ok("Terraform v1.3.9\non linux_amd64".to_string()));
+
+        let checker = SystemRequirementsChecker {
+            system: Box::new(mock_system),
+        };
+
+        assert!(checker.check(vec![TERRAFORM_REQUIREMENT]).is_err());
+    }
 }
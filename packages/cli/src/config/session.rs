@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// Console session persisted by `login` and used by `push` and `pull`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub console_url: String,
+    pub address: String,
+    pub token: String,
+}
+
+// implementations =============================================
+
+impl Session {
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let home = std::env::var("HOME").map_err(|_| "HOME is not set")?;
+
+        Ok(PathBuf::from(home).join(".opruaas").join("session.json"))
+    }
+
+    /// Session from an api token in `OPRUAAS_CONSOLE_TOKEN`, falling back to the one stored by `login`
+    pub fn current() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        match (
+            std::env::var("OPRUAAS_CONSOLE_URL"),
+            std::env::var("OPRUAAS_CONSOLE_TOKEN"),
+        ) {
+            (Ok(console_url), Ok(token)) => Ok(Some(Self {
+                console_url,
+                address: String::new(),
+                token,
+            })),
+            _ => Self::load(),
+        }
+    }
+
+    pub fn load() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let session: Session = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        Ok(Some(session))
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
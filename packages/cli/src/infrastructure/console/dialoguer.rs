@@ -11,6 +11,7 @@ impl Dialoguer {
 pub trait TDialoguer: Send + Sync {
     fn prompt(&self, message: &str) -> String;
     fn confirm(&self, message: &str) -> bool;
+    fn password(&self, message: &str) -> String;
 }
 
 impl TDialoguer for Dialoguer {
@@ -27,4 +28,11 @@ impl TDialoguer for Dialoguer {
             .interact()
             .expect("Failed to confirm")
     }
+
+    fn password(&self, message: &str) -> String {
+        dialoguer::Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(message)
+            .interact()
+            .expect("Failed to prompt")
+    }
 }
@@ -7,17 +7,24 @@ use clap::{Parser, Subcommand};
 use colored::Colorize;
 use commands::{
     build::BuildTargets,
-    deploy::{DeployDeploymentKind, DeployTarget},
+    deploy::{DeployDeploymentKind, DeployOptions, DeployTarget, InfraProvider, InfraStep, TlsArgs},
     init::InitTargets,
     inspect::InspectTarget,
     monitor::{MonitorKind, MonitorTarget},
     release::ReleaseTargets,
     start::StartDeploymentKind,
-    BuildCommand, DeployCommand, InitCommand, InspectCommand, MonitorCommand, NewCommand, ReleaseCommand, StartCommand,
+    BuildCommand, DeployCommand, InitCommand, InspectCommand, LoginCommand, MonitorCommand, NewCommand, PullCommand,
+    PushCommand, ReleaseCommand, StartCommand, TemplateCommand,
 };
 use dotenv::dotenv;
-use infrastructure::console::print_error;
+use infrastructure::console::{print_error, print_info, print_warning};
 use log::{Level, LevelFilter};
+use opraas_core::{
+    domain::DeploymentOptions,
+    system::{set_command_runner, RecordingCommandRunner},
+};
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::watch;
 
 #[derive(Parser)]
 #[clap(name = "opruaas")]
@@ -30,6 +37,10 @@ struct Args {
     /// Suppress logging output
     #[arg(short, long, default_value_t = false)]
     verbose: bool,
+
+    /// Print the docker, helm, terraform and kubectl commands and the files that would be written, without running them
+    #[arg(long, global = true, default_value_t = false)]
+    dry_run: bool,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -93,6 +104,85 @@ enum Commands {
 
         #[arg(long, help = "Path to a custom helm values file")]
         values: Option<String>,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = InfraProvider::TerraformAws,
+            help = "Provision a new EKS cluster with terraform or install into the current kube context with helm"
+        )]
+        provider: InfraProvider,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with = "apply_plan",
+            help = "Save the infra plan and a readable summary under the deployment directory without applying it"
+        )]
+        plan_only: bool,
+
+        #[arg(
+            long,
+            help = "Apply an infra plan saved with --plan-only, refused if the inputs changed since"
+        )]
+        apply_plan: Option<String>,
+
+        #[arg(
+            long,
+            help = "Upsert the generated DNS records in this Route53 hosted zone with the aws cli"
+        )]
+        route53_zone_id: Option<String>,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+    },
+    /// Render the kubernetes manifests of a deployment to a directory, without installing them
+    ///
+    /// Needs network access to download the chart dependencies from their helm repositories, and the
+    /// contracts of the deployment to be deployed, the chart embeds their artifacts and addresses.
+    Template {
+        #[arg(value_enum, default_value_t = DeployDeploymentKind::Sequencer)]
+        kind: DeployDeploymentKind,
+
+        #[arg(long)]
+        deployment_id: String,
+
+        #[arg(long, default_value = "manifests")]
+        output_dir: String,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Replace the contents of a non empty output dir"
+        )]
+        overwrite: bool,
+
+        #[arg(long, default_value = "localhost")]
+        domain: String,
+
+        #[arg(long, default_value_t = false)]
+        monitoring: bool,
+
+        #[arg(long, default_value_t = false)]
+        explorer: bool,
+
+        #[arg(long, default_value = "")]
+        sequencer_url: String,
+
+        #[arg(long, default_value = "gp2")]
+        storage_class_name: String,
+
+        #[arg(long, default_value = "opruaas")]
+        deployment_release_tag: String,
+
+        #[arg(long, default_value = "opruaas")]
+        deployment_release_namespace: String,
+
+        #[arg(long, help = "Path to a custom helm values file")]
+        values: Option<String>,
+
+        #[command(flatten)]
+        tls: TlsArgs,
     },
     /// Get details about the current deployment. Target must be one of: contracts, infra
     Inspect {
@@ -117,10 +207,27 @@ enum Commands {
         #[arg(trailing_var_arg = true)]
         args: Option<Vec<String>>,
     },
+    /// Sign in to the console with a local wallet
+    Login {
+        #[arg(long, help = "Console api url, defaults to OPRUAAS_CONSOLE_URL")]
+        console_url: Option<String>,
+    },
+    /// Upload a local deployment and its artifacts to the console
+    Push {
+        #[arg(long)]
+        deployment_id: String,
+    },
+    /// Download a deployment and its artifacts from the console
+    Pull {
+        #[arg(long)]
+        deployment_id: String,
+    },
 }
 
 pub struct AppContext {
     pub user_id: Option<String>,
+    /// Turns true on Ctrl-C, commands stop what they run and clean up
+    pub cancel: watch::Receiver<bool>,
 }
 
 #[tokio::main]
@@ -153,12 +260,30 @@ async fn main() {
         .filter_module("opraas_core", log_level)
         .init();
 
+    // the only signal handler, a second Ctrl-C exits without waiting for the cleanup
+    let (cancel_tx, cancel) = watch::channel(false);
+    if let Err(e) = ctrlc::set_handler(move || {
+        if cancel_tx.send_replace(true) {
+            std::process::exit(130);
+        }
+        print_warning("Cancelling, press Ctrl + C again to exit right away...");
+    }) {
+        print_error(&format!("Failed to set the Ctrl-C handler: {}", e));
+    }
+
     let ctx = AppContext {
         user_id: Some("root".into()),
+        cancel,
     };
 
+    let recorder = args.dry_run.then(|| {
+        let recorder = Arc::new(RecordingCommandRunner::new());
+        set_command_runner(recorder.clone());
+        recorder
+    });
+
     // run commands
-    if let Err(e) = match args.cmd {
+    let result = match args.cmd {
         Commands::New { name } => NewCommand::new().run(&ctx, &name),
         Commands::Init { target } => InitCommand::new().run(&ctx, &target),
         Commands::Build { target } => BuildCommand::new().run(&ctx, &target),
@@ -170,7 +295,7 @@ async fn main() {
             sequencer_url,
             values,
         } => {
-            StartCommand::new()
+            StartCommand::new(ctx.cancel.clone())
                 .run(
                     &ctx,
                     kind,
@@ -192,20 +317,71 @@ async fn main() {
             sequencer_url,
             storage_class_name,
             values,
+            provider,
+            plan_only,
+            apply_plan,
+            route53_zone_id,
+            tls,
         } => {
-            DeployCommand::new()
+            let infra_step = match (plan_only, apply_plan) {
+                (true, _) => InfraStep::Plan,
+                (false, Some(plan_file)) => InfraStep::ApplyPlan(PathBuf::from(plan_file)),
+                (false, None) => InfraStep::Deploy,
+            };
+
+            DeployCommand::new(provider, route53_zone_id, ctx.cancel.clone())
+                .run(
+                    &ctx,
+                    DeployOptions {
+                        target,
+                        deployment_name: deployment_name.unwrap_or(deployment_id.clone()),
+                        deployment_id,
+                        release_tag: deployment_release_tag,
+                        release_namespace: deployment_release_namespace,
+                        deploy_deterministic_deployer,
+                        kind,
+                        sequencer_url,
+                        storage_class_name,
+                        values: values.map(PathBuf::from),
+                        tls,
+                        infra_step,
+                    },
+                )
+                .await
+        }
+        Commands::Template {
+            kind,
+            deployment_id,
+            output_dir,
+            overwrite,
+            domain,
+            monitoring,
+            explorer,
+            sequencer_url,
+            storage_class_name,
+            deployment_release_tag,
+            deployment_release_namespace,
+            values,
+            tls,
+        } => {
+            TemplateCommand::new(ctx.cancel.clone())
                 .run(
                     &ctx,
-                    &target,
                     &deployment_id,
-                    &deployment_name.unwrap_or(deployment_id.clone()),
-                    &deployment_release_tag,
-                    &deployment_release_namespace,
-                    deploy_deterministic_deployer,
-                    kind,
-                    &sequencer_url,
-                    &storage_class_name,
-                    values,
+                    &output_dir,
+                    overwrite,
+                    &DeploymentOptions {
+                        kind: kind.into(),
+                        monitoring,
+                        explorer,
+                        release_tag: deployment_release_tag,
+                        release_namespace: deployment_release_namespace,
+                        storage_class_name,
+                        sequencer_url: Some(sequencer_url),
+                        values_path: values.map(PathBuf::from),
+                        tls: tls.for_host(&domain),
+                        host: domain,
+                    },
                 )
                 .await
         }
@@ -227,7 +403,20 @@ async fn main() {
                 .run(&ctx, &target, &deployment_id, kind, args)
                 .await
         }
-    } {
+        Commands::Login { console_url } => LoginCommand::new().run(&ctx, console_url).await,
+        Commands::Push { deployment_id } => PushCommand::new().run(&ctx, &deployment_id).await,
+        Commands::Pull { deployment_id } => PullCommand::new().run(&ctx, &deployment_id).await,
+    };
+
+    // printed even if the command failed, it shows how far it got
+    if let Some(recorder) = recorder {
+        print_info("\nDry run, nothing was executed. The command would have run:\n");
+        for step in recorder.transcript() {
+            println!("{}", step);
+        }
+    }
+
+    if let Err(e) = result {
         print_error(&format!("\n\nError: {}\n\n", e));
         std::process::exit(1);
     }
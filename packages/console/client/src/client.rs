@@ -0,0 +1,312 @@
+use crate::{ConsoleError, ConsoleSession, DeploymentRevision, NonceResponse, VerifyPayload, CHECKSUM_HEADER};
+use opraas_core::domain::{Deployment, DeploymentArtifact};
+use reqwest::{
+    header::IF_MATCH,
+    multipart::{Form, Part},
+    Client, Method, RequestBuilder, Response, StatusCode,
+};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+
+/// Typed client for the console api, see `/openapi.json` on any console for the full specification
+#[derive(Debug, Clone)]
+pub struct ConsoleClient {
+    http: Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl ConsoleClient {
+    pub fn new<T>(base_url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            http: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Authenticates requests with a session or api token
+    pub fn with_token<T>(mut self, token: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.token = Some(token.into());
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    // auth ==========================================================
+
+    /// Requests a single use nonce to be included in the signed message
+    pub async fn nonce(&self) -> Result<String, ConsoleError> {
+        let response: NonceResponse = json(self.request(Method::GET, "/auth/nonce").send().await?).await?;
+
+        Ok(response.nonce)
+    }
+
+    /// Exchanges a signed Sign-In with Ethereum message for a session
+    pub async fn verify(&self, message: &str, signature: &str) -> Result<ConsoleSession, ConsoleError> {
+        let response = self
+            .request(Method::POST, "/auth/verify")
+            .json(&VerifyPayload { message, signature })
+            .send()
+            .await?;
+
+        // a rejected signature is not an expired session
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(ConsoleError::Status {
+                status: response.status(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        json(response).await
+    }
+
+    // deployments ===================================================
+
+    /// Deployments in every org the user belongs to
+    pub async fn list_deployments(&self) -> Result<Vec<Deployment>, ConsoleError> {
+        json(self.request(Method::GET, "/deployments").send().await?).await
+    }
+
+    pub async fn find_deployment(&self, id: &str) -> Result<Option<Deployment>, ConsoleError> {
+        let response = self
+            .request(Method::GET, &format!("/deployments/{}", id))
+            .send()
+            .await?;
+
+        optional(response, json).await
+    }
+
+    /// Creates the deployment in the user personal org, an empty id is replaced by a generated one
+    pub async fn create_deployment(&self, deployment: &Deployment) -> Result<Deployment, ConsoleError> {
+        let response = self
+            .request(Method::POST, "/deployments")
+            .json(deployment)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// None if the console has no deployment with the same id. Without an etag
+    /// whatever revision is current is replaced
+    pub async fn update_deployment(
+        &self,
+        deployment: &Deployment,
+        etag: Option<&str>,
+    ) -> Result<Option<Deployment>, ConsoleError> {
+        let response = self
+            .request(Method::PUT, &format!("/deployments/{}", deployment.id))
+            .header(IF_MATCH, etag.unwrap_or("*"))
+            .json(deployment)
+            .send()
+            .await?;
+
+        optional(response, json).await
+    }
+
+    pub async fn delete_deployment(&self, id: &str) -> Result<(), ConsoleError> {
+        let response = self
+            .request(Method::DELETE, &format!("/deployments/{}", id))
+            .send()
+            .await?;
+        ensure_success(response).await?;
+
+        Ok(())
+    }
+
+    /// Every revision of the deployment, oldest first
+    pub async fn list_revisions(&self, id: &str) -> Result<Vec<DeploymentRevision>, ConsoleError> {
+        let response = self
+            .request(Method::GET, &format!("/deployments/{}/revisions", id))
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    // artifacts =====================================================
+
+    /// Downloads the artifact, verified against the checksum reported by the console
+    pub async fn find_artifact(&self, deployment_id: &str) -> Result<Option<DeploymentArtifact>, ConsoleError> {
+        let response = self
+            .request(
+                Method::GET,
+                &format!("/deployments/{}/artifact", deployment_id),
+            )
+            .send()
+            .await?;
+
+        optional(response, |response| async move {
+            let checksum = response
+                .headers()
+                .get(CHECKSUM_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_lowercase());
+            let artifact = response.bytes().await?.to_vec();
+
+            // artifacts uploaded before checksums were recorded come without one
+            if let Some(expected) = checksum {
+                verify_checksum(&artifact, &expected)?;
+            }
+
+            Ok(artifact)
+        })
+        .await
+    }
+
+    pub async fn artifact_exists(&self, deployment_id: &str) -> Result<bool, ConsoleError> {
+        let response = self
+            .request(
+                Method::HEAD,
+                &format!("/deployments/{}/artifact", deployment_id),
+            )
+            .send()
+            .await?;
+
+        Ok(optional(response, |_| async { Ok::<_, ConsoleError>(()) })
+            .await?
+            .is_some())
+    }
+
+    /// Uploads the zipped artifact, returns its sha256 as stored by the console
+    pub async fn upload_artifact(
+        &self,
+        deployment_id: &str,
+        artifact: DeploymentArtifact,
+    ) -> Result<String, ConsoleError> {
+        let expected = hex::encode(Sha256::digest(&artifact));
+        let form = Form::new().part(
+            "file",
+            Part::bytes(artifact)
+                .file_name("artifact.zip")
+                .mime_str("application/zip")?,
+        );
+
+        let response = self
+            .request(
+                Method::PUT,
+                &format!("/deployments/{}/artifact", deployment_id),
+            )
+            .multipart(form)
+            .send()
+            .await?;
+        let response = ensure_success(response).await?;
+
+        match response
+            .headers()
+            .get(CHECKSUM_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(actual) if !actual.eq_ignore_ascii_case(&expected) => Err(ConsoleError::ChecksumMismatch {
+                expected,
+                actual: actual.to_string(),
+            }),
+            _ => Ok(expected),
+        }
+    }
+
+    pub async fn delete_artifact(&self, deployment_id: &str) -> Result<(), ConsoleError> {
+        let response = self
+            .request(
+                Method::DELETE,
+                &format!("/deployments/{}/artifact", deployment_id),
+            )
+            .send()
+            .await?;
+        ensure_success(response).await?;
+
+        Ok(())
+    }
+}
+
+/// Turns non successful console responses into errors
+async fn ensure_success(response: Response) -> Result<Response, ConsoleError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(ConsoleError::Unauthorized);
+    }
+
+    Err(ConsoleError::Status {
+        status,
+        body: response.text().await.unwrap_or_default(),
+    })
+}
+
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T, ConsoleError> {
+    Ok(ensure_success(response).await?.json().await?)
+}
+
+/// Maps not found to none, reading successful responses with `read`
+async fn optional<T, F, Fut>(response: Response, read: F) -> Result<Option<T>, ConsoleError>
+where
+    F: FnOnce(Response) -> Fut,
+    Fut: std::future::Future<Output = Result<T, ConsoleError>>,
+{
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    read(ensure_success(response).await?).await.map(Some)
+}
+
+fn verify_checksum(artifact: &[u8], expected: &str) -> Result<(), ConsoleError> {
+    let actual = hex::encode(Sha256::digest(artifact));
+    if actual != expected {
+        return Err(ConsoleError::ChecksumMismatch {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_checksum_accepts_matching_artifacts() {
+        let artifact = b"artifact".to_vec();
+        let checksum = hex::encode(Sha256::digest(&artifact));
+
+        assert!(verify_checksum(&artifact, &checksum).is_ok());
+    }
+
+    #[test]
+    fn verify_checksum_rejects_tampered_artifacts() {
+        let checksum = hex::encode(Sha256::digest(b"artifact"));
+
+        assert!(matches!(
+            verify_checksum(b"tampered", &checksum),
+            Err(ConsoleError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn base_url_trailing_slash_is_ignored() {
+        let client = ConsoleClient::new("https://console.example.com/");
+
+        assert_eq!(client.base_url, "https://console.example.com");
+    }
+}
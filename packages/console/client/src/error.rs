@@ -0,0 +1,17 @@
+use reqwest::StatusCode;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum ConsoleError {
+    #[error("Console session is missing or expired, please run `login` again")]
+    Unauthorized,
+
+    #[error("Console request failed with status {status}. {body}")]
+    Status { status: StatusCode, body: String },
+
+    #[error("Artifact checksum mismatch, expected {expected} but downloaded {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("Could not reach the console. {0}")]
+    Http(#[from] reqwest::Error),
+}
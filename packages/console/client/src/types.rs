@@ -0,0 +1,30 @@
+use opraas_core::domain::Deployment;
+use serde::{Deserialize, Serialize};
+
+pub use opraas_core::infrastructure::deployment::CHECKSUM_HEADER;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NonceResponse {
+    pub nonce: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyPayload<'a> {
+    pub message: &'a str,
+    pub signature: &'a str,
+}
+
+/// Session issued by the console once a signed message is verified
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConsoleSession {
+    pub token: String,
+    pub user_id: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeploymentRevision {
+    pub revision: i32,
+    pub created_at: i64,
+    pub deployment: Deployment,
+}
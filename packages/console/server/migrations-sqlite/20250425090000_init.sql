@@ -0,0 +1,108 @@
+-- SQLite schema, mirrors the postgres migrations. Timestamps are unix seconds
+CREATE TABLE auth_nonces (
+    nonce TEXT NOT NULL, -- Random nonce
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()), -- Issue time, nonces expire after a short ttl
+
+    PRIMARY KEY (nonce)
+);
+
+CREATE TABLE auth_sessions (
+    token_hash TEXT NOT NULL, -- SHA-256 of the session token, the token itself is never stored
+    user_id TEXT NOT NULL, -- Signer address
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    expires_at INTEGER NOT NULL,
+
+    PRIMARY KEY (token_hash)
+);
+
+CREATE INDEX auth_sessions_expires_at_idx ON auth_sessions (expires_at);
+
+CREATE TABLE api_tokens (
+    id TEXT NOT NULL, -- Unique identifier
+    user_id TEXT NOT NULL, -- Owner ID
+    name TEXT NOT NULL, -- Human readable name
+    token_hash TEXT NOT NULL, -- SHA-256 of the token, the token itself is never stored
+    scopes TEXT NOT NULL, -- Granted scopes as a json array, e.g. ["deployments:read"]
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    expires_at INTEGER, -- Optional expiry
+    last_used_at INTEGER, -- Updated on every authenticated request
+
+    PRIMARY KEY (id),
+    UNIQUE (token_hash)
+);
+
+CREATE INDEX api_tokens_user_id_idx ON api_tokens (user_id);
+
+CREATE TABLE orgs (
+    id TEXT NOT NULL, -- Unique identifier, personal orgs use the owner user id
+    name TEXT NOT NULL, -- Org name
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE org_members (
+    org_id TEXT NOT NULL REFERENCES orgs (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL, -- Member address
+    role TEXT NOT NULL CHECK (role IN ('owner', 'operator', 'viewer')),
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    PRIMARY KEY (org_id, user_id)
+);
+
+CREATE INDEX org_members_user_id_idx ON org_members (user_id);
+
+CREATE TABLE deployments (
+    id TEXT NOT NULL, -- Unique identifier
+    owner_id TEXT NOT NULL REFERENCES orgs (id), -- Owning org
+    version INTEGER NOT NULL, -- Head revision
+    artifact_sha256 TEXT, -- Checksum of the last uploaded artifact
+    artifact_size INTEGER,
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE deployment_revisions (
+    deployment_id TEXT NOT NULL REFERENCES deployments (id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL, -- Sequential per deployment, starting at 1
+    name TEXT NOT NULL,
+    release_tag TEXT NOT NULL,
+    release_registry TEXT NOT NULL,
+    network_config TEXT NOT NULL, -- Serialized network configuration
+    accounts_config TEXT NOT NULL, -- Serialized accounts configuration
+    infra_base_url TEXT,
+    contracts_addresses TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    PRIMARY KEY (deployment_id, revision)
+);
+
+CREATE TRIGGER deployment_revisions_immutable
+    BEFORE UPDATE ON deployment_revisions
+BEGIN
+    SELECT RAISE(ABORT, 'deployment_revisions are immutable');
+END;
+
+CREATE TABLE audit_events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT, -- Monotonic identifier, used as pagination cursor
+    actor_id TEXT NOT NULL, -- User performing the action
+    action TEXT NOT NULL, -- e.g. deployment.updated
+    deployment_id TEXT NOT NULL, -- Target deployment, kept after the deployment is deleted
+    diff TEXT NOT NULL, -- Changed fields as json, with secrets redacted
+    request_id TEXT, -- Request id as in the x-request-id header
+    created_at INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE INDEX audit_events_deployment_id_idx ON audit_events (deployment_id, id DESC);
+
+CREATE TRIGGER audit_events_no_update
+    BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append only');
+END;
+
+CREATE TRIGGER audit_events_no_delete
+    BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append only');
+END;
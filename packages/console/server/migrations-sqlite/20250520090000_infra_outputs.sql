@@ -0,0 +1,2 @@
+-- Serialized outputs of the provisioned infra, e.g. cluster name and load balancer hostname
+ALTER TABLE deployment_revisions ADD COLUMN infra_outputs TEXT;
@@ -0,0 +1,12 @@
+-- At most one queued or running job per deployment, enforced here so concurrent requests can't both start one
+
+-- older duplicates can only come from that race, the newest one is kept
+UPDATE jobs SET status = 'failed', error = 'Superseded by another job of the deployment', finished_at = unixepoch()
+WHERE status IN ('queued', 'running') AND EXISTS (
+    SELECT 1 FROM jobs newer
+    WHERE newer.deployment_id = jobs.deployment_id
+        AND newer.status IN ('queued', 'running')
+        AND (newer.created_at, newer.id) > (jobs.created_at, jobs.id)
+);
+
+CREATE UNIQUE INDEX jobs_in_progress_idx ON jobs (deployment_id) WHERE status IN ('queued', 'running');
@@ -0,0 +1,19 @@
+-- Single use nonces handed out to clients before signing a SIWE message
+CREATE TABLE auth_nonces (
+    nonce TEXT NOT NULL, -- Random nonce
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- Issue time, nonces expire after a short ttl
+
+    PRIMARY KEY (nonce)
+);
+
+-- Sessions issued after a SIWE message is verified
+CREATE TABLE auth_sessions (
+    token_hash TEXT NOT NULL, -- SHA-256 of the session token, the token itself is never stored
+    user_id TEXT NOT NULL, -- Signer address
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+
+    PRIMARY KEY (token_hash)
+);
+
+CREATE INDEX auth_sessions_expires_at_idx ON auth_sessions (expires_at);
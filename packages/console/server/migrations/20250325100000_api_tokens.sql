@@ -0,0 +1,16 @@
+-- Personal api tokens for automation such as ci pipelines
+CREATE TABLE api_tokens (
+    id TEXT NOT NULL, -- Unique identifier
+    user_id TEXT NOT NULL, -- Owner ID
+    name TEXT NOT NULL, -- Human readable name
+    token_hash TEXT NOT NULL, -- SHA-256 of the token, the token itself is never stored
+    scopes TEXT[] NOT NULL, -- Granted scopes, e.g. deployments:read
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ, -- Optional expiry
+    last_used_at TIMESTAMPTZ, -- Updated on every authenticated request
+
+    PRIMARY KEY (id),
+    UNIQUE (token_hash)
+);
+
+CREATE INDEX api_tokens_user_id_idx ON api_tokens (user_id);
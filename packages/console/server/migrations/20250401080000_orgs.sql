@@ -0,0 +1,29 @@
+-- Organizations own deployments, users access them through their membership role
+CREATE TABLE orgs (
+    id TEXT NOT NULL, -- Unique identifier, personal orgs use the owner user id
+    name TEXT NOT NULL, -- Org name
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE org_members (
+    org_id TEXT NOT NULL REFERENCES orgs (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL, -- Member address
+    role TEXT NOT NULL CHECK (role IN ('owner', 'operator', 'viewer')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (org_id, user_id)
+);
+
+CREATE INDEX org_members_user_id_idx ON org_members (user_id);
+
+-- Existing deployments move to the personal org of their owner, keeping owner_id as the owning org id
+INSERT INTO orgs (id, name)
+SELECT DISTINCT owner_id, 'Personal' FROM deployments;
+
+INSERT INTO org_members (org_id, user_id, role)
+SELECT DISTINCT owner_id, owner_id, 'owner' FROM deployments;
+
+ALTER TABLE deployments
+    ADD CONSTRAINT deployments_owner_id_fkey FOREIGN KEY (owner_id) REFERENCES orgs (id);
@@ -0,0 +1,24 @@
+-- Append only log of every console mutation
+CREATE TABLE audit_events (
+    id BIGSERIAL NOT NULL, -- Monotonic identifier, used as pagination cursor
+    actor_id TEXT NOT NULL, -- User performing the action
+    action TEXT NOT NULL, -- e.g. deployment.updated
+    deployment_id TEXT NOT NULL, -- Target deployment, kept after the deployment is deleted
+    diff JSONB NOT NULL, -- Changed fields as { field: { before, after } } with secrets redacted
+    request_id TEXT, -- Request id as in the x-request-id header
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX audit_events_deployment_id_idx ON audit_events (deployment_id, id DESC);
+
+CREATE FUNCTION audit_events_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
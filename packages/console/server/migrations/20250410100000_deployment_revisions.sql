@@ -0,0 +1,39 @@
+-- Every save creates an immutable revision, deployments only point to the current head
+CREATE TABLE deployment_revisions (
+    deployment_id TEXT NOT NULL REFERENCES deployments (id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL, -- Sequential per deployment, starting at 1
+    name TEXT NOT NULL, -- Deployment name
+    release_tag TEXT NOT NULL, -- Release tag
+    release_registry TEXT NOT NULL, -- Release registry
+    network_config TEXT NOT NULL, -- TEXT for serialized network configuration
+    accounts_config TEXT NOT NULL, -- TEXT for serialized accounts configuration
+    infra_base_url TEXT, -- Optional base URL
+    contracts_addresses TEXT, -- Optional contract addresses
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (deployment_id, revision)
+);
+
+-- Existing deployments become their first revision
+INSERT INTO deployment_revisions (deployment_id, revision, name, release_tag, release_registry, network_config, accounts_config, infra_base_url, contracts_addresses)
+SELECT id, 1, name, release_tag, release_registry, network_config, accounts_config, infra_base_url, contracts_addresses FROM deployments;
+
+ALTER TABLE deployments ADD COLUMN head_revision INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE deployments
+    DROP COLUMN name,
+    DROP COLUMN release_tag,
+    DROP COLUMN release_registry,
+    DROP COLUMN network_config,
+    DROP COLUMN accounts_config,
+    DROP COLUMN infra_base_url,
+    DROP COLUMN contracts_addresses;
+
+CREATE FUNCTION deployment_revisions_immutable() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'deployment_revisions are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER deployment_revisions_immutable
+    BEFORE UPDATE ON deployment_revisions
+    FOR EACH ROW EXECUTE FUNCTION deployment_revisions_immutable();
@@ -0,0 +1,2 @@
+-- The head revision number doubles as the deployment version used for optimistic concurrency
+ALTER TABLE deployments RENAME COLUMN head_revision TO version;
@@ -0,0 +1,4 @@
+-- Checksum of the last uploaded artifact, computed while streaming it to storage
+ALTER TABLE deployments
+    ADD COLUMN artifact_sha256 TEXT,
+    ADD COLUMN artifact_size BIGINT;
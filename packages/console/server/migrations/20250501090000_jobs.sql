@@ -0,0 +1,28 @@
+-- Deployments performed by the console workers
+CREATE TABLE jobs (
+    id TEXT NOT NULL,
+    deployment_id TEXT NOT NULL, -- Target deployment, kept after the deployment is deleted
+    spec JSONB NOT NULL, -- What to deploy and how, secrets are never stored
+    status TEXT NOT NULL, -- queued, running, succeeded, failed or cancelled
+    error TEXT, -- Failure reason, set once failed
+    cancel_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    created_by TEXT NOT NULL, -- User who requested the job
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX jobs_deployment_id_idx ON jobs (deployment_id, created_at DESC);
+
+CREATE TABLE job_logs (
+    id BIGSERIAL NOT NULL, -- Monotonic identifier, used as the event id of the log stream
+    job_id TEXT NOT NULL REFERENCES jobs (id) ON DELETE CASCADE,
+    line TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX job_logs_job_id_idx ON job_logs (job_id, id);
@@ -0,0 +1,34 @@
+-- Org subscriptions to console events
+CREATE TABLE webhooks (
+    id TEXT NOT NULL,
+    org_id TEXT NOT NULL REFERENCES orgs (id) ON DELETE CASCADE,
+    url TEXT NOT NULL, -- Receives a POST per event
+    secret TEXT NOT NULL, -- Signs deliveries, kept as is as it is needed to compute signatures
+    events TEXT NOT NULL, -- Json array of subscribed events, empty for every event
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX webhooks_org_id_idx ON webhooks (org_id);
+
+-- Queue of events to deliver, kept once delivered as the delivery log
+CREATE TABLE webhook_deliveries (
+    id TEXT NOT NULL, -- Sent on every attempt so receivers can drop duplicates
+    webhook_id TEXT NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+    event TEXT NOT NULL, -- e.g. deployment.created
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL, -- pending, succeeded or failed
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- Also pushed forward while an attempt is in flight
+    response_status INTEGER, -- Status code of the last attempt, none if the request failed
+    error TEXT, -- Failure of the last attempt
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX webhook_deliveries_webhook_id_idx ON webhook_deliveries (webhook_id, created_at DESC);
+CREATE INDEX webhook_deliveries_due_idx ON webhook_deliveries (status, next_attempt_at);
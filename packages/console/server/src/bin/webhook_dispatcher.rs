@@ -0,0 +1,39 @@
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use opraas_server::{
+    infrastructure::{database::DbPool, domain::webhook::SqlWebhookRepository},
+    webhooks::{RetryPolicy, TargetPolicy, WebhookDispatcher},
+};
+use serde_json::Value;
+use std::{env, sync::Arc};
+use tracing::level_filters::LevelFilter;
+
+/// Sends the due webhook deliveries on every invocation.
+/// The api can't dispatch them in the background while it runs as a lambda, so this one is scheduled next to it
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::INFO)
+        .init();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let db_pool = DbPool::connect(&database_url)
+        .await
+        .expect("Unable to connect to the database");
+
+    let dispatcher = Arc::new(
+        WebhookDispatcher::new(
+            Arc::new(SqlWebhookRepository::new(db_pool)),
+            RetryPolicy::default(),
+        )
+        .with_targets(TargetPolicy::from_env()),
+    );
+
+    run(service_fn(move |_: LambdaEvent<Value>| {
+        let dispatcher = dispatcher.clone();
+        async move {
+            let attempted = dispatcher.dispatch_due().await.map_err(|e| e.to_string())?;
+            Ok::<_, Error>(serde_json::json!({ "attempted": attempted }))
+        }
+    }))
+    .await
+}
@@ -0,0 +1,95 @@
+use opraas_core::infrastructure::deployment::TerraformBackend;
+use std::{env, path::PathBuf};
+
+const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:4000";
+const DEFAULT_ARTIFACTS_DIR: &str = "./artifacts";
+const DEFAULT_JOB_WORKERS: usize = 1;
+const DEFAULT_TERRAFORM_STATE_DIR: &str = "./terraform-state";
+
+/// Server settings, read once from the environment at startup
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// In prod the server runs as a lambda function, otherwise it listens on `listen_addr`
+    pub prod: bool,
+    pub listen_addr: String,
+    /// `postgres://` or `sqlite://`, the scheme picks the database backend
+    pub database_url: String,
+    pub storage: StorageConfig,
+    /// Deployment jobs run at the same time, 0 disables jobs.
+    /// Always 0 in prod, jobs are only supported on self hosted consoles as a lambda stops once it responds
+    pub job_workers: usize,
+    /// Where infra jobs keep the terraform state, as their projects are temporary
+    pub terraform_backend: TerraformBackend,
+}
+
+/// Where deployment artifacts are kept, set with `ARTIFACTS_STORAGE`
+#[derive(Clone, Debug)]
+pub enum StorageConfig {
+    S3 { region: String, bucket: String },
+    Fs { root: PathBuf },
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let prod = env::var("ENV").unwrap_or_else(|_| "dev".into()) == "prod";
+        let job_workers = match env::var("JOB_WORKERS") {
+            Ok(_) if prod => {
+                return Err("JOB_WORKERS can't be set with ENV=prod, jobs need a self hosted console".into())
+            }
+            Err(_) if prod => 0,
+            Ok(workers) => workers
+                .parse()
+                .map_err(|_| format!("Invalid JOB_WORKERS \"{}\"", workers))?,
+            Err(_) => DEFAULT_JOB_WORKERS,
+        };
+
+        Ok(Self {
+            prod,
+            listen_addr: env::var("LISTEN_ADDR").unwrap_or_else(|_| DEFAULT_LISTEN_ADDR.into()),
+            database_url: env::var("DATABASE_URL").map_err(|_| "DATABASE_URL not set")?,
+            storage: StorageConfig::from_env()?,
+            job_workers,
+            terraform_backend: terraform_backend_from_env()?,
+        })
+    }
+}
+
+/// `TERRAFORM_STATE_BUCKET` keeps the state in s3, otherwise it goes to `TERRAFORM_STATE_DIR`
+fn terraform_backend_from_env() -> Result<TerraformBackend, String> {
+    if let Ok(bucket) = env::var("TERRAFORM_STATE_BUCKET") {
+        return Ok(TerraformBackend::S3 {
+            bucket,
+            region: env::var("AWS_REGION").map_err(|_| "AWS_REGION not set")?,
+            lock_table: env::var("TERRAFORM_LOCK_TABLE").ok(),
+        });
+    }
+
+    // terraform runs from the project directory, relative paths would point inside it
+    let dir = env::var("TERRAFORM_STATE_DIR").unwrap_or_else(|_| DEFAULT_TERRAFORM_STATE_DIR.into());
+    let dir = env::current_dir()
+        .map_err(|e| format!("Could not resolve TERRAFORM_STATE_DIR. {}", e))?
+        .join(dir);
+    Ok(TerraformBackend::Local { dir })
+}
+
+impl StorageConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let storage = env::var("ARTIFACTS_STORAGE").unwrap_or_else(|_| "s3".into());
+
+        match storage.as_str() {
+            "s3" => Ok(Self::S3 {
+                region: env::var("AWS_REGION").map_err(|_| "AWS_REGION not set")?,
+                bucket: env::var("ARTIFACTS_BUCKET").map_err(|_| "ARTIFACTS_BUCKET not set")?,
+            }),
+            "fs" => Ok(Self::Fs {
+                root: env::var("ARTIFACTS_DIR")
+                    .unwrap_or_else(|_| DEFAULT_ARTIFACTS_DIR.into())
+                    .into(),
+            }),
+            other => Err(format!(
+                "Unknown ARTIFACTS_STORAGE \"{}\", expected s3 or fs",
+                other
+            )),
+        }
+    }
+}
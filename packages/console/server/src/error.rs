@@ -2,6 +2,10 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use opraas_core::{
+    config::ConfigError,
+    domain::{DeploymentError, ProjectError},
+};
 use thiserror::Error as ThisError;
 
 #[derive(Clone, Debug, ThisError)]
@@ -12,6 +16,30 @@ pub enum ApiError {
     #[error("Authorization error. {0}")]
     AuthError(String),
 
+    #[error("Forbidden. {0}")]
+    Forbidden(String),
+
+    #[error("Not found. {0}")]
+    NotFound(String),
+
+    #[error("Conflict. {0}")]
+    Conflict(String),
+
+    #[error("Precondition failed. {0}")]
+    PreconditionFailed(String),
+
+    #[error("Precondition required. {0}")]
+    PreconditionRequired(String),
+
+    #[error("Payload too large. {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unprocessable entity. {0}")]
+    UnprocessableEntity(String),
+
+    #[error("Not implemented. {0}")]
+    NotImplemented(String),
+
     #[error("Internal Server Error {0}")]
     InternalServerError(String),
 }
@@ -21,6 +49,14 @@ impl IntoResponse for ApiError {
         let response = match self {
             Self::BadRequest(e) => (StatusCode::BAD_REQUEST, e.to_string()),
             Self::AuthError(e) => (StatusCode::UNAUTHORIZED, e.to_string()),
+            Self::Forbidden(e) => (StatusCode::FORBIDDEN, e.to_string()),
+            Self::NotFound(e) => (StatusCode::NOT_FOUND, e.to_string()),
+            Self::Conflict(e) => (StatusCode::CONFLICT, e.to_string()),
+            Self::PreconditionFailed(e) => (StatusCode::PRECONDITION_FAILED, e.to_string()),
+            Self::PreconditionRequired(e) => (StatusCode::PRECONDITION_REQUIRED, e.to_string()),
+            Self::PayloadTooLarge(e) => (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()),
+            Self::UnprocessableEntity(e) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+            Self::NotImplemented(e) => (StatusCode::NOT_IMPLEMENTED, e.to_string()),
             e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
         };
 
@@ -34,6 +70,41 @@ impl From<Box<dyn std::error::Error>> for ApiError {
     }
 }
 
+impl From<DeploymentError> for ApiError {
+    fn from(err: DeploymentError) -> Self {
+        match err {
+            DeploymentError::NotFound(_) => ApiError::NotFound(err.to_string()),
+            DeploymentError::Conflict(_) => ApiError::Conflict(err.to_string()),
+            DeploymentError::Invalid(err) => err.into(),
+            DeploymentError::Missing(_) | DeploymentError::ContractsNotDeployed => {
+                ApiError::UnprocessableEntity(err.to_string())
+            }
+            DeploymentError::Project(err) => err.into(),
+            err => ApiError::InternalServerError(err.to_string()),
+        }
+    }
+}
+
+impl From<ConfigError> for ApiError {
+    fn from(err: ConfigError) -> Self {
+        match err {
+            ConfigError::Invalid { .. } | ConfigError::Parse(_) => ApiError::UnprocessableEntity(err.to_string()),
+            err => ApiError::InternalServerError(err.to_string()),
+        }
+    }
+}
+
+impl From<ProjectError> for ApiError {
+    fn from(err: ProjectError) -> Self {
+        match err {
+            ProjectError::NotFound(_) => ApiError::NotFound(err.to_string()),
+            ProjectError::OutsideRoot(_) => ApiError::UnprocessableEntity(err.to_string()),
+            ProjectError::Config(err) => err.into(),
+            err => ApiError::InternalServerError(err.to_string()),
+        }
+    }
+}
+
 // impl From<SqlxError> for Error {
 //     fn from(sqlx_error: SqlxError) -> Self {
 //         match sqlx_error.as_database_error() {
@@ -0,0 +1,94 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::{
+        audit::{AuditEvent, SqlAuditRepository},
+        deployment::{ArtifactStorage, SqlDeploymentRepository},
+    },
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission},
+    },
+};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use opraas_core::application::deployment::manager::DeploymentManagerService;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// Cursor returned as `next_before` by the previous page
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditPage {
+    pub events: Vec<AuditEvent>,
+    /// Cursor for the next page, none once all events were returned
+    pub next_before: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/audit",
+    tag = "audit",
+    params(("id" = String, Path, description = "Deployment id"), AuditQuery),
+    responses(
+        (status = 200, description = "Audit events, newest first", body = AuditPage),
+        (status = 404, description = "Deployment not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list(
+    Path(id): Path<String>,
+    Query(query): Query<AuditQuery>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(audit_repo): Extension<Arc<SqlAuditRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    org_authorizer
+        .authorize_deployment(&user, &deployment, Permission::ReadDeployments)
+        .await?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let events = audit_repo
+        .find_by_deployment(&deployment.id, query.before, limit)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list audit events".into()))?;
+
+    let next_before = if events.len() as i64 == limit {
+        events.last().map(|e| e.id)
+    } else {
+        None
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(AuditPage {
+            events,
+            next_before,
+        }),
+    ))
+}
@@ -0,0 +1,139 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::auth::SqlAuthRepository,
+    middlewares::auth::{hash_token, AuthConfig},
+};
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use hex::FromHex;
+use serde::{Deserialize, Serialize};
+use siwe::{Message, VerificationOpts};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Tolerated drift between client and server clocks
+const CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NonceResponse {
+    pub nonce: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyPayload {
+    pub message: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VerifyResponse {
+    pub token: String,
+    pub user_id: String,
+    pub expires_at: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/nonce",
+    tag = "auth",
+    responses((status = 200, description = "Single use nonce to include in the SIWE message", body = NonceResponse))
+)]
+pub async fn nonce(
+    Extension(auth_repo): Extension<Arc<SqlAuthRepository>>,
+    Extension(auth_config): Extension<Arc<AuthConfig>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let nonce = siwe::generate_nonce();
+
+    auth_repo
+        .create_nonce(&nonce, auth_config.nonce_ttl)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok((StatusCode::OK, Json(NonceResponse { nonce })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify",
+    tag = "auth",
+    request_body = VerifyPayload,
+    responses(
+        (status = 200, description = "Session for the signing address", body = VerifyResponse),
+        (status = 400, description = "Message or signature could not be parsed"),
+        (status = 401, description = "Message was rejected"),
+    )
+)]
+pub async fn verify(
+    Extension(auth_repo): Extension<Arc<SqlAuthRepository>>,
+    Extension(auth_config): Extension<Arc<AuthConfig>>,
+    Json(payload): Json<VerifyPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let message =
+        Message::from_str(&payload.message).map_err(|_| ApiError::BadRequest("Could not parse siwe message".into()))?;
+    let signature = <[u8; 65]>::from_hex(payload.signature.trim_start_matches("0x"))
+        .map_err(|_| ApiError::BadRequest("Invalid signature".into()))?;
+    let now = OffsetDateTime::now_utc();
+
+    // message must be bound to this console
+    let domain = message.domain.to_string();
+    if !auth_config.domains.contains(&domain) {
+        return Err(ApiError::AuthError("Domain is not allowed".into()));
+    }
+    if message.uri.authority_str() != Some(domain.as_str()) {
+        return Err(ApiError::AuthError("URI does not match domain".into()));
+    }
+
+    // and recent, nonces are short lived anyway
+    let issued_at = *message.issued_at.as_ref();
+    if issued_at > now + CLOCK_SKEW || issued_at < now - auth_config.nonce_ttl - CLOCK_SKEW {
+        return Err(ApiError::AuthError(
+            "Message issued at is out of range".into(),
+        ));
+    }
+
+    // checks signature, domain, expiration and not before
+    message
+        .verify(
+            &signature,
+            &VerificationOpts {
+                domain: Some(message.domain.clone()),
+                timestamp: Some(now),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| ApiError::AuthError(e.to_string()))?;
+
+    // nonces are single use, consumed only once the signature is known to be valid
+    if !auth_repo
+        .consume_nonce(&message.nonce, auth_config.nonce_ttl)
+        .await
+        .map_err(ApiError::from)?
+    {
+        return Err(ApiError::AuthError(
+            "Nonce is invalid, expired or already used".into(),
+        ));
+    }
+
+    // session never outlives the signed message
+    let mut expires_at = now + auth_config.session_ttl;
+    if let Some(expiration_time) = &message.expiration_time {
+        expires_at = expires_at.min(*expiration_time.as_ref());
+    }
+
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    let user_id = hex::encode(message.address);
+    auth_repo
+        .create_session(&hash_token(&token), &user_id, expires_at.unix_timestamp())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(VerifyResponse {
+            token,
+            user_id,
+            expires_at: expires_at.unix_timestamp(),
+        }),
+    ))
+}
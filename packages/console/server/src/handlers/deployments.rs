@@ -1,138 +1,426 @@
 use crate::{
     error::ApiError,
-    infrastructure::domain::deployment::{S3DeploymentArtifactsRepository, SqlDeploymentRepository},
-    middlewares::auth::AuthCurrentUser,
+    infrastructure::domain::{
+        deployment::{ArtifactStorage, SqlDeploymentRepository},
+        org::SqlOrgRepository,
+    },
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission},
+        write_context::WriteContext,
+    },
+    utils::{audit, merge_patch::merge_patch},
+};
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
 };
-use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
 use opraas_core::{application::deployment::manager::DeploymentManagerService, domain::Deployment};
+use serde::Deserialize;
 use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OrgQuery {
+    /// Org to operate on, defaults to the user personal org on create and to all the user orgs on list
+    pub org_id: Option<String>,
+}
+
+fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Version expected by the client, none when any version is accepted with `If-Match: *`
+fn if_match_version(headers: &HeaderMap) -> Result<Option<i32>, ApiError> {
+    let if_match = headers
+        .get(header::IF_MATCH)
+        .ok_or(ApiError::PreconditionRequired(
+            "If-Match header with the deployment ETag is required".into(),
+        ))?
+        .to_str()
+        .map_err(|_| ApiError::BadRequest("Invalid If-Match header".into()))?
+        .trim();
+
+    if if_match == "*" {
+        return Ok(None);
+    }
 
+    if_match
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse::<i32>()
+        .map(Some)
+        .map_err(|_| ApiError::PreconditionFailed("ETag does not match any deployment version".into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/deployments",
+    tag = "deployments",
+    params(OrgQuery),
+    request_body(content = Deployment, description = "An empty id is replaced by a generated one"),
+    responses(
+        (status = 200, description = "Created deployment", body = Deployment,
+            headers(("etag" = String, description = "First revision, to be sent back as If-Match"))),
+        (status = 409, description = "A deployment with given id already exists, in any org"),
+        (status = 422, description = "Invalid deployment id"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn create(
-    Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    Query(query): Query<OrgQuery>,
+    ctx: WriteContext,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
     Json(mut deployment): Json<Deployment>,
 ) -> Result<impl IntoResponse, ApiError> {
-    deployment.id = uuid::Uuid::new_v4().to_string();
-    deployment.owner_id = user.id.clone();
+    let org_id = match query.org_id {
+        Some(org_id) => org_id,
+        None => org_repo
+            .ensure_personal(&ctx.user.id)
+            .await
+            .map_err(ApiError::from)?,
+    };
+    ctx.authorize(&org_id, Permission::WriteDeployments).await?;
 
-    deployments_manager
-        .save(&deployment)
+    // keep ids chosen by clients such as the cli so deployments can be synced
+    if deployment.id.trim().is_empty() {
+        deployment.id = uuid::Uuid::new_v4().to_string();
+    }
+    Deployment::validate_id(&deployment.id)?;
+    deployment.owner_id = org_id;
+
+    let version = deployments_repo
+        .create(&deployment)
         .await
         .map_err(ApiError::from)?;
 
+    ctx.record(
+        "deployment.created",
+        &deployment,
+        &audit::diff(
+            &serde_json::Value::Null,
+            &serde_json::to_value(&deployment).unwrap_or_default(),
+        ),
+    )
+    .await?;
+
     let deployment_json = serde_json::to_string(&deployment)
         .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
-    Ok((StatusCode::OK, deployment_json))
+    Ok((
+        StatusCode::OK,
+        [(header::ETAG, etag(version))],
+        deployment_json,
+    ))
 }
 
+#[utoipa::path(
+    put,
+    path = "/deployments/{id}",
+    tag = "deployments",
+    params(
+        ("id" = String, Path, description = "Deployment id"),
+        ("if-match" = String, Header, description = "ETag of the revision being replaced, or * for any"),
+    ),
+    request_body = Deployment,
+    responses(
+        (
+            status = 200,
+            description = "Updated deployment",
+            body = Deployment,
+            headers(("etag" = String, description = "New revision"))
+        ),
+        (status = 404, description = "Deployment not found"),
+        (status = 412, description = "Deployment was modified since the given ETag"),
+        (status = 422, description = "Updated deployment is invalid"),
+        (status = 428, description = "If-Match header is missing"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn update(
+    headers: HeaderMap,
     Path(id): Path<String>,
-    Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    ctx: WriteContext,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
     Json(deployment_update): Json<Deployment>, // Receive the updated deployment as JSON
 ) -> Result<impl IntoResponse, ApiError> {
-    let mut deployment = deployments_manager
-        .find_by_id(&id)
+    let expected_version = if_match_version(&headers)?;
+
+    let head = deployments_repo
+        .find_head(&id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    // Ensure the current user is the owner of the deployment
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "You are not the owner of this deployment".into(),
+    ctx.authorize_deployment(&head.deployment, Permission::WriteDeployments)
+        .await?;
+
+    if expected_version.is_some_and(|version| version != head.revision) {
+        return Err(ApiError::PreconditionFailed(
+            "Deployment was modified, fetch it again and retry".into(),
         ));
     }
 
+    let before = serde_json::to_value(&head.deployment).unwrap_or_default();
+
     // Update the fields with the new data
+    let mut deployment = head.deployment;
     deployment.name = deployment_update.name;
     deployment.accounts_config = deployment_update.accounts_config;
     deployment.contracts_addresses = deployment_update.contracts_addresses;
     deployment.infra_base_url = deployment_update.infra_base_url;
+    deployment.infra_outputs = deployment_update.infra_outputs;
     deployment.network_config = deployment_update.network_config;
     deployment.release_registry = deployment_update.release_registry;
     deployment.release_tag = deployment_update.release_tag;
+    deployment.validate()?;
 
-    // Save the updated deployment
-    deployments_manager
-        .save(&deployment)
+    // a concurrent save between reading and writing the head also fails the precondition
+    let version = deployments_repo
+        .save_if_version(&deployment, head.revision)
         .await
-        .map_err(ApiError::from)?;
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::PreconditionFailed(
+            "Deployment was modified, fetch it again and retry".into(),
+        ))?;
+
+    ctx.record(
+        "deployment.updated",
+        &deployment,
+        &audit::diff(
+            &before,
+            &serde_json::to_value(&deployment).unwrap_or_default(),
+        ),
+    )
+    .await?;
 
     let deployment_json = serde_json::to_string(&deployment)
         .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
-    Ok((StatusCode::OK, deployment_json))
+    Ok((
+        StatusCode::OK,
+        [(header::ETAG, etag(version))],
+        deployment_json,
+    ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/deployments",
+    tag = "deployments",
+    params(OrgQuery),
+    responses((status = 200, description = "Deployments in the user orgs", body = Vec<Deployment>)),
+    security(("bearer" = []))
+)]
 pub async fn list(
+    Query(query): Query<OrgQuery>,
     Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let deployments = deployments_manager
-        .find_by_owner(&user.id)
-        .await
-        .map_err(|_| ApiError::InternalServerError("Could not list deployments".into()))?;
+    let org_ids = match query.org_id {
+        Some(org_id) => vec![org_id],
+        None => {
+            org_repo
+                .ensure_personal(&user.id)
+                .await
+                .map_err(ApiError::from)?;
+            org_repo
+                .find_by_member(&user.id)
+                .await
+                .map_err(ApiError::from)?
+                .into_iter()
+                .map(|org| org.id)
+                .collect()
+        }
+    };
+
+    let mut deployments = Vec::new();
+    for org_id in org_ids {
+        org_authorizer
+            .authorize(&user, &org_id, Permission::ReadDeployments)
+            .await?;
+
+        deployments.extend(
+            deployments_manager
+                .find_by_owner(&org_id)
+                .await
+                .map_err(|_| ApiError::InternalServerError("Could not list deployments".into()))?,
+        );
+    }
 
     let deployments_json = serde_json::to_string(&deployments)
         .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
     Ok((StatusCode::OK, deployments_json))
 }
 
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}",
+    tag = "deployments",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (
+            status = 200,
+            description = "Deployment",
+            body = Deployment,
+            headers(("etag" = String, description = "Current revision, to be sent back as If-Match"))
+        ),
+        (status = 404, description = "Deployment not found"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn get_by_id(
     Path(id): Path<String>,
     Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let deployment = deployments_manager
-        .find_by_id(&id)
+    let head = deployments_repo
+        .find_head(&id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    // verify deployment is owned by user
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "Deployment is not owned by user".into(),
+    org_authorizer
+        .authorize_deployment(&user, &head.deployment, Permission::ReadDeployments)
+        .await?;
+
+    let deployment_json = serde_json::to_string(&head.deployment)
+        .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
+    Ok((
+        StatusCode::OK,
+        [(header::ETAG, etag(head.revision))],
+        deployment_json,
+    ))
+}
+
+/// Applies a JSON Merge Patch to the deployment, guarded by `If-Match`
+#[utoipa::path(
+    patch,
+    path = "/deployments/{id}",
+    tag = "deployments",
+    params(
+        ("id" = String, Path, description = "Deployment id"),
+        ("if-match" = String, Header, description = "ETag of the revision being patched, or * for any"),
+    ),
+    request_body(content = serde_json::Value, description = "JSON Merge Patch, id and owner_id cannot be patched"),
+    responses(
+        (
+            status = 200,
+            description = "Patched deployment",
+            body = Deployment,
+            headers(("etag" = String, description = "New revision"))
+        ),
+        (status = 400, description = "Patch touches id or owner_id"),
+        (status = 404, description = "Deployment not found"),
+        (status = 412, description = "Deployment was modified since the given ETag"),
+        (status = 422, description = "Patched deployment is invalid"),
+        (status = 428, description = "If-Match header is missing"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn patch(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ctx: WriteContext,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, ApiError> {
+    let expected_version = if_match_version(&headers)?;
+
+    let head = deployments_repo
+        .find_head(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    ctx.authorize_deployment(&head.deployment, Permission::WriteDeployments)
+        .await?;
+
+    if expected_version.is_some_and(|version| version != head.revision) {
+        return Err(ApiError::PreconditionFailed(
+            "Deployment was modified, fetch it again and retry".into(),
         ));
     }
+    if patch.get("id").is_some() || patch.get("owner_id").is_some() {
+        return Err(ApiError::BadRequest(
+            "id and owner_id cannot be patched".into(),
+        ));
+    }
+
+    let before = serde_json::to_value(&head.deployment)
+        .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
+    let mut patched = before.clone();
+    merge_patch(&mut patched, &patch);
+
+    let deployment: Deployment =
+        serde_json::from_value(patched).map_err(|e| ApiError::UnprocessableEntity(e.to_string()))?;
+    deployment.validate()?;
+
+    // a concurrent save between reading and writing the head also fails the precondition
+    let version = deployments_repo
+        .save_if_version(&deployment, head.revision)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::PreconditionFailed(
+            "Deployment was modified, fetch it again and retry".into(),
+        ))?;
+
+    ctx.record(
+        "deployment.updated",
+        &deployment,
+        &audit::diff(
+            &before,
+            &serde_json::to_value(&deployment).unwrap_or_default(),
+        ),
+    )
+    .await?;
 
     let deployment_json = serde_json::to_string(&deployment)
         .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
-    Ok((StatusCode::OK, deployment_json))
+    Ok((
+        StatusCode::OK,
+        [(header::ETAG, etag(version))],
+        deployment_json,
+    ))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/deployments/{id}",
+    tag = "deployments",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (status = 200, description = "Deleted deployment", body = Deployment),
+        (status = 404, description = "Deployment not found"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn delete(
     Path(id): Path<String>,
-    Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    ctx: WriteContext,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
 ) -> Result<impl IntoResponse, ApiError> {
     let deployment = deployments_manager
         .find_by_id(&id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "Deployment is not owned by user".into(),
-        ));
-    }
+    ctx.authorize_deployment(&deployment, Permission::WriteDeployments)
+        .await?;
 
     let _ = deployments_manager.delete_artifact(&deployment).await;
 
@@ -141,6 +429,16 @@ pub async fn delete(
         .await
         .map_err(ApiError::from)?;
 
+    ctx.record(
+        "deployment.deleted",
+        &deployment,
+        &audit::diff(
+            &serde_json::to_value(&deployment).unwrap_or_default(),
+            &serde_json::Value::Null,
+        ),
+    )
+    .await?;
+
     let deployment_json = serde_json::to_string(&deployment)
         .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
     Ok((StatusCode::OK, deployment_json))
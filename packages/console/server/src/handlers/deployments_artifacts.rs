@@ -1,159 +1,501 @@
 use crate::{
     error::ApiError,
-    infrastructure::domain::deployment::{S3DeploymentArtifactsRepository, SqlDeploymentRepository},
-    middlewares::auth::AuthCurrentUser,
+    infrastructure::domain::{
+        deployment::{ArtifactChecksum, ArtifactStorage, PresignedUrl, SqlDeploymentRepository},
+    },
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission},
+        write_context::WriteContext,
+    },
+    openapi::{ArtifactUpload, ZipFile},
+    utils::{audit, zip},
 };
 use axum::{
-    extract::{Multipart, Path},
-    http::{header, StatusCode},
+    extract::{Multipart, Path, Query},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
-    Extension,
+    Extension, Json,
 };
 use opraas_core::application::deployment::manager::DeploymentManagerService;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use utoipa::{IntoParams, ToSchema};
 
+/// Sha256 of the stored artifact, hex encoded
+pub const CHECKSUM_HEADER: &str = "x-checksum-sha256";
+const PRESIGNED_URL_TTL: Duration = Duration::from_secs(15 * 60);
+/// Largest artifact accepted through presigned uploads, same as the api request body limit
+pub const MAX_ARTIFACT_SIZE: u64 = 250 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UploadUrlQuery {
+    /// Size in bytes of the artifact to upload, the url only accepts a body of exactly this size
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PresignedDownload {
+    #[serde(flatten)]
+    pub presigned: PresignedUrl,
+    /// Expected checksum of the download, none for artifacts uploaded before checksums were recorded
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/deployments/{id}/artifact",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id")),
+    request_body(content = ArtifactUpload, content_type = "multipart/form-data"),
+    responses(
+        (
+            status = 200,
+            description = "Artifact stored",
+            body = String,
+            content_type = "text/plain",
+            headers(("x-checksum-sha256" = String, description = "Sha256 of the stored artifact, hex encoded"))
+        ),
+        (status = 404, description = "Deployment not found"),
+        (status = 422, description = "Artifact is not a zip or misses required files"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn create(
     Path(deployment_id): Path<String>,
-    Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    ctx: WriteContext,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(artifacts_repo): Extension<Arc<ArtifactStorage>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse, ApiError> {
-    let mut field: Option<Vec<u8>> = None;
-    while let Some(formitem) = multipart.next_field().await.unwrap() {
-        field = Some(
-            formitem
-                .bytes()
-                .await
-                .map_err(|_| ApiError::BadRequest("Could not read deployment file".into()))?
-                .to_vec(),
-        );
-    }
-
-    let Some(deployment_artifact) = field else {
-        return Err(ApiError::BadRequest("No deployment file provided".into()));
-    };
-
     let deployment = deployments_manager
         .find_by_id(&deployment_id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    // verify deployment is owned by user
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "Deployment is not owned by user".into(),
-        ));
+    ctx.authorize_deployment(&deployment, Permission::WriteDeployments)
+        .await?;
+
+    let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| ApiError::BadRequest("Could not read deployment file".into()))?
+    else {
+        return Err(ApiError::BadRequest("No deployment file provided".into()));
+    };
+
+    // stream parts to storage as they arrive, nothing is published until the upload is completed
+    let mut upload = artifacts_repo
+        .start_upload(&deployment)
+        .await
+        .map_err(ApiError::from)?;
+    let streamed = async {
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|_| ApiError::BadRequest("Could not read deployment file".into()))?
+        {
+            upload.write(&chunk).await.map_err(ApiError::from)?;
+        }
+
+        let spooled = upload.spooled().map_err(ApiError::from)?;
+        zip::verify_zip(spooled, &zip::ARTIFACT_REQUIRED_FILES)
+            .map_err(|e| ApiError::UnprocessableEntity(format!("Invalid deployment artifact: {}", e)))
+    }
+    .await;
+    if let Err(e) = streamed {
+        upload.abort().await.map_err(ApiError::from)?;
+        return Err(e);
     }
 
-    deployments_manager
-        .save_artifact(&deployment, deployment_artifact)
+    // a failed completion aborts the upload itself, no parts are left behind
+    let checksum = upload.complete().await.map_err(ApiError::from)?;
+    deployments_repo
+        .save_artifact_checksum(&deployment.id, &checksum)
         .await
         .map_err(ApiError::from)?;
 
-    Ok((StatusCode::OK, "Ok"))
+    ctx.record(
+        "artifact.uploaded",
+        &deployment,
+        &audit::diff(
+            &serde_json::Value::Null,
+            &serde_json::json!({ "artifact": { "sha256": checksum.sha256, "size": checksum.size } }),
+        ),
+    )
+    .await?;
+
+    Ok((StatusCode::OK, [(CHECKSUM_HEADER, checksum.sha256)], "Ok"))
 }
 
+#[utoipa::path(
+    head,
+    path = "/deployments/{id}/artifact",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (
+            status = 200,
+            description = "Artifact exists",
+            headers(("x-checksum-sha256" = String, description = "Sha256 of the stored artifact, hex encoded"))
+        ),
+        (status = 404, description = "Deployment or artifact not found"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn head(
     Path(id): Path<String>,
     Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
 ) -> Result<impl IntoResponse, ApiError> {
     let deployment = deployments_manager
         .find_by_id(&id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "Deployment is not owned by user".into(),
-        ));
-    }
+    org_authorizer
+        .authorize_deployment(&user, &deployment, Permission::ReadDeployments)
+        .await?;
 
     let exists = deployments_manager
         .exists_artifact(&deployment)
         .await
         .map_err(ApiError::from)?;
 
-    if exists {
-        Ok((StatusCode::OK, [("X-Resource-Exist", "true")]))
-    } else {
-        Ok((StatusCode::NOT_FOUND, [("X-Resource-Exist", "false")]))
+    if !exists {
+        return Ok((StatusCode::NOT_FOUND, [("X-Resource-Exist", "false")]).into_response());
     }
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-resource-exist", HeaderValue::from_static("true"));
+    if let Some(checksum) = find_checksum(&deployments_repo, &deployment.id).await? {
+        headers.insert(CHECKSUM_HEADER, checksum);
+    }
+
+    Ok((StatusCode::OK, headers).into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/artifact",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (
+            status = 200,
+            description = "Artifact zip",
+            body = ZipFile,
+            content_type = "application/zip",
+            headers(("x-checksum-sha256" = String, description = "Sha256 of the artifact, hex encoded"))
+        ),
+        (status = 404, description = "Deployment or artifact not found"),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn get_by_id(
     Path(id): Path<String>,
     Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
 ) -> Result<impl IntoResponse, ApiError> {
     let deployment = deployments_manager
         .find_by_id(&id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    // verify deployment is owned by user
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "Deployment is not owned by user".into(),
-        ));
-    }
+    org_authorizer
+        .authorize_deployment(&user, &deployment, Permission::ReadDeployments)
+        .await?;
 
     let res = deployments_manager
         .find_artifact(&deployment)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
+            "Could not find artifact for given deployment".into(),
+        ))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/zip"),
+    );
+    if let Some(checksum) = find_checksum(&deployments_repo, &deployment.id).await? {
+        headers.insert(CHECKSUM_HEADER, checksum);
+    }
+
+    Ok((StatusCode::OK, headers, res))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/deployments/{id}/artifact",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (status = 200, description = "Artifact deleted", body = String, content_type = "text/plain"),
+        (status = 404, description = "Deployment not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete(
+    Path(id): Path<String>,
+    ctx: WriteContext,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    ctx.authorize_deployment(&deployment, Permission::WriteDeployments)
+        .await?;
+
+    deployments_manager
+        .delete_artifact(&deployment)
+        .await
+        .map_err(ApiError::from)?;
+    deployments_repo
+        .delete_artifact_checksum(&deployment.id)
+        .await
+        .map_err(ApiError::from)?;
+
+    ctx.record("artifact.deleted", &deployment, &serde_json::json!({}))
+        .await?;
+
+    Ok((StatusCode::OK, "Ok"))
+}
+
+/// Artifacts uploaded before checksums were recorded have none
+async fn find_checksum(
+    deployments_repo: &SqlDeploymentRepository,
+    deployment_id: &str,
+) -> Result<Option<HeaderValue>, ApiError> {
+    let checksum = deployments_repo
+        .find_artifact_checksum(deployment_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(checksum.and_then(|c| HeaderValue::from_str(&c.sha256).ok()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/deployments/{id}/artifact/upload-url",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id"), UploadUrlQuery),
+    responses(
+        (
+            status = 200,
+            description = "Url to upload the artifact to, call complete once done to verify and publish it",
+            body = PresignedUrl
+        ),
+        (status = 404, description = "Deployment not found"),
+        (status = 413, description = "Artifact is larger than the console accepts"),
+        (status = 501, description = "Artifact storage does not support presigned urls"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn upload_url(
+    Path(id): Path<String>,
+    Query(query): Query<UploadUrlQuery>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(artifacts_repo): Extension<Arc<ArtifactStorage>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    org_authorizer
+        .authorize_deployment(&user, &deployment, Permission::WriteDeployments)
+        .await?;
+
+    if query.size > MAX_ARTIFACT_SIZE {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Artifacts can be at most {} bytes",
+            MAX_ARTIFACT_SIZE
+        )));
+    }
+
+    let presigned = artifacts_repo
+        .presign_upload(&deployment, query.size, PRESIGNED_URL_TTL)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotImplemented(
+            "Artifact storage does not support presigned urls".into(),
+        ))?;
+
+    Ok((StatusCode::OK, Json(presigned)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/artifact/download-url",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (status = 200, description = "Url to download the artifact from", body = PresignedDownload),
+        (status = 404, description = "Deployment or artifact not found"),
+        (status = 501, description = "Artifact storage does not support presigned urls"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn download_url(
+    Path(id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(artifacts_repo): Extension<Arc<ArtifactStorage>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
+    org_authorizer
+        .authorize_deployment(&user, &deployment, Permission::ReadDeployments)
+        .await?;
+
+    let exists = deployments_manager
+        .exists_artifact(&deployment)
+        .await
+        .map_err(ApiError::from)?;
+    if !exists {
+        return Err(ApiError::NotFound(
+            "Could not find artifact for given deployment".into(),
+        ));
+    }
+
+    let presigned = artifacts_repo
+        .presign_download(&deployment, PRESIGNED_URL_TTL)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotImplemented(
+            "Artifact storage does not support presigned urls".into(),
+        ))?;
+    let checksum = deployments_repo
+        .find_artifact_checksum(&deployment.id)
+        .await
+        .map_err(ApiError::from)?;
+
     Ok((
         StatusCode::OK,
-        [(header::CONTENT_TYPE, "application/zip")],
-        res,
+        Json(PresignedDownload {
+            presigned,
+            sha256: checksum.as_ref().map(|c| c.sha256.clone()),
+            size: checksum.as_ref().map(|c| c.size),
+        }),
     ))
 }
 
-pub async fn delete(
+/// Called once a direct upload finished, verifies the staged artifact, publishes it and records its checksum
+#[utoipa::path(
+    post,
+    path = "/deployments/{id}/artifact/complete",
+    tag = "artifacts",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (status = 200, description = "Artifact verified and published", body = ArtifactChecksum),
+        (status = 404, description = "Deployment not found or nothing was uploaded"),
+        (status = 409, description = "Artifact was uploaded again while being verified"),
+        (status = 422, description = "Uploaded artifact is invalid and was discarded, the published one is kept"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn complete(
     Path(id): Path<String>,
-    Extension(user): Extension<AuthCurrentUser>,
-    Extension(deployments_manager): Extension<
-        Arc<DeploymentManagerService<SqlDeploymentRepository, S3DeploymentArtifactsRepository>>,
-    >,
+    ctx: WriteContext,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(artifacts_repo): Extension<Arc<ArtifactStorage>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
 ) -> Result<impl IntoResponse, ApiError> {
     let deployment = deployments_manager
         .find_by_id(&id)
         .await
         .map_err(ApiError::from)?
-        .ok_or(ApiError::BadRequest(
+        .ok_or(ApiError::NotFound(
             "Could not find deployment with given id".into(),
         ))?;
 
-    // verify deployment is owned by user
-    if deployment.owner_id != user.id {
-        return Err(ApiError::AuthError(
-            "Deployment is not owned by user".into(),
+    ctx.authorize_deployment(&deployment, Permission::WriteDeployments)
+        .await?;
+
+    // the client may have uploaded anything, so the checksum is computed here rather than trusted
+    let spooled = artifacts_repo
+        .find_staged(&deployment)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "No artifact was uploaded for given deployment".into(),
+        ))?;
+
+    // the error isn't Send, only its message is kept across the cleanup awaits
+    if let Err(e) = zip::verify_zip(spooled.file.path(), &zip::ARTIFACT_REQUIRED_FILES).map_err(|e| e.to_string()) {
+        artifacts_repo
+            .delete_staged(&deployment)
+            .await
+            .map_err(ApiError::from)?;
+
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Invalid deployment artifact: {}",
+            e
+        )));
+    }
+
+    let published = artifacts_repo
+        .publish_staged(&deployment, &spooled)
+        .await
+        .map_err(ApiError::from)?;
+    if !published {
+        return Err(ApiError::Conflict(
+            "Artifact was uploaded again while being verified, complete the upload again".into(),
         ));
     }
 
-    deployments_manager
-        .delete_artifact(&deployment)
+    let checksum = spooled.checksum;
+    deployments_repo
+        .save_artifact_checksum(&deployment.id, &checksum)
         .await
         .map_err(ApiError::from)?;
 
-    Ok((StatusCode::OK, "Ok"))
+    ctx.record(
+        "artifact.uploaded",
+        &deployment,
+        &audit::diff(
+            &serde_json::Value::Null,
+            &serde_json::json!({ "artifact": { "sha256": checksum.sha256, "size": checksum.size } }),
+        ),
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(checksum)))
 }
@@ -1,5 +1,11 @@
 use axum::response::IntoResponse;
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "Api is up", body = String, content_type = "text/plain"))
+)]
 pub async fn health() -> impl IntoResponse {
     (axum::http::StatusCode::OK, "Ok")
 }
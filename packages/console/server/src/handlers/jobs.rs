@@ -0,0 +1,408 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::{
+        deployment::{ArtifactStorage, SqlDeploymentRepository},
+        job::{Job, JobLog, JobSpec, JobStatus, SqlJobRepository},
+    },
+    jobs::{JobRunner, JobSecrets},
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission},
+        write_context::WriteContext,
+    },
+    utils::audit,
+};
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
+};
+use futures_util::stream::{self, Stream};
+use opraas_core::{application::deployment::manager::DeploymentManagerService, domain::Deployment};
+use serde::Deserialize;
+use std::{collections::VecDeque, convert::Infallible, sync::Arc, time::Duration};
+use utoipa::ToSchema;
+
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateJobPayload {
+    pub spec: JobSpec,
+    #[serde(default)]
+    pub secrets: JobSecrets,
+}
+
+/// State of a log stream between polls
+struct LogStream {
+    job_repo: Arc<SqlJobRepository>,
+    job_id: String,
+    after: Option<i64>,
+    pending: VecDeque<JobLog>,
+    done: bool,
+}
+
+/// Finds the job and its deployment, checking the user can access it
+async fn find_job(
+    id: &str,
+    user: &AuthCurrentUser,
+    permission: Permission,
+    org_authorizer: &OrgAuthorizer,
+    job_repo: &SqlJobRepository,
+    deployments_manager: &DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>,
+) -> Result<(Job, Deployment), ApiError> {
+    let job = job_repo
+        .find_by_id(id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find job with given id".into(),
+        ))?;
+
+    // jobs of deleted deployments are gone with them
+    let deployment = deployments_manager
+        .find_by_id(&job.deployment_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find job with given id".into(),
+        ))?;
+
+    org_authorizer
+        .authorize_deployment(user, &deployment, permission)
+        .await
+        .map_err(|e| match e {
+            ApiError::NotFound(_) => ApiError::NotFound("Could not find job with given id".into()),
+            e => e,
+        })?;
+
+    Ok((job, deployment))
+}
+
+/// Rejects jobs that would fail right away
+fn validate(spec: &JobSpec, secrets: &JobSecrets, deployment: &Deployment) -> Result<(), ApiError> {
+    let mut deployment = deployment.clone();
+    secrets.apply(&mut deployment);
+
+    match spec {
+        JobSpec::Contracts { .. } => {
+            if deployment.network_config.l1_rpc_url.is_none() {
+                return Err(ApiError::UnprocessableEntity(
+                    "secrets.l1_rpc_url is required to deploy contracts".into(),
+                ));
+            }
+            if deployment.accounts_config.deployer_private_key.is_none() {
+                return Err(ApiError::UnprocessableEntity(
+                    "secrets.deployer_private_key is required to deploy contracts".into(),
+                ));
+            }
+        }
+        JobSpec::Infra(spec) => {
+            if deployment.contracts_addresses.is_none() {
+                return Err(ApiError::Conflict(
+                    "Contracts must be deployed before the infra".into(),
+                ));
+            }
+            if spec.host.trim().is_empty() {
+                return Err(ApiError::UnprocessableEntity("host is required".into()));
+            }
+            if spec.replica && spec.sequencer_url.as_deref().unwrap_or_default().is_empty() {
+                return Err(ApiError::UnprocessableEntity(
+                    "sequencer_url is required for replicas".into(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/deployments/{id}/jobs",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Deployment id")),
+    request_body = CreateJobPayload,
+    responses(
+        (status = 202, description = "Job queued", body = Job),
+        (status = 404, description = "Deployment not found"),
+        (status = 409, description = "Another job is in progress or the contracts are not deployed yet"),
+        (status = 422, description = "Missing secrets or options"),
+        (status = 501, description = "Jobs don't run on this console, as when it runs as a lambda"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create(
+    Path(id): Path<String>,
+    ctx: WriteContext,
+    Extension(job_repo): Extension<Arc<SqlJobRepository>>,
+    Extension(job_runner): Extension<Arc<JobRunner>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+    Json(payload): Json<CreateJobPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    ctx.authorize_deployment(&deployment, Permission::WriteDeployments)
+        .await?;
+
+    if !job_runner.is_enabled() {
+        return Err(ApiError::NotImplemented(
+            "Jobs don't run on this console, deploy with `opruaas deploy` instead".into(),
+        ));
+    }
+    validate(&payload.spec, &payload.secrets, &deployment)?;
+
+    let job = job_repo
+        .create(
+            &uuid::Uuid::new_v4().to_string(),
+            &deployment.id,
+            &payload.spec,
+            &ctx.user.id,
+        )
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::Conflict(
+            "Another job is in progress for this deployment".into(),
+        ))?;
+
+    ctx.record(
+        "job.created",
+        &deployment,
+        &audit::diff(
+            &serde_json::Value::Null,
+            &serde_json::to_value(&job).unwrap_or_default(),
+        ),
+    )
+    .await?;
+
+    job_runner.submit(job.clone(), payload.secrets);
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/jobs",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (status = 200, description = "Jobs of the deployment, newest first", body = Vec<Job>),
+        (status = 404, description = "Deployment not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list(
+    Path(id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(job_repo): Extension<Arc<SqlJobRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    org_authorizer
+        .authorize_deployment(&user, &deployment, Permission::ReadDeployments)
+        .await?;
+
+    let jobs = job_repo
+        .find_by_deployment(&deployment.id)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list jobs".into()))?;
+
+    Ok((StatusCode::OK, Json(jobs)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job", body = Job),
+        (status = 404, description = "Job not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_by_id(
+    Path(id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(job_repo): Extension<Arc<SqlJobRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (job, _) = find_job(
+        &id,
+        &user,
+        Permission::ReadDeployments,
+        &org_authorizer,
+        &job_repo,
+        &deployments_manager,
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(job)))
+}
+
+/// Cancels a queued job right away, a running one stops at its next step
+#[utoipa::path(
+    post,
+    path = "/jobs/{id}/cancel",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 202, description = "Cancellation requested", body = Job),
+        (status = 404, description = "Job not found"),
+        (status = 409, description = "Job already finished"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn cancel(
+    Path(id): Path<String>,
+    ctx: WriteContext,
+    Extension(job_repo): Extension<Arc<SqlJobRepository>>,
+    Extension(job_runner): Extension<Arc<JobRunner>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (_, deployment) = find_job(
+        &id,
+        &ctx.user,
+        Permission::WriteDeployments,
+        &ctx.org_authorizer,
+        &job_repo,
+        &deployments_manager,
+    )
+    .await?;
+
+    let job = job_repo
+        .request_cancel(&id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::Conflict("Job already finished".into()))?;
+    if job.status == JobStatus::Running {
+        job_runner.cancel(&job.id);
+    }
+
+    ctx.record(
+        "job.cancelled",
+        &deployment,
+        &audit::diff(
+            &serde_json::json!({ "jobs": { &job.id: { "cancel_requested": false } } }),
+            &serde_json::json!({ "jobs": { &job.id: { "cancel_requested": true } } }),
+        ),
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Streams the job log as Server-Sent Events, one `message` per line with the line id as event id.
+/// Ends with an `end` event carrying the final status. Resumes after `Last-Event-ID` when given
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}/logs",
+    tag = "jobs",
+    params(
+        ("id" = String, Path, description = "Job id"),
+        ("last-event-id" = Option<i64>, Header, description = "Id of the last line received, to resume a stream"),
+    ),
+    responses(
+        (status = 200, description = "Log stream", body = String, content_type = "text/event-stream"),
+        (status = 404, description = "Job not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn logs(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(job_repo): Extension<Arc<SqlJobRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (job, _) = find_job(
+        &id,
+        &user,
+        Permission::ReadDeployments,
+        &org_authorizer,
+        &job_repo,
+        &deployments_manager,
+    )
+    .await?;
+
+    let after = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok());
+
+    let stream = log_stream(LogStream {
+        job_repo,
+        job_id: job.id,
+        after,
+        pending: VecDeque::new(),
+        done: false,
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn log_stream(state: LogStream) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(log) = state.pending.pop_front() {
+                state.after = Some(log.id);
+                let event = Event::default().id(log.id.to_string()).data(log.line);
+                return Some((Ok(event), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            // read the status first, lines written before the job finished are then all visible
+            let job = state
+                .job_repo
+                .find_by_id(&state.job_id)
+                .await
+                .map_err(|e| e.to_string());
+            let logs = state
+                .job_repo
+                .find_logs(&state.job_id, state.after)
+                .await
+                .map_err(|e| e.to_string());
+
+            match (job, logs) {
+                (Ok(Some(job)), Ok(logs)) => {
+                    if !logs.is_empty() {
+                        state.pending.extend(logs);
+                    } else if job.status.is_finished() {
+                        state.done = true;
+                        let event = Event::default().event("end").data(job.status.as_str());
+                        return Some((Ok(event), state));
+                    } else {
+                        tokio::time::sleep(LOG_POLL_INTERVAL).await;
+                    }
+                }
+                (Ok(None), _) => return None,
+                (Err(e), _) | (_, Err(e)) => {
+                    state.done = true;
+                    let event = Event::default().event("error").data(e);
+                    return Some((Ok(event), state));
+                }
+            }
+        }
+    })
+}
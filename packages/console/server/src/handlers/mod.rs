@@ -1,4 +1,12 @@
+pub mod audit;
+pub mod auth;
 pub mod deployments;
 pub mod deployments_artifacts;
 pub mod health;
+pub mod jobs;
+pub mod openapi;
+pub mod orgs;
 pub mod projects;
+pub mod revisions;
+pub mod tokens;
+pub mod webhooks;
@@ -0,0 +1,7 @@
+use crate::openapi::ApiDoc;
+use axum::{response::IntoResponse, Json};
+use utoipa::OpenApi;
+
+pub async fn openapi() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
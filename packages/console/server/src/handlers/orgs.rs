@@ -0,0 +1,216 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::org::{OrgMember, OrgMembership, SqlOrgRepository},
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission, Role},
+    },
+};
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use serde::Deserialize;
+use std::{str::FromStr, sync::Arc};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateOrgPayload {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveMemberPayload {
+    /// owner, operator or viewer
+    pub role: String,
+}
+
+/// Members are identified by their address, stored the same way as `AuthCurrentUser::id`
+fn normalize_user_id(address: &str) -> Result<String, ApiError> {
+    let user_id = address.trim_start_matches("0x").to_lowercase();
+    if user_id.len() != 40 || hex::decode(&user_id).is_err() {
+        return Err(ApiError::BadRequest("Invalid member address".into()));
+    }
+
+    Ok(user_id)
+}
+
+#[utoipa::path(
+    post,
+    path = "/orgs",
+    tag = "orgs",
+    request_body = CreateOrgPayload,
+    responses(
+        (status = 200, description = "Created org, owned by the signed in user", body = OrgMembership),
+        (status = 400, description = "Org name is missing"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create(
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+    Json(payload): Json<CreateOrgPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.require_session()?;
+
+    if payload.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Org name is required".into()));
+    }
+
+    let org_id = uuid::Uuid::new_v4().to_string();
+    org_repo
+        .create(&org_id, payload.name.trim(), &user.id)
+        .await
+        .map_err(ApiError::from)?;
+
+    let org = org_repo
+        .find_by_member(&user.id)
+        .await
+        .map_err(ApiError::from)?
+        .into_iter()
+        .find(|org| org.id == org_id)
+        .ok_or(ApiError::InternalServerError("Could not create org".into()))?;
+
+    Ok((StatusCode::OK, Json(org)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs",
+    tag = "orgs",
+    responses((status = 200, description = "Orgs the user is a member of", body = Vec<OrgMembership>)),
+    security(("bearer" = []))
+)]
+pub async fn list(
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_repo
+        .ensure_personal(&user.id)
+        .await
+        .map_err(ApiError::from)?;
+
+    let orgs = org_repo
+        .find_by_member(&user.id)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list orgs".into()))?;
+
+    Ok((StatusCode::OK, Json(orgs)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs/{id}/members",
+    tag = "orgs",
+    params(("id" = String, Path, description = "Org id")),
+    responses((status = 200, description = "Org members", body = Vec<OrgMember>)),
+    security(("bearer" = []))
+)]
+pub async fn list_members(
+    Path(org_id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ReadDeployments)
+        .await?;
+
+    let members = org_repo
+        .find_members(&org_id)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list members".into()))?;
+
+    Ok((StatusCode::OK, Json(members)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/orgs/{id}/members/{member_id}",
+    tag = "orgs",
+    params(("id" = String, Path, description = "Org id"), ("member_id" = String, Path, description = "Member address")),
+    request_body = SaveMemberPayload,
+    responses(
+        (status = 204, description = "Member added or role changed"),
+        (status = 400, description = "Invalid address or role"),
+        (status = 409, description = "Org must keep at least one owner"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn save_member(
+    Path((org_id, member_id)): Path<(String, String)>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+    Json(payload): Json<SaveMemberPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ManageOrg)
+        .await?;
+
+    let member_id = normalize_user_id(&member_id)?;
+    let role = Role::from_str(&payload.role)?;
+    if member_id == user.id && role != Role::Owner {
+        ensure_other_owner(&org_repo, &org_id, &member_id).await?;
+    }
+
+    org_repo
+        .save_member(&org_id, &member_id, role.as_str())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/orgs/{id}/members/{member_id}",
+    tag = "orgs",
+    params(("id" = String, Path, description = "Org id"), ("member_id" = String, Path, description = "Member address")),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 404, description = "Member not found"),
+        (status = 409, description = "Org must keep at least one owner"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete_member(
+    Path((org_id, member_id)): Path<(String, String)>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(org_repo): Extension<Arc<SqlOrgRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ManageOrg)
+        .await?;
+
+    let member_id = normalize_user_id(&member_id)?;
+    ensure_other_owner(&org_repo, &org_id, &member_id).await?;
+
+    if !org_repo
+        .delete_member(&org_id, &member_id)
+        .await
+        .map_err(ApiError::from)?
+    {
+        return Err(ApiError::NotFound(
+            "Could not find member with given id".into(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Orgs must keep at least one owner
+async fn ensure_other_owner(org_repo: &SqlOrgRepository, org_id: &str, member_id: &str) -> Result<(), ApiError> {
+    let has_other_owner = org_repo
+        .find_members(org_id)
+        .await
+        .map_err(ApiError::from)?
+        .iter()
+        .any(|m| m.user_id != member_id && m.role == Role::Owner.as_str());
+
+    if !has_other_owner {
+        return Err(ApiError::Conflict(
+            "Org must keep at least one owner".into(),
+        ));
+    }
+
+    Ok(())
+}
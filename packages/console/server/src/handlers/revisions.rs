@@ -0,0 +1,203 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::deployment::DeploymentRevision,
+    infrastructure::domain::{
+        deployment::{ArtifactStorage, SqlDeploymentRepository},
+    },
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission},
+        write_context::WriteContext,
+    },
+    utils::audit::{self, FieldChange},
+};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use opraas_core::{application::deployment::manager::DeploymentManagerService, domain::Deployment};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffQuery {
+    pub from: i32,
+    /// Defaults to the head revision
+    pub to: Option<i32>,
+}
+
+async fn find_authorized(
+    id: &str,
+    user: &AuthCurrentUser,
+    permission: Permission,
+    org_authorizer: &OrgAuthorizer,
+    deployments_manager: &DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>,
+) -> Result<Deployment, ApiError> {
+    let deployment = deployments_manager
+        .find_by_id(id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find deployment with given id".into(),
+        ))?;
+
+    org_authorizer
+        .authorize_deployment(user, &deployment, permission)
+        .await?;
+
+    Ok(deployment)
+}
+
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/revisions",
+    tag = "revisions",
+    params(("id" = String, Path, description = "Deployment id")),
+    responses(
+        (status = 200, description = "Every revision, oldest first", body = Vec<DeploymentRevision>),
+        (status = 404, description = "Deployment not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list(
+    Path(id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    find_authorized(
+        &id,
+        &user,
+        Permission::ReadDeployments,
+        &org_authorizer,
+        &deployments_manager,
+    )
+    .await?;
+
+    let revisions = deployments_repo
+        .find_revisions(&id)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list revisions".into()))?;
+
+    Ok((StatusCode::OK, Json(revisions)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/diff",
+    tag = "revisions",
+    params(("id" = String, Path, description = "Deployment id"), DiffQuery),
+    responses(
+        (status = 200, description = "Changed fields by path", body = std::collections::HashMap<String, FieldChange>),
+        (status = 404, description = "Deployment or revision not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn diff(
+    Path(id): Path<String>,
+    Query(query): Query<DiffQuery>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let head = find_authorized(
+        &id,
+        &user,
+        Permission::ReadDeployments,
+        &org_authorizer,
+        &deployments_manager,
+    )
+    .await?;
+
+    let from = deployments_repo
+        .find_revision(&id, query.from)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find revision with given number".into(),
+        ))?
+        .deployment;
+    let to = match query.to {
+        Some(to) => {
+            deployments_repo
+                .find_revision(&id, to)
+                .await
+                .map_err(ApiError::from)?
+                .ok_or(ApiError::NotFound(
+                    "Could not find revision with given number".into(),
+                ))?
+                .deployment
+        }
+        None => head,
+    };
+
+    let diff = audit::diff(
+        &serde_json::to_value(&from).unwrap_or_default(),
+        &serde_json::to_value(&to).unwrap_or_default(),
+    );
+
+    Ok((StatusCode::OK, Json(diff)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/deployments/{id}/revisions/{revision}/restore",
+    tag = "revisions",
+    params(("id" = String, Path, description = "Deployment id"), ("revision" = i32, Path, description = "Revision to restore")),
+    responses(
+        (status = 200, description = "Deployment saved as a new revision with the restored content", body = Deployment),
+        (status = 404, description = "Deployment or revision not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn restore(
+    Path((id, revision)): Path<(String, i32)>,
+    ctx: WriteContext,
+    Extension(deployments_repo): Extension<Arc<SqlDeploymentRepository>>,
+    Extension(deployments_manager): Extension<Arc<DeploymentManagerService<SqlDeploymentRepository, ArtifactStorage>>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let head = find_authorized(
+        &id,
+        &ctx.user,
+        Permission::WriteDeployments,
+        &ctx.org_authorizer,
+        &deployments_manager,
+    )
+    .await?;
+
+    let mut deployment = deployments_repo
+        .find_revision(&id, revision)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound(
+            "Could not find revision with given number".into(),
+        ))?
+        .deployment;
+
+    // restoring appends a copy of the revision, history is never rewritten
+    deployment.owner_id = head.owner_id.clone();
+    deployments_manager
+        .save(&deployment)
+        .await
+        .map_err(ApiError::from)?;
+
+    ctx.record(
+        "deployment.restored",
+        &deployment,
+        &audit::diff(
+            &serde_json::to_value(&head).unwrap_or_default(),
+            &serde_json::to_value(&deployment).unwrap_or_default(),
+        ),
+    )
+    .await?;
+
+    let deployment_json = serde_json::to_string(&deployment)
+        .map_err(|_| ApiError::InternalServerError("Could not serialize deployment".into()))?;
+    Ok((StatusCode::OK, deployment_json))
+}
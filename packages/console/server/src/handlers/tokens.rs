@@ -0,0 +1,147 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::auth::{ApiToken, SqlApiTokenRepository},
+    middlewares::auth::{hash_token, AuthCurrentUser, API_TOKEN_PREFIX, SCOPES},
+};
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTokenPayload {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Optional unix timestamp after which the token is rejected
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateTokenResponse {
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+    /// Only returned once, at creation
+    pub token: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tokens",
+    tag = "tokens",
+    request_body = CreateTokenPayload,
+    responses(
+        (status = 200, description = "Created token, the secret is only returned here", body = CreateTokenResponse),
+        (status = 400, description = "Invalid name, scopes or expiry"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create(
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(api_token_repo): Extension<Arc<SqlApiTokenRepository>>,
+    Json(payload): Json<CreateTokenPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.require_session()?;
+
+    if payload.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Token name is required".into()));
+    }
+    if payload.scopes.is_empty() {
+        return Err(ApiError::BadRequest(
+            "At least one scope is required".into(),
+        ));
+    }
+    if let Some(scope) = payload
+        .scopes
+        .iter()
+        .find(|s| !SCOPES.contains(&s.as_str()))
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Unknown scope {}, available scopes are {}",
+            scope,
+            SCOPES.join(", ")
+        )));
+    }
+    if payload
+        .expires_at
+        .is_some_and(|t| t <= OffsetDateTime::now_utc().unix_timestamp())
+    {
+        return Err(ApiError::BadRequest(
+            "Token expiry must be in the future".into(),
+        ));
+    }
+
+    let token = format!(
+        "{}{}",
+        API_TOKEN_PREFIX,
+        hex::encode(rand::random::<[u8; 32]>())
+    );
+    let api_token = api_token_repo
+        .create(
+            &uuid::Uuid::new_v4().to_string(),
+            &user.id,
+            payload.name.trim(),
+            &hash_token(&token),
+            &payload.scopes,
+            payload.expires_at,
+        )
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(CreateTokenResponse { api_token, token }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tokens",
+    tag = "tokens",
+    responses((status = 200, description = "Tokens of the signed in user", body = Vec<ApiToken>)),
+    security(("bearer" = []))
+)]
+pub async fn list(
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(api_token_repo): Extension<Arc<SqlApiTokenRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.require_session()?;
+
+    let api_tokens = api_token_repo
+        .find_by_user(&user.id)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list tokens".into()))?;
+
+    Ok((StatusCode::OK, Json(api_tokens)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tokens/{id}",
+    tag = "tokens",
+    params(("id" = String, Path, description = "Token id")),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 404, description = "Token not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete(
+    Path(id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(api_token_repo): Extension<Arc<SqlApiTokenRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    user.require_session()?;
+
+    if !api_token_repo
+        .delete(&id, &user.id)
+        .await
+        .map_err(ApiError::from)?
+    {
+        return Err(ApiError::NotFound(
+            "Could not find token with given id".into(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,242 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::webhook::{DeliveryStatus, SqlWebhookRepository, Webhook, WebhookDelivery, WEBHOOK_EVENTS},
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission},
+    },
+    webhooks::TargetPolicy,
+};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookPayload {
+    /// Receives a POST per event, http or https
+    pub url: String,
+    /// Signs deliveries, a random one is generated if missing
+    pub secret: Option<String>,
+    /// Events to deliver, every event if empty
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    /// Only returned once, at creation
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveriesQuery {
+    /// Only deliveries in this status, e.g. failed
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<i64>,
+}
+
+impl CreateWebhookPayload {
+    /// The url is checked again on every delivery, where it may have started resolving elsewhere
+    async fn validate(&self, targets: &TargetPolicy) -> Result<(), ApiError> {
+        let url =
+            reqwest::Url::parse(&self.url).map_err(|e| ApiError::BadRequest(format!("Invalid webhook url. {}", e)))?;
+        if !["http", "https"].contains(&url.scheme()) {
+            return Err(ApiError::BadRequest(
+                "Webhook url must be http or https".into(),
+            ));
+        }
+
+        if self.secret.as_ref().is_some_and(|s| s.trim().is_empty()) {
+            return Err(ApiError::BadRequest("Webhook secret can't be empty".into()));
+        }
+
+        if let Some(event) = self
+            .events
+            .iter()
+            .find(|e| !WEBHOOK_EVENTS.contains(&e.as_str()))
+        {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown event {}, available events are {}",
+                event,
+                WEBHOOK_EVENTS.join(", ")
+            )));
+        }
+
+        targets
+            .resolve(&url)
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Webhooks are looked up within the org so ids from other orgs are not found
+async fn find_webhook(
+    webhook_repo: &SqlWebhookRepository,
+    org_id: &str,
+    webhook_id: &str,
+) -> Result<Webhook, ApiError> {
+    webhook_repo
+        .find_by_id(webhook_id)
+        .await
+        .map_err(ApiError::from)?
+        .filter(|webhook| webhook.org_id == org_id)
+        .ok_or(ApiError::NotFound(
+            "Could not find webhook with given id".into(),
+        ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/orgs/{id}/webhooks",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Org id")),
+    request_body = CreateWebhookPayload,
+    responses(
+        (status = 200, description = "Created webhook, the secret is only returned here", body = CreateWebhookResponse),
+        (status = 400, description = "Invalid url, secret or events, or the url resolves to a non public address"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create(
+    Path(org_id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(webhook_repo): Extension<Arc<SqlWebhookRepository>>,
+    Extension(webhook_targets): Extension<TargetPolicy>,
+    Json(payload): Json<CreateWebhookPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ManageOrg)
+        .await?;
+
+    payload.validate(&webhook_targets).await?;
+
+    let secret = payload
+        .secret
+        .unwrap_or_else(|| hex::encode(rand::random::<[u8; 32]>()));
+    let webhook = webhook_repo
+        .create(
+            &uuid::Uuid::new_v4().to_string(),
+            &org_id,
+            &payload.url,
+            &secret,
+            &payload.events,
+            &user.id,
+        )
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(CreateWebhookResponse { webhook, secret }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs/{id}/webhooks",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Org id")),
+    responses((status = 200, description = "Org webhooks, without their secrets", body = Vec<Webhook>)),
+    security(("bearer" = []))
+)]
+pub async fn list(
+    Path(org_id): Path<String>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(webhook_repo): Extension<Arc<SqlWebhookRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ManageOrg)
+        .await?;
+
+    let webhooks = webhook_repo
+        .find_by_org(&org_id)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list webhooks".into()))?;
+
+    Ok((StatusCode::OK, Json(webhooks)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/orgs/{id}/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Org id"), ("webhook_id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Webhook deleted, pending deliveries are dropped"),
+        (status = 404, description = "Webhook not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete(
+    Path((org_id, webhook_id)): Path<(String, String)>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(webhook_repo): Extension<Arc<SqlWebhookRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ManageOrg)
+        .await?;
+
+    let webhook = find_webhook(&webhook_repo, &org_id, &webhook_id).await?;
+    webhook_repo
+        .delete(&webhook.id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/orgs/{id}/webhooks/{webhook_id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Org id"),
+        ("webhook_id" = String, Path, description = "Webhook id"),
+        DeliveriesQuery
+    ),
+    responses(
+        (status = 200, description = "Deliveries with the outcome of their last attempt, newest first", body = Vec<WebhookDelivery>),
+        (status = 404, description = "Webhook not found"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn deliveries(
+    Path((org_id, webhook_id)): Path<(String, String)>,
+    Query(query): Query<DeliveriesQuery>,
+    Extension(user): Extension<AuthCurrentUser>,
+    Extension(org_authorizer): Extension<Arc<OrgAuthorizer>>,
+    Extension(webhook_repo): Extension<Arc<SqlWebhookRepository>>,
+) -> Result<impl IntoResponse, ApiError> {
+    org_authorizer
+        .authorize(&user, &org_id, Permission::ManageOrg)
+        .await?;
+
+    let webhook = find_webhook(&webhook_repo, &org_id, &webhook_id).await?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let deliveries = webhook_repo
+        .find_deliveries(&webhook.id, query.status, limit)
+        .await
+        .map_err(|_| ApiError::InternalServerError("Could not list deliveries".into()))?;
+
+    Ok((StatusCode::OK, Json(deliveries)))
+}
@@ -1,29 +1,89 @@
-use async_once::AsyncOnce;
-use lazy_static::lazy_static;
 use sqlx::pool::Pool;
 use sqlx::postgres::{PgPoolOptions, Postgres};
-use std::env;
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqlitePoolOptions};
+use std::str::FromStr;
 
 use crate::error::ApiError;
 
 const DB_POOL_MAX_CONNECTIONS: u32 = 5;
 
-pub type DbPool = Pool<Postgres>;
+/// Postgres in production, sqlite for local development and tests. Picked from the `DATABASE_URL` scheme
+#[derive(Clone, Debug)]
+pub enum DbPool {
+    Postgres(Pool<Postgres>),
+    Sqlite(Pool<Sqlite>),
+}
 
-lazy_static! {
-    static ref DB_POOL: AsyncOnce<Result<DbPool, ApiError>> = AsyncOnce::new(async { create_pool().await });
+/// Evaluates `$body` with `$pool` bound to the concrete pool, so the same query code runs against either database
+macro_rules! with_pool {
+    ($db:expr, $pool:ident => $body:expr) => {
+        match $db {
+            $crate::infrastructure::database::DbPool::Postgres($pool) => $body,
+            $crate::infrastructure::database::DbPool::Sqlite($pool) => $body,
+        }
+    };
 }
+pub(crate) use with_pool;
 
-pub async fn create_pool() -> Result<Pool<Postgres>, ApiError> {
-    let db_uri = env::var("DATABASE_URL").expect("Missing \"DATABASE_URL\" environment variable");
+impl DbPool {
+    pub async fn connect(url: &str) -> Result<Self, ApiError> {
+        if url.starts_with("sqlite:") {
+            let options = SqliteConnectOptions::from_str(url)
+                .map_err(|e| ApiError::InternalServerError(e.to_string()))?
+                .create_if_missing(true);
 
-    PgPoolOptions::new()
-        .max_connections(DB_POOL_MAX_CONNECTIONS)
-        .connect(&db_uri)
-        .await
-        .map_err(|e| ApiError::InternalServerError(e.to_string()))
-}
+            return SqlitePoolOptions::new()
+                .max_connections(DB_POOL_MAX_CONNECTIONS)
+                .connect_with(options)
+                .await
+                .map(Self::Sqlite)
+                .map_err(|e| ApiError::InternalServerError(e.to_string()));
+        }
+
+        PgPoolOptions::new()
+            .max_connections(DB_POOL_MAX_CONNECTIONS)
+            .connect(url)
+            .await
+            .map(Self::Postgres)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))
+    }
+
+    /// Each database keeps its own migrations
+    pub async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        match self {
+            Self::Postgres(pool) => sqlx::migrate!("./migrations").run(pool).await,
+            Self::Sqlite(pool) => sqlx::migrate!("./migrations-sqlite").run(pool).await,
+        }
+    }
+
+    /// Sql expression reading a timestamp column as unix seconds
+    pub fn epoch(&self, column: &str) -> String {
+        match self {
+            Self::Postgres(_) => format!("EXTRACT(EPOCH FROM {})::BIGINT", column),
+            Self::Sqlite(_) => column.to_string(),
+        }
+    }
+
+    /// Sql expression turning a unix seconds parameter into a timestamp
+    pub fn timestamp(&self, param: &str) -> String {
+        match self {
+            Self::Postgres(_) => format!("to_timestamp({})", param),
+            Self::Sqlite(_) => format!("CAST({} AS INTEGER)", param),
+        }
+    }
+
+    pub fn now(&self) -> &'static str {
+        match self {
+            Self::Postgres(_) => "NOW()",
+            Self::Sqlite(_) => "unixepoch()",
+        }
+    }
 
-pub async fn get_db_pool() -> Result<DbPool, ApiError> {
-    DB_POOL.get().await.clone()
+    /// Postgres needs explicit casts where sqlite is dynamically typed
+    pub fn cast(&self, expr: &str, postgres_type: &str) -> String {
+        match self {
+            Self::Postgres(_) => format!("{}::{}", expr, postgres_type),
+            Self::Sqlite(_) => expr.to_string(),
+        }
+    }
 }
@@ -0,0 +1,119 @@
+use crate::{
+    infrastructure::database::{with_pool, DbPool},
+    utils::audit::FieldChange,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+pub struct SqlAuditRepository {
+    client: DbPool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub actor_id: String,
+    pub action: String,
+    pub deployment_id: String,
+    /// Changed fields by path, each with its value before and after
+    #[schema(value_type = std::collections::HashMap<String, FieldChange>)]
+    pub diff: serde_json::Value,
+    pub request_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct AuditEventDto {
+    pub id: i64,
+    pub actor_id: String,
+    pub action: String,
+    pub deployment_id: String,
+    pub diff: String,
+    pub request_id: Option<String>,
+    pub created_at: i64,
+}
+
+impl From<AuditEventDto> for AuditEvent {
+    fn from(event: AuditEventDto) -> Self {
+        Self {
+            id: event.id,
+            actor_id: event.actor_id,
+            action: event.action,
+            deployment_id: event.deployment_id,
+            diff: serde_json::from_str(&event.diff).unwrap_or_default(),
+            request_id: event.request_id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+impl SqlAuditRepository {
+    pub fn new(client: DbPool) -> Self {
+        Self { client }
+    }
+
+    fn columns(&self) -> String {
+        format!(
+            "id, actor_id, action, deployment_id, {} AS diff, request_id, {} AS created_at",
+            self.client.cast("diff", "TEXT"),
+            self.client.epoch("created_at"),
+        )
+    }
+
+    /// Returns the stored event, to be forwarded to webhooks
+    pub async fn append(
+        &self,
+        actor_id: &str,
+        action: &str,
+        deployment_id: &str,
+        diff: &serde_json::Value,
+        request_id: Option<&str>,
+    ) -> Result<AuditEvent, Box<dyn std::error::Error>> {
+        let insert = format!(
+            "INSERT INTO audit_events (actor_id, action, deployment_id, diff, request_id) \
+            VALUES ($1, $2, $3, {}, $5) RETURNING {}",
+            self.client.cast("$4", "JSONB"),
+            self.columns()
+        );
+        let diff = serde_json::to_string(diff)?;
+
+        let event: AuditEventDto = with_pool!(&self.client, pool => {
+            sqlx::query_as(&insert)
+                .bind(actor_id)
+                .bind(action)
+                .bind(deployment_id)
+                .bind(&diff)
+                .bind(request_id)
+                .fetch_one(pool)
+                .await?
+        });
+
+        Ok(event.into())
+    }
+
+    /// Newest first, `before` is the id of the last event of the previous page
+    pub async fn find_by_deployment(
+        &self,
+        deployment_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT {} FROM audit_events WHERE deployment_id = $1 AND ({} IS NULL OR id < $2) \
+            ORDER BY id DESC LIMIT $3",
+            self.columns(),
+            self.client.cast("$2", "BIGINT")
+        );
+
+        let events: Vec<AuditEventDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(deployment_id)
+                .bind(before)
+                .bind(limit)
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(events.into_iter().map(AuditEvent::from).collect())
+    }
+}
@@ -0,0 +1,152 @@
+use crate::infrastructure::database::{with_pool, DbPool};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Api tokens, listed without the token itself which is only known at creation
+pub struct SqlApiTokenRepository {
+    client: DbPool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+/// Scopes travel as a json array, postgres keeps them in a TEXT[] and sqlite as json text
+#[derive(Debug, sqlx::FromRow)]
+struct ApiTokenDto {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub scopes: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+impl From<ApiTokenDto> for ApiToken {
+    fn from(token: ApiTokenDto) -> Self {
+        Self {
+            id: token.id,
+            user_id: token.user_id,
+            name: token.name,
+            scopes: serde_json::from_str(&token.scopes).unwrap_or_default(),
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+        }
+    }
+}
+
+impl SqlApiTokenRepository {
+    pub fn new(client: DbPool) -> Self {
+        Self { client }
+    }
+
+    fn columns(&self) -> String {
+        let scopes = match self.client {
+            DbPool::Postgres(_) => "array_to_json(scopes)::TEXT AS scopes",
+            DbPool::Sqlite(_) => "scopes",
+        };
+
+        format!(
+            "id, user_id, name, {}, {} AS created_at, {} AS expires_at, {} AS last_used_at",
+            scopes,
+            self.client.epoch("created_at"),
+            self.client.epoch("expires_at"),
+            self.client.epoch("last_used_at"),
+        )
+    }
+
+    pub async fn create(
+        &self,
+        id: &str,
+        user_id: &str,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+        expires_at: Option<i64>,
+    ) -> Result<ApiToken, Box<dyn std::error::Error>> {
+        let scopes_param = match self.client {
+            DbPool::Postgres(_) => "ARRAY(SELECT json_array_elements_text($5::JSON))",
+            DbPool::Sqlite(_) => "$5",
+        };
+        let insert = format!(
+            "INSERT INTO api_tokens (id, user_id, name, token_hash, scopes, expires_at) \
+            VALUES ($1, $2, $3, $4, {}, {}) RETURNING {}",
+            scopes_param,
+            self.client.timestamp("$6"),
+            self.columns()
+        );
+        let scopes = serde_json::to_string(scopes)?;
+
+        let token: ApiTokenDto = with_pool!(&self.client, pool => {
+            sqlx::query_as(&insert)
+                .bind(id)
+                .bind(user_id)
+                .bind(name)
+                .bind(token_hash)
+                .bind(&scopes)
+                .bind(expires_at.map(|t| t as f64))
+                .fetch_one(pool)
+                .await?
+        });
+
+        Ok(token.into())
+    }
+
+    pub async fn find_by_user(&self, user_id: &str) -> Result<Vec<ApiToken>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT {} FROM api_tokens WHERE user_id = $1 ORDER BY created_at DESC",
+            self.columns()
+        );
+
+        let tokens: Vec<ApiTokenDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(user_id)
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(tokens.into_iter().map(ApiToken::from).collect())
+    }
+
+    /// Returns the token if active, recording the usage on the way
+    pub async fn use_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE api_tokens SET last_used_at = {now} \
+            WHERE token_hash = $1 AND (expires_at IS NULL OR expires_at > {now}) RETURNING {}",
+            self.columns(),
+            now = self.client.now()
+        );
+
+        let token: Option<ApiTokenDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&update)
+                .bind(token_hash)
+                .fetch_optional(pool)
+                .await?
+        });
+
+        Ok(token.map(ApiToken::from))
+    }
+
+    /// Revokes the token, returns false if the user has no token with given id
+    pub async fn delete(&self, id: &str, user_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let result = with_pool!(&self.client, pool => {
+            sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(user_id)
+                .execute(pool)
+                .await?
+                .rows_affected()
+        });
+
+        Ok(result > 0)
+    }
+}
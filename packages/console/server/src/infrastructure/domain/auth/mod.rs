@@ -0,0 +1,5 @@
+pub mod api_token_repo;
+pub mod repo;
+
+pub use api_token_repo::*;
+pub use repo::*;
@@ -0,0 +1,109 @@
+use crate::infrastructure::database::{with_pool, DbPool};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Nonces and sessions used by the SIWE flow
+pub struct SqlAuthRepository {
+    client: DbPool,
+}
+
+/// Unix timestamp `ttl` ago
+fn cutoff(ttl: Duration) -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .saturating_sub(ttl)
+        .as_secs_f64()
+}
+
+impl SqlAuthRepository {
+    pub fn new(client: DbPool) -> Self {
+        Self { client }
+    }
+
+    /// Stores a freshly issued nonce, dropping the ones older than `ttl` on the way
+    pub async fn create_nonce(&self, nonce: &str, ttl: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let delete_expired = format!(
+            "DELETE FROM auth_nonces WHERE created_at < {}",
+            self.client.timestamp("$1")
+        );
+
+        with_pool!(&self.client, pool => {
+            sqlx::query(&delete_expired)
+                .bind(cutoff(ttl))
+                .execute(pool)
+                .await?;
+
+            sqlx::query("INSERT INTO auth_nonces (nonce) VALUES ($1)")
+                .bind(nonce)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    /// Atomically removes the nonce, returns false if it was never issued, already used or expired
+    pub async fn consume_nonce(&self, nonce: &str, ttl: Duration) -> Result<bool, Box<dyn std::error::Error>> {
+        let consume = format!(
+            "DELETE FROM auth_nonces WHERE nonce = $1 AND created_at >= {} RETURNING nonce",
+            self.client.timestamp("$2")
+        );
+
+        let consumed: Option<(String,)> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&consume)
+                .bind(nonce)
+                .bind(cutoff(ttl))
+                .fetch_optional(pool)
+                .await?
+        });
+
+        Ok(consumed.is_some())
+    }
+
+    /// Stores a session expiring at the given unix timestamp
+    pub async fn create_session(
+        &self,
+        token_hash: &str,
+        user_id: &str,
+        expires_at: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let delete_expired = format!(
+            "DELETE FROM auth_sessions WHERE expires_at < {}",
+            self.client.now()
+        );
+        let insert = format!(
+            "INSERT INTO auth_sessions (token_hash, user_id, expires_at) VALUES ($1, $2, {})",
+            self.client.timestamp("$3")
+        );
+
+        with_pool!(&self.client, pool => {
+            sqlx::query(&delete_expired).execute(pool).await?;
+
+            sqlx::query(&insert)
+                .bind(token_hash)
+                .bind(user_id)
+                .bind(expires_at as f64)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    /// Returns the user id of a non expired session
+    pub async fn find_session_user(&self, token_hash: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT user_id FROM auth_sessions WHERE token_hash = $1 AND expires_at > {}",
+            self.client.now()
+        );
+
+        let session: Option<(String,)> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(token_hash)
+                .fetch_optional(pool)
+                .await?
+        });
+
+        Ok(session.map(|(user_id,)| user_id))
+    }
+}
@@ -1,11 +1,78 @@
-use aws_sdk_s3::primitives::ByteStream;
-use opraas_core::domain::{Deployment, DeploymentArtifact, TDeploymentArtifactsRepository};
+use aws_sdk_s3::{
+    presigning::{PresignedRequest, PresigningConfig},
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+};
+use opraas_core::domain::{Deployment, DeploymentArtifact, DeploymentError, TDeploymentArtifactsRepository};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tempfile::NamedTempFile;
+use utoipa::ToSchema;
 
+/// S3 requires every part but the last one to be at least 5mb
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Clone)]
 pub struct S3DeploymentArtifactsRepository {
     client: aws_sdk_s3::Client,
     bucket_name: String,
 }
 
+/// Streams an artifact to an S3 multipart upload, hashing it as it goes. Only one part is kept in
+/// memory, but the whole artifact is also spooled to a temporary file: zips keep their index at the
+/// end, so they can't be verified before the last chunk arrives. Disk usage is bounded by the
+/// request body limit. Nothing is visible in the bucket until `complete` is called.
+pub struct S3ArtifactUpload {
+    client: aws_sdk_s3::Client,
+    bucket_name: String,
+    key: String,
+    upload_id: String,
+    parts: Vec<CompletedPart>,
+    buffer: Vec<u8>,
+    hasher: Sha256,
+    size: u64,
+    spool: NamedTempFile,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ArtifactChecksum {
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Url a client can use to reach the bucket directly, without going through the api
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PresignedUrl {
+    pub url: String,
+    pub method: String,
+    /// Headers the request has to be sent with for the signature to hold
+    pub headers: HashMap<String, String>,
+    pub expires_at: i64,
+}
+
+/// Stored artifact downloaded to a temporary file
+pub struct SpooledArtifact {
+    pub file: NamedTempFile,
+    pub checksum: ArtifactChecksum,
+    /// Etag of the downloaded object, so the same content is the one published
+    pub etag: Option<String>,
+}
+
+pub(crate) fn artifact_key(deployment: &Deployment) -> String {
+    format!("{}-{}.zip", &deployment.owner_id, &deployment.id)
+}
+
+/// Direct uploads land here, they are only moved to the artifact key once verified
+pub(crate) fn staged_artifact_key(deployment: &Deployment) -> String {
+    format!("staging/{}", artifact_key(deployment))
+}
+
 impl S3DeploymentArtifactsRepository {
     pub fn new(client: aws_sdk_s3::Client, bucket_name: String) -> Self {
         Self {
@@ -13,30 +80,307 @@ impl S3DeploymentArtifactsRepository {
             bucket_name,
         }
     }
+
+    pub async fn start_upload(&self, deployment: &Deployment) -> Result<S3ArtifactUpload, Box<dyn std::error::Error>> {
+        let key = artifact_key(deployment);
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket_name)
+            .key(&key)
+            .content_type("application/zip")
+            .send()
+            .await?;
+
+        Ok(S3ArtifactUpload {
+            client: self.client.clone(),
+            bucket_name: self.bucket_name.clone(),
+            key,
+            upload_id: upload
+                .upload_id()
+                .ok_or("Multipart upload id missing")?
+                .to_string(),
+            parts: Vec::new(),
+            buffer: Vec::with_capacity(MULTIPART_PART_SIZE),
+            hasher: Sha256::new(),
+            size: 0,
+            spool: NamedTempFile::new()?,
+        })
+    }
+
+    /// Presigns an upload to the staging key, the signed content length rejects bodies of any other size
+    pub async fn presign_upload(
+        &self,
+        deployment: &Deployment,
+        size: u64,
+        ttl: Duration,
+    ) -> Result<PresignedUrl, Box<dyn std::error::Error>> {
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket_name)
+            .key(staged_artifact_key(deployment))
+            .content_type("application/zip")
+            .content_length(size as i64)
+            .presigned(PresigningConfig::expires_in(ttl)?)
+            .await?;
+
+        Ok(PresignedUrl::new(request, ttl))
+    }
+
+    pub async fn presign_download(
+        &self,
+        deployment: &Deployment,
+        ttl: Duration,
+    ) -> Result<PresignedUrl, Box<dyn std::error::Error>> {
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket_name)
+            .key(artifact_key(deployment))
+            .presigned(PresigningConfig::expires_in(ttl)?)
+            .await?;
+
+        Ok(PresignedUrl::new(request, ttl))
+    }
+
+    /// Downloads the staged artifact to disk hashing it on the way, none if nothing was uploaded
+    pub async fn find_staged(
+        &self,
+        deployment: &Deployment,
+    ) -> Result<Option<SpooledArtifact>, Box<dyn std::error::Error>> {
+        let mut resp = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket_name)
+            .key(staged_artifact_key(deployment))
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut file = NamedTempFile::new()?;
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        while let Some(chunk) = resp.body.try_next().await? {
+            hasher.update(&chunk);
+            file.write_all(&chunk)?;
+            size += chunk.len() as u64;
+        }
+        file.flush()?;
+
+        Ok(Some(SpooledArtifact {
+            file,
+            checksum: ArtifactChecksum {
+                sha256: hex::encode(hasher.finalize()),
+                size,
+            },
+            etag: resp.e_tag().map(str::to_string),
+        }))
+    }
+
+    /// Moves the verified staged artifact to the artifact key, false if it was replaced since it was spooled
+    pub async fn publish_staged(
+        &self,
+        deployment: &Deployment,
+        spooled: &SpooledArtifact,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let copied = self
+            .client
+            .copy_object()
+            .bucket(&self.bucket_name)
+            .copy_source(format!(
+                "{}/{}",
+                self.bucket_name,
+                staged_artifact_key(deployment)
+            ))
+            .set_copy_source_if_match(spooled.etag.clone())
+            .key(artifact_key(deployment))
+            .content_type("application/zip")
+            .send()
+            .await;
+        match copied {
+            Ok(_) => {}
+            Err(err)
+                if err
+                    .raw_response()
+                    .is_some_and(|r| r.status().as_u16() == 412) =>
+            {
+                return Ok(false)
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        self.delete_staged(deployment).await?;
+
+        Ok(true)
+    }
+
+    pub async fn delete_staged(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket_name)
+            .key(staged_artifact_key(deployment))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl PresignedUrl {
+    fn new(request: PresignedRequest, ttl: Duration) -> Self {
+        let expires_at = SystemTime::now() + ttl;
+
+        Self {
+            url: request.uri().to_string(),
+            method: request.method().to_string(),
+            headers: request
+                .headers()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            expires_at: expires_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl S3ArtifactUpload {
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.hasher.update(chunk);
+        self.spool.write_all(chunk)?;
+        self.buffer.extend_from_slice(chunk);
+        self.size += chunk.len() as u64;
+
+        if self.buffer.len() >= MULTIPART_PART_SIZE {
+            self.upload_part().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Everything written so far, on disk
+    pub fn spooled(&mut self) -> Result<&Path, Box<dyn std::error::Error>> {
+        self.spool.flush()?;
+
+        Ok(self.spool.path())
+    }
+
+    /// The multipart upload is aborted if it can't be completed, so no parts are left behind
+    pub async fn complete(mut self) -> Result<ArtifactChecksum, Box<dyn std::error::Error>> {
+        // the error isn't Send, only its message is kept across the abort
+        if let Err(e) = self.complete_parts().await.map_err(|e| e.to_string()) {
+            if let Err(abort_err) = self.abort_parts().await.map_err(|e| e.to_string()) {
+                error!(
+                    "Could not abort multipart upload {}. {}",
+                    self.upload_id, abort_err
+                );
+            }
+            return Err(e.into());
+        }
+
+        Ok(ArtifactChecksum {
+            sha256: hex::encode(self.hasher.finalize()),
+            size: self.size,
+        })
+    }
+
+    pub async fn abort(self) -> Result<(), Box<dyn std::error::Error>> {
+        self.abort_parts().await
+    }
+
+    async fn complete_parts(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.buffer.is_empty() || self.parts.is_empty() {
+            self.upload_part().await?;
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket_name)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(self.parts.clone()))
+                    .build(),
+            )
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn abort_parts(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(&self.bucket_name)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn upload_part(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let part_number = self.parts.len() as i32 + 1;
+        let body = std::mem::replace(&mut self.buffer, Vec::with_capacity(MULTIPART_PART_SIZE));
+        let part = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket_name)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(body))
+            .send()
+            .await?;
+
+        self.parts.push(
+            CompletedPart::builder()
+                .e_tag(part.e_tag().unwrap_or_default())
+                .part_number(part_number)
+                .build(),
+        );
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
 impl TDeploymentArtifactsRepository for S3DeploymentArtifactsRepository {
-    async fn find_one(
-        &self,
-        deployment: &Deployment,
-    ) -> Result<Option<DeploymentArtifact>, Box<dyn std::error::Error>> {
-        let key = format!("{}-{}.zip", &deployment.owner_id, &deployment.id);
-        let resp = self
+    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError> {
+        let key = artifact_key(deployment);
+        let resp = match self
             .client
             .get_object()
             .bucket(&self.bucket_name)
             .key(&key)
             .send()
-            .await?;
+            .await
+        {
+            Ok(resp) => resp,
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(err) => return Err(DeploymentError::storage(err)),
+        };
 
-        let deployment_artifact: Vec<u8> = resp.body.collect().await?.to_vec();
+        let deployment_artifact: Vec<u8> = resp
+            .body
+            .collect()
+            .await
+            .map_err(DeploymentError::storage)?
+            .to_vec();
 
         Ok(Some(deployment_artifact))
     }
 
-    async fn exists(&self, deployment: &Deployment) -> Result<bool, Box<dyn std::error::Error>> {
-        let key = format!("{}-{}.zip", &deployment.owner_id, &deployment.id);
+    async fn exists(&self, deployment: &Deployment) -> Result<bool, DeploymentError> {
+        let key = artifact_key(deployment);
         let resp = self
             .client
             .head_object()
@@ -48,12 +392,8 @@ impl TDeploymentArtifactsRepository for S3DeploymentArtifactsRepository {
         Ok(resp.is_ok())
     }
 
-    async fn save(
-        &self,
-        deployment: &Deployment,
-        artifact: DeploymentArtifact,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let key = format!("{}-{}.zip", &deployment.owner_id, &deployment.id);
+    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), DeploymentError> {
+        let key = artifact_key(deployment);
         self.client
             .put_object()
             .bucket(&self.bucket_name)
@@ -61,20 +401,21 @@ impl TDeploymentArtifactsRepository for S3DeploymentArtifactsRepository {
             .key(&key)
             .body(ByteStream::from(artifact))
             .send()
-            .await?;
+            .await
+            .map_err(DeploymentError::storage)?;
 
         Ok(())
     }
 
-    async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
-        let key = format!("{}-{}.zip", &deployment.owner_id, &deployment.id);
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        let key = artifact_key(deployment);
         self.client
             .delete_object()
             .bucket(&self.bucket_name)
             .key(&key)
             .send()
             .await
-            .map_err(|err| err.to_string())?;
+            .map_err(DeploymentError::storage)?;
 
         Ok(())
     }
@@ -0,0 +1,143 @@
+use super::{
+    ArtifactChecksum, FsArtifactUpload, FsDeploymentArtifactsRepository, PresignedUrl, S3ArtifactUpload,
+    S3DeploymentArtifactsRepository, SpooledArtifact,
+};
+use opraas_core::domain::{Deployment, DeploymentArtifact, DeploymentError, TDeploymentArtifactsRepository};
+use std::{path::Path, time::Duration};
+
+/// Artifacts storage backend picked at startup
+#[derive(Clone)]
+pub enum ArtifactStorage {
+    S3(S3DeploymentArtifactsRepository),
+    Fs(FsDeploymentArtifactsRepository),
+}
+
+pub enum ArtifactUpload {
+    S3(S3ArtifactUpload),
+    Fs(FsArtifactUpload),
+}
+
+impl ArtifactStorage {
+    pub async fn start_upload(&self, deployment: &Deployment) -> Result<ArtifactUpload, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(repo) => Ok(ArtifactUpload::S3(repo.start_upload(deployment).await?)),
+            Self::Fs(repo) => Ok(ArtifactUpload::Fs(repo.start_upload(deployment).await?)),
+        }
+    }
+
+    /// None if the backend can't be reached directly by clients
+    pub async fn presign_upload(
+        &self,
+        deployment: &Deployment,
+        size: u64,
+        ttl: Duration,
+    ) -> Result<Option<PresignedUrl>, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(repo) => Ok(Some(repo.presign_upload(deployment, size, ttl).await?)),
+            Self::Fs(_) => Ok(None),
+        }
+    }
+
+    /// None if the backend can't be reached directly by clients
+    pub async fn presign_download(
+        &self,
+        deployment: &Deployment,
+        ttl: Duration,
+    ) -> Result<Option<PresignedUrl>, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(repo) => Ok(Some(repo.presign_download(deployment, ttl).await?)),
+            Self::Fs(_) => Ok(None),
+        }
+    }
+
+    /// Artifact uploaded through a presigned url and not yet published, none if there's nothing staged
+    pub async fn find_staged(
+        &self,
+        deployment: &Deployment,
+    ) -> Result<Option<SpooledArtifact>, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(repo) => repo.find_staged(deployment).await,
+            Self::Fs(_) => Ok(None),
+        }
+    }
+
+    /// False if the staged artifact changed since it was spooled
+    pub async fn publish_staged(
+        &self,
+        deployment: &Deployment,
+        spooled: &SpooledArtifact,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(repo) => repo.publish_staged(deployment, spooled).await,
+            Self::Fs(_) => Err("Artifact storage does not stage uploads".into()),
+        }
+    }
+
+    pub async fn delete_staged(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(repo) => repo.delete_staged(deployment).await,
+            Self::Fs(_) => Ok(()),
+        }
+    }
+}
+
+impl ArtifactUpload {
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(upload) => upload.write(chunk).await,
+            Self::Fs(upload) => upload.write(chunk).await,
+        }
+    }
+
+    pub fn spooled(&mut self) -> Result<&Path, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(upload) => upload.spooled(),
+            Self::Fs(upload) => upload.spooled(),
+        }
+    }
+
+    pub async fn complete(self) -> Result<ArtifactChecksum, Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(upload) => upload.complete().await,
+            Self::Fs(upload) => upload.complete().await,
+        }
+    }
+
+    pub async fn abort(self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::S3(upload) => upload.abort().await,
+            Self::Fs(upload) => upload.abort().await,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TDeploymentArtifactsRepository for ArtifactStorage {
+    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError> {
+        match self {
+            Self::S3(repo) => repo.find_one(deployment).await,
+            Self::Fs(repo) => repo.find_one(deployment).await,
+        }
+    }
+
+    async fn exists(&self, deployment: &Deployment) -> Result<bool, DeploymentError> {
+        match self {
+            Self::S3(repo) => repo.exists(deployment).await,
+            Self::Fs(repo) => repo.exists(deployment).await,
+        }
+    }
+
+    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), DeploymentError> {
+        match self {
+            Self::S3(repo) => repo.save(deployment, artifact).await,
+            Self::Fs(repo) => repo.save(deployment, artifact).await,
+        }
+    }
+
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        match self {
+            Self::S3(repo) => repo.delete(deployment).await,
+            Self::Fs(repo) => repo.delete(deployment).await,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use super::{artifact_key, ArtifactChecksum};
+use opraas_core::domain::{Deployment, DeploymentArtifact, DeploymentError, TDeploymentArtifactsRepository};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+
+/// Artifacts stored as plain files under a root directory, for self hosted consoles
+#[derive(Clone)]
+pub struct FsDeploymentArtifactsRepository {
+    root: PathBuf,
+}
+
+/// Writes the artifact to a temporary file next to its destination, moved in place on `complete`
+pub struct FsArtifactUpload {
+    path: PathBuf,
+    spool: NamedTempFile,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl FsDeploymentArtifactsRepository {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn artifact_path(&self, deployment: &Deployment) -> PathBuf {
+        self.root.join(artifact_key(deployment))
+    }
+
+    pub async fn start_upload(&self, deployment: &Deployment) -> Result<FsArtifactUpload, Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.root)?;
+
+        Ok(FsArtifactUpload {
+            path: self.artifact_path(deployment),
+            spool: NamedTempFile::new_in(&self.root)?,
+            hasher: Sha256::new(),
+            size: 0,
+        })
+    }
+}
+
+impl FsArtifactUpload {
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.hasher.update(chunk);
+        self.spool.write_all(chunk)?;
+        self.size += chunk.len() as u64;
+
+        Ok(())
+    }
+
+    pub fn spooled(&mut self) -> Result<&Path, Box<dyn std::error::Error>> {
+        self.spool.flush()?;
+
+        Ok(self.spool.path())
+    }
+
+    pub async fn complete(self) -> Result<ArtifactChecksum, Box<dyn std::error::Error>> {
+        self.spool.persist(&self.path)?;
+
+        Ok(ArtifactChecksum {
+            sha256: hex::encode(self.hasher.finalize()),
+            size: self.size,
+        })
+    }
+
+    /// The temporary file is removed on drop
+    pub async fn abort(self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TDeploymentArtifactsRepository for FsDeploymentArtifactsRepository {
+    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError> {
+        match fs::read(self.artifact_path(deployment)) {
+            Ok(artifact) => Ok(Some(artifact)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn exists(&self, deployment: &Deployment) -> Result<bool, DeploymentError> {
+        Ok(self.artifact_path(deployment).is_file())
+    }
+
+    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), DeploymentError> {
+        fs::create_dir_all(&self.root)?;
+
+        // write then rename so readers never see a partial artifact
+        let mut spool = NamedTempFile::new_in(&self.root)?;
+        spool.write_all(&artifact)?;
+        spool
+            .persist(self.artifact_path(deployment))
+            .map_err(|e| e.error)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        match fs::remove_file(self.artifact_path(deployment)) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
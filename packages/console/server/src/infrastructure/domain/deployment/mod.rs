@@ -1,5 +1,9 @@
 pub mod artifact_repo;
+pub mod artifact_storage;
+pub mod fs_artifact_repo;
 pub mod repo;
 
 pub use artifact_repo::*;
+pub use artifact_storage::*;
+pub use fs_artifact_repo::*;
 pub use repo::*;
@@ -1,10 +1,32 @@
-use crate::infrastructure::database::DbPool;
-use opraas_core::domain::{Deployment, TDeploymentRepository};
+use super::ArtifactChecksum;
+use crate::infrastructure::database::{with_pool, DbPool};
+use opraas_core::domain::{Deployment, DeploymentError, TDeploymentRepository};
+use serde::Serialize;
+use utoipa::ToSchema;
 
+/// Deployments stored as immutable revisions, reads return the head revision
 pub struct SqlDeploymentRepository {
     client: DbPool,
 }
 
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeploymentRevision {
+    pub revision: i32,
+    pub created_at: i64,
+    pub deployment: Deployment,
+}
+
+const DEPLOYMENT_COLUMNS: &str = "d.id, d.owner_id, r.name, r.release_tag, r.release_registry, \
+    r.infra_base_url, r.infra_outputs, r.contracts_addresses, r.network_config, r.accounts_config";
+
+#[derive(Debug, sqlx::FromRow)]
+struct DeploymentRevisionDto {
+    pub revision: i32,
+    pub created_at: i64,
+    #[sqlx(flatten)]
+    pub deployment: DeploymentDto,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 struct DeploymentDto {
     pub id: String,
@@ -13,24 +35,32 @@ struct DeploymentDto {
     pub release_tag: String,
     pub release_registry: String,
     pub infra_base_url: Option<String>,
+    pub infra_outputs: Option<String>,
     pub contracts_addresses: Option<String>,
     pub network_config: String,
     pub accounts_config: String,
 }
 
-impl From<DeploymentDto> for Deployment {
-    fn from(deployment: DeploymentDto) -> Self {
-        Self {
+/// Fails on json columns that no longer parse into the deployment config
+impl TryFrom<DeploymentDto> for Deployment {
+    type Error = serde_json::Error;
+
+    fn try_from(deployment: DeploymentDto) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: deployment.id,
             name: deployment.name,
             owner_id: deployment.owner_id,
             release_tag: deployment.release_tag,
             release_registry: deployment.release_registry,
             infra_base_url: deployment.infra_base_url,
+            infra_outputs: deployment
+                .infra_outputs
+                .map(|outputs| serde_json::from_str(&outputs))
+                .transpose()?,
             contracts_addresses: deployment.contracts_addresses,
-            network_config: serde_json::from_str(&deployment.network_config).unwrap(),
-            accounts_config: serde_json::from_str(&deployment.accounts_config).unwrap(),
-        }
+            network_config: serde_json::from_str(&deployment.network_config)?,
+            accounts_config: serde_json::from_str(&deployment.accounts_config)?,
+        })
     }
 }
 
@@ -43,6 +73,9 @@ impl From<Deployment> for DeploymentDto {
             release_tag: deployment.release_tag,
             release_registry: deployment.release_registry,
             infra_base_url: deployment.infra_base_url,
+            infra_outputs: deployment
+                .infra_outputs
+                .map(|outputs| serde_json::to_string(&outputs).unwrap()),
             contracts_addresses: deployment.contracts_addresses,
             network_config: serde_json::to_string(&deployment.network_config).unwrap(),
             accounts_config: serde_json::to_string(&deployment.accounts_config).unwrap(),
@@ -50,94 +83,285 @@ impl From<Deployment> for DeploymentDto {
     }
 }
 
+impl TryFrom<DeploymentRevisionDto> for DeploymentRevision {
+    type Error = serde_json::Error;
+
+    fn try_from(revision: DeploymentRevisionDto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            revision: revision.revision,
+            created_at: revision.created_at,
+            deployment: revision.deployment.try_into()?,
+        })
+    }
+}
+
 impl SqlDeploymentRepository {
     pub fn new(client: DbPool) -> Self {
         Self { client }
     }
-}
 
-#[async_trait::async_trait]
-impl TDeploymentRepository for SqlDeploymentRepository {
-    async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, Box<dyn std::error::Error>> {
-        let result: DeploymentDto = sqlx::query_as("SELECT * FROM deployments WHERE id = $1")
+    /// Every revision of the deployment, newest first
+    pub async fn find_revisions(&self, id: &str) -> Result<Vec<DeploymentRevision>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT r.revision, {} AS created_at, {} \
+            FROM deployments d JOIN deployment_revisions r ON r.deployment_id = d.id \
+            WHERE d.id = $1 ORDER BY r.revision DESC",
+            self.client.epoch("r.created_at"),
+            DEPLOYMENT_COLUMNS
+        );
+
+        let result: Vec<DeploymentRevisionDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select).bind(id).fetch_all(pool).await?
+        });
+
+        Ok(result
+            .into_iter()
+            .map(DeploymentRevision::try_from)
+            .collect::<Result<_, _>>()?)
+    }
+
+    pub async fn find_revision(
+        &self,
+        id: &str,
+        revision: i32,
+    ) -> Result<Option<DeploymentRevision>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT r.revision, {} AS created_at, {} \
+            FROM deployments d JOIN deployment_revisions r ON r.deployment_id = d.id \
+            WHERE d.id = $1 AND r.revision = $2",
+            self.client.epoch("r.created_at"),
+            DEPLOYMENT_COLUMNS
+        );
+
+        let result: Option<DeploymentRevisionDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(id)
+                .bind(revision)
+                .fetch_optional(pool)
+                .await?
+        });
+
+        Ok(result.map(DeploymentRevision::try_from).transpose()?)
+    }
+
+    /// Head revision, its number is the current deployment version
+    pub async fn find_head(&self, id: &str) -> Result<Option<DeploymentRevision>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT r.revision, {} AS created_at, {} \
+            FROM deployments d JOIN deployment_revisions r ON r.deployment_id = d.id AND r.revision = d.version \
+            WHERE d.id = $1",
+            self.client.epoch("r.created_at"),
+            DEPLOYMENT_COLUMNS
+        );
+
+        let result: Option<DeploymentRevisionDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select).bind(id).fetch_optional(pool).await?
+        });
+
+        Ok(result.map(DeploymentRevision::try_from).transpose()?)
+    }
+
+    /// Saves only if the deployment is still at `version`, returns the new version or none on conflict
+    pub async fn save_if_version(
+        &self,
+        deployment: &Deployment,
+        version: i32,
+    ) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+        Ok(self
+            .append_revision(deployment, Some(version), false)
+            .await?)
+    }
+
+    /// Inserts a new deployment with its first revision, ids are unique across every org
+    pub async fn create(&self, deployment: &Deployment) -> Result<i32, DeploymentError> {
+        match self.append_revision(deployment, None, true).await {
+            Ok(version) => version.ok_or(DeploymentError::NotFound(deployment.id.clone())),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Err(DeploymentError::Conflict(
+                "A deployment with given id already exists".into(),
+            )),
+            Err(e) => Err(DeploymentError::storage(e)),
+        }
+    }
+
+    pub async fn save_artifact_checksum(
+        &self,
+        id: &str,
+        checksum: &ArtifactChecksum,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        with_pool!(&self.client, pool => {
+            sqlx::query("UPDATE deployments SET artifact_sha256 = $2, artifact_size = $3 WHERE id = $1")
+                .bind(id)
+                .bind(&checksum.sha256)
+                .bind(checksum.size as i64)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    pub async fn find_artifact_checksum(
+        &self,
+        id: &str,
+    ) -> Result<Option<ArtifactChecksum>, Box<dyn std::error::Error>> {
+        let result: Option<(String, i64)> = with_pool!(&self.client, pool => {
+            sqlx::query_as(
+                "SELECT artifact_sha256, artifact_size FROM deployments \
+                WHERE id = $1 AND artifact_sha256 IS NOT NULL",
+            )
             .bind(id)
-            .fetch_one(&self.client)
-            .await?;
+            .fetch_optional(pool)
+            .await?
+        });
 
-        Ok(Some(result.into()))
+        Ok(result.map(|(sha256, size)| ArtifactChecksum {
+            sha256,
+            size: size as u64,
+        }))
     }
 
-    async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, Box<dyn std::error::Error>> {
-        let result: Vec<DeploymentDto> = sqlx::query_as("SELECT * FROM deployments WHERE owner_id = $1")
-            .bind(owner_id)
-            .fetch_all(&self.client)
-            .await?;
+    pub async fn delete_artifact_checksum(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        with_pool!(&self.client, pool => {
+            sqlx::query("UPDATE deployments SET artifact_sha256 = NULL, artifact_size = NULL WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await?;
+        });
 
-        Ok(result.into_iter().map(Deployment::from).collect())
+        Ok(())
     }
 
-    async fn save(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+    /// Appends a new revision and moves the head to it, `create` inserts the deployment first.
+    /// None if the deployment doesn't exist or isn't at `expected_version`
+    async fn append_revision(
+        &self,
+        deployment: &Deployment,
+        expected_version: Option<i32>,
+        create: bool,
+    ) -> Result<Option<i32>, sqlx::Error> {
         let deployment_dto: DeploymentDto = deployment.clone().into();
+        let bump_version = format!(
+            "UPDATE deployments SET version = version + 1 \
+            WHERE id = $1 AND ({} IS NULL OR version = $2) RETURNING version",
+            self.client.cast("$2", "INTEGER")
+        );
 
-        let exists: Option<String> = sqlx::query_scalar!(
-            "SELECT id FROM deployments WHERE id = $1",
-            deployment_dto.id,
-        )
-        .fetch_optional(&self.client)
-        .await?;
-
-        if exists.is_some() {
-            sqlx::query!(
-                r#"
-                UPDATE deployments 
-                SET 
-                    owner_id = $2,
-                    name = $3,
-                    release_tag = $4,
-                    release_registry = $5,
-                    infra_base_url = $6,
-                    contracts_addresses = $7,
-                    network_config = $8,
-                    accounts_config = $9
-                WHERE id = $1
-                "#,
-                deployment_dto.id,
-                deployment_dto.owner_id,
-                deployment_dto.name,
-                deployment_dto.release_tag,
-                deployment_dto.release_registry,
-                deployment_dto.infra_base_url,
-                deployment_dto.contracts_addresses,
-                deployment_dto.network_config,
-                deployment_dto.accounts_config,
-            )
-            .execute(&self.client)
-            .await?;
-        } else {
-            sqlx::query!(
-                "INSERT INTO deployments (id, name, owner_id, release_tag, release_registry, infra_base_url, contracts_addresses, network_config, accounts_config)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
-                deployment_dto.id,
-                deployment_dto.name,
-                deployment_dto.owner_id,
-                deployment_dto.release_tag,
-                deployment_dto.release_registry,
-                deployment_dto.infra_base_url,
-                deployment_dto.contracts_addresses,
-                deployment_dto.network_config,
-                deployment_dto.accounts_config,
+        let version = with_pool!(&self.client, pool => {
+            let mut tx = pool.begin().await?;
+
+            // a taken id fails on the primary key, whichever org owns it. Existing
+            // deployments keep their owner, revisions never move them between orgs
+            if create {
+                sqlx::query("INSERT INTO deployments (id, owner_id, version) VALUES ($1, $2, 0)")
+                    .bind(&deployment_dto.id)
+                    .bind(&deployment_dto.owner_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            // row lock serializes concurrent saves of the same deployment
+            let version: Option<i32> = sqlx::query_scalar(&bump_version)
+                .bind(&deployment_dto.id)
+                .bind(expected_version)
+                .fetch_optional(&mut *tx)
+                .await?;
+            let Some(version) = version else {
+                tx.rollback().await?;
+                return Ok(None);
+            };
+
+            sqlx::query(
+                "INSERT INTO deployment_revisions (deployment_id, revision, name, release_tag, release_registry, \
+                infra_base_url, infra_outputs, contracts_addresses, network_config, accounts_config) \
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
             )
-            .execute(&self.client)
+            .bind(&deployment_dto.id)
+            .bind(version)
+            .bind(&deployment_dto.name)
+            .bind(&deployment_dto.release_tag)
+            .bind(&deployment_dto.release_registry)
+            .bind(&deployment_dto.infra_base_url)
+            .bind(&deployment_dto.infra_outputs)
+            .bind(&deployment_dto.contracts_addresses)
+            .bind(&deployment_dto.network_config)
+            .bind(&deployment_dto.accounts_config)
+            .execute(&mut *tx)
             .await?;
-        }
+
+            tx.commit().await?;
+
+            version
+        });
+
+        Ok(Some(version))
+    }
+}
+
+#[async_trait::async_trait]
+impl TDeploymentRepository for SqlDeploymentRepository {
+    async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, DeploymentError> {
+        let select = format!(
+            "SELECT {} FROM deployments d \
+            JOIN deployment_revisions r ON r.deployment_id = d.id AND r.revision = d.version \
+            WHERE d.id = $1",
+            DEPLOYMENT_COLUMNS
+        );
+
+        let result: Option<DeploymentDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(DeploymentError::storage)?
+        });
+
+        Ok(result.map(Deployment::try_from).transpose()?)
+    }
+
+    async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, DeploymentError> {
+        let select = format!(
+            "SELECT {} FROM deployments d \
+            JOIN deployment_revisions r ON r.deployment_id = d.id AND r.revision = d.version \
+            WHERE d.owner_id = $1",
+            DEPLOYMENT_COLUMNS
+        );
+
+        let result: Vec<DeploymentDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(owner_id)
+                .fetch_all(pool)
+                .await
+                .map_err(DeploymentError::storage)?
+        });
+
+        Ok(result
+            .into_iter()
+            .map(Deployment::try_from)
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Only saves existing deployments, `create` inserts new ones
+    async fn save(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        self.append_revision(deployment, None, false)
+            .await
+            .map_err(DeploymentError::storage)?
+            .ok_or(DeploymentError::NotFound(deployment.id.clone()))?;
 
         Ok(())
     }
 
-    async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
-        sqlx::query!("DELETE FROM deployments WHERE id = $1", deployment.id)
-            .execute(&self.client)
-            .await?;
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        let deleted = with_pool!(&self.client, pool => {
+            sqlx::query("DELETE FROM deployments WHERE id = $1")
+                .bind(&deployment.id)
+                .execute(pool)
+                .await
+                .map_err(DeploymentError::storage)?
+                .rows_affected()
+        });
+        if deleted == 0 {
+            return Err(DeploymentError::NotFound(deployment.id.clone()));
+        }
 
         Ok(())
     }
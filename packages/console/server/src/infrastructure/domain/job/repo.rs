@@ -0,0 +1,361 @@
+use crate::infrastructure::database::{with_pool, DbPool};
+use opraas_core::domain::DeploymentTls;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Deployment jobs and their logs, secrets needed to run a job are never persisted
+pub struct SqlJobRepository {
+    client: DbPool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Job {
+    pub id: String,
+    pub deployment_id: String,
+    pub spec: JobSpec,
+    pub status: JobStatus,
+    /// Failure reason, set once failed
+    pub error: Option<String>,
+    /// Set once cancelled, a running job stops at the next step
+    pub cancel_requested: bool,
+    pub created_by: String,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+/// What a job deploys
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobSpec {
+    /// Deploys the L1 contracts, storing their addresses and artifacts in the deployment
+    Contracts {
+        #[serde(default)]
+        deploy_deterministic_deployer: bool,
+    },
+    /// Deploys the chain infrastructure, the contracts must have been deployed first
+    Infra(InfraJobSpec),
+}
+
+/// Same options as `opruaas deploy infra`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InfraJobSpec {
+    /// Domain the chain is exposed at
+    pub host: String,
+    #[serde(default)]
+    pub replica: bool,
+    /// Required for replicas
+    pub sequencer_url: Option<String>,
+    #[serde(default)]
+    pub monitoring: bool,
+    #[serde(default)]
+    pub explorer: bool,
+    #[serde(default = "defaults::storage_class_name")]
+    pub storage_class_name: String,
+    #[serde(default = "defaults::release_name")]
+    pub release_tag: String,
+    #[serde(default = "defaults::release_name")]
+    pub release_namespace: String,
+    /// Let's Encrypt production certificates if omitted
+    #[serde(default)]
+    pub tls: DeploymentTls,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct JobLog {
+    /// Monotonic, sent as the event id of the log stream
+    pub id: i64,
+    pub line: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct JobDto {
+    pub id: String,
+    pub deployment_id: String,
+    pub spec: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub cancel_requested: bool,
+    pub created_by: String,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+mod defaults {
+    pub fn storage_class_name() -> String {
+        "gp2".to_string()
+    }
+
+    pub fn release_name() -> String {
+        "opruaas".to_string()
+    }
+}
+
+// implementations =============================================
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "succeeded" => Ok(JobStatus::Succeeded),
+            "failed" => Ok(JobStatus::Failed),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            _ => Err(format!("Unknown job status {}", s)),
+        }
+    }
+}
+
+impl TryFrom<JobDto> for Job {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(job: JobDto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: job.id,
+            deployment_id: job.deployment_id,
+            spec: serde_json::from_str(&job.spec)?,
+            status: job.status.parse()?,
+            error: job.error,
+            cancel_requested: job.cancel_requested,
+            created_by: job.created_by,
+            created_at: job.created_at,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+        })
+    }
+}
+
+impl SqlJobRepository {
+    pub fn new(client: DbPool) -> Self {
+        Self { client }
+    }
+
+    fn columns(&self) -> String {
+        format!(
+            "id, deployment_id, {} AS spec, status, error, cancel_requested, created_by, \
+            {} AS created_at, {} AS started_at, {} AS finished_at",
+            self.client.cast("spec", "TEXT"),
+            self.client.epoch("created_at"),
+            self.client.epoch("started_at"),
+            self.client.epoch("finished_at"),
+        )
+    }
+
+    /// Queues a job, none if another one is queued or running for the deployment
+    pub async fn create(
+        &self,
+        id: &str,
+        deployment_id: &str,
+        spec: &JobSpec,
+        created_by: &str,
+    ) -> Result<Option<Job>, Box<dyn std::error::Error>> {
+        let insert = format!(
+            "INSERT INTO jobs (id, deployment_id, spec, status, created_by) \
+            VALUES ($1, $2, {}, $4, $5) RETURNING {}",
+            self.client.cast("$3", "JSONB"),
+            self.columns()
+        );
+        let spec = serde_json::to_string(spec)?;
+
+        let result: Result<JobDto, sqlx::Error> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&insert)
+                .bind(id)
+                .bind(deployment_id)
+                .bind(&spec)
+                .bind(JobStatus::Queued.as_str())
+                .bind(created_by)
+                .fetch_one(pool)
+                .await
+        });
+
+        // jobs_in_progress_idx allows a single unfinished job per deployment
+        match result {
+            Ok(job) => Ok(Some(job.try_into()?)),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<Job>, Box<dyn std::error::Error>> {
+        let select = format!("SELECT {} FROM jobs WHERE id = $1", self.columns());
+
+        let job: Option<JobDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(id)
+                .fetch_optional(pool)
+                .await?
+        });
+
+        job.map(Job::try_from).transpose()
+    }
+
+    /// Newest first
+    pub async fn find_by_deployment(&self, deployment_id: &str) -> Result<Vec<Job>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT {} FROM jobs WHERE deployment_id = $1 ORDER BY created_at DESC, id",
+            self.columns()
+        );
+
+        let jobs: Vec<JobDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(deployment_id)
+                .fetch_all(pool)
+                .await?
+        });
+
+        jobs.into_iter().map(Job::try_from).collect()
+    }
+
+    /// Moves a queued job to running, false if it was cancelled while queued
+    pub async fn start(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE jobs SET status = $1, started_at = {} WHERE id = $2 AND status = $3",
+            self.client.now()
+        );
+
+        let result = with_pool!(&self.client, pool => {
+            sqlx::query(&update)
+                .bind(JobStatus::Running.as_str())
+                .bind(id)
+                .bind(JobStatus::Queued.as_str())
+                .execute(pool)
+                .await?
+                .rows_affected()
+        });
+
+        Ok(result > 0)
+    }
+
+    pub async fn finish(
+        &self,
+        id: &str,
+        status: JobStatus,
+        error: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE jobs SET status = $1, error = $2, finished_at = {} WHERE id = $3",
+            self.client.now()
+        );
+
+        with_pool!(&self.client, pool => {
+            sqlx::query(&update)
+                .bind(status.as_str())
+                .bind(error)
+                .bind(id)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    /// Flags the job for cancellation, queued jobs are cancelled right away.
+    /// Returns the job as updated, none if already finished
+    pub async fn request_cancel(&self, id: &str) -> Result<Option<Job>, Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE jobs SET cancel_requested = TRUE, \
+            status = CASE WHEN status = $1 THEN $2 ELSE status END, \
+            finished_at = CASE WHEN status = $1 THEN {} ELSE finished_at END \
+            WHERE id = $3 AND status IN ($1, $4) RETURNING {}",
+            self.client.now(),
+            self.columns()
+        );
+
+        let job: Option<JobDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&update)
+                .bind(JobStatus::Queued.as_str())
+                .bind(JobStatus::Cancelled.as_str())
+                .bind(id)
+                .bind(JobStatus::Running.as_str())
+                .fetch_optional(pool)
+                .await?
+        });
+
+        job.map(Job::try_from).transpose()
+    }
+
+    /// Fails jobs left queued or running by a previous server process, their secrets are gone with it
+    pub async fn fail_unfinished(&self, error: &str) -> Result<u64, Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE jobs SET status = $1, error = $2, finished_at = {} WHERE status IN ($3, $4)",
+            self.client.now()
+        );
+
+        let result = with_pool!(&self.client, pool => {
+            sqlx::query(&update)
+                .bind(JobStatus::Failed.as_str())
+                .bind(error)
+                .bind(JobStatus::Queued.as_str())
+                .bind(JobStatus::Running.as_str())
+                .execute(pool)
+                .await?
+                .rows_affected()
+        });
+
+        Ok(result)
+    }
+
+    pub async fn append_log(&self, job_id: &str, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+        with_pool!(&self.client, pool => {
+            sqlx::query("INSERT INTO job_logs (job_id, line) VALUES ($1, $2)")
+                .bind(job_id)
+                .bind(line)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    /// Oldest first, `after` is the id of the last line already read
+    pub async fn find_logs(&self, job_id: &str, after: Option<i64>) -> Result<Vec<JobLog>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT id, line, {} AS created_at FROM job_logs \
+            WHERE job_id = $1 AND id > $2 ORDER BY id",
+            self.client.epoch("created_at")
+        );
+
+        let logs: Vec<JobLog> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(job_id)
+                .bind(after.unwrap_or(0))
+                .fetch_all(pool)
+                .await?
+        });
+
+        Ok(logs)
+    }
+}
@@ -1 +1,6 @@
+pub mod audit;
+pub mod auth;
 pub mod deployment;
+pub mod job;
+pub mod org;
+pub mod webhook;
@@ -0,0 +1,133 @@
+use crate::infrastructure::database::{with_pool, DbPool};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+const PERSONAL_ORG_NAME: &str = "Personal";
+
+pub struct SqlOrgRepository {
+    client: DbPool,
+}
+
+/// Org as seen by one of its members
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct OrgMembership {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct OrgMember {
+    pub org_id: String,
+    pub user_id: String,
+    pub role: String,
+    pub created_at: i64,
+}
+
+impl SqlOrgRepository {
+    pub fn new(client: DbPool) -> Self {
+        Self { client }
+    }
+
+    /// Creates the org owned by `owner_id`
+    pub async fn create(&self, id: &str, name: &str, owner_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        with_pool!(&self.client, pool => {
+            let mut tx = pool.begin().await?;
+
+            sqlx::query("INSERT INTO orgs (id, name) VALUES ($1, $2) ON CONFLICT (id) DO NOTHING")
+                .bind(id)
+                .bind(name)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "INSERT INTO org_members (org_id, user_id, role) VALUES ($1, $2, 'owner') ON CONFLICT (org_id, user_id) DO NOTHING",
+            )
+            .bind(id)
+            .bind(owner_id)
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+        });
+
+        Ok(())
+    }
+
+    /// Every user has a personal org, with the same id as the user, created on first use
+    pub async fn ensure_personal(&self, user_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.create(user_id, PERSONAL_ORG_NAME, user_id).await?;
+
+        Ok(user_id.to_string())
+    }
+
+    pub async fn find_by_member(&self, user_id: &str) -> Result<Vec<OrgMembership>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT o.id, o.name, m.role, {} AS created_at \
+            FROM orgs o JOIN org_members m ON m.org_id = o.id WHERE m.user_id = $1 ORDER BY o.created_at",
+            self.client.epoch("o.created_at")
+        );
+
+        let orgs: Vec<OrgMembership> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select).bind(user_id).fetch_all(pool).await?
+        });
+
+        Ok(orgs)
+    }
+
+    pub async fn find_role(&self, org_id: &str, user_id: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let role: Option<(String,)> = with_pool!(&self.client, pool => {
+            sqlx::query_as("SELECT role FROM org_members WHERE org_id = $1 AND user_id = $2")
+                .bind(org_id)
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+        });
+
+        Ok(role.map(|(role,)| role))
+    }
+
+    pub async fn find_members(&self, org_id: &str) -> Result<Vec<OrgMember>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT org_id, user_id, role, {} AS created_at \
+            FROM org_members WHERE org_id = $1 ORDER BY created_at",
+            self.client.epoch("created_at")
+        );
+
+        let members: Vec<OrgMember> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select).bind(org_id).fetch_all(pool).await?
+        });
+
+        Ok(members)
+    }
+
+    /// Adds the member or updates its role
+    pub async fn save_member(&self, org_id: &str, user_id: &str, role: &str) -> Result<(), Box<dyn std::error::Error>> {
+        with_pool!(&self.client, pool => {
+            sqlx::query(
+                "INSERT INTO org_members (org_id, user_id, role) VALUES ($1, $2, $3) \
+                ON CONFLICT (org_id, user_id) DO UPDATE SET role = EXCLUDED.role",
+            )
+            .bind(org_id)
+            .bind(user_id)
+            .bind(role)
+            .execute(pool)
+            .await?;
+        });
+
+        Ok(())
+    }
+
+    pub async fn delete_member(&self, org_id: &str, user_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let result = with_pool!(&self.client, pool => {
+            sqlx::query("DELETE FROM org_members WHERE org_id = $1 AND user_id = $2")
+                .bind(org_id)
+                .bind(user_id)
+                .execute(pool)
+                .await?
+                .rows_affected()
+        });
+
+        Ok(result > 0)
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    infrastructure::{
+        database::{with_pool, DbPool},
+        domain::audit::AuditEvent,
+    },
+    utils::audit::FieldChange,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Events webhooks can subscribe to, named after the audit actions that trigger them
+pub const WEBHOOK_EVENTS: [&str; 9] = [
+    "deployment.created",
+    "deployment.updated",
+    "deployment.restored",
+    "deployment.deleted",
+    "deployment.deployed",
+    "artifact.uploaded",
+    "artifact.deleted",
+    "job.created",
+    "job.cancelled",
+];
+
+/// Org webhooks and the queue of their deliveries
+pub struct SqlWebhookRepository {
+    client: DbPool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Webhook {
+    pub id: String,
+    pub org_id: String,
+    pub url: String,
+    /// Subscribed events, empty for every event
+    pub events: Vec<String>,
+    /// Signs deliveries, never listed
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    #[schema(value_type = WebhookPayload)]
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// When the next attempt is due, meaningless once delivered or failed
+    pub next_attempt_at: i64,
+    /// Status code of the last attempt, none if the request failed
+    pub response_status: Option<i32>,
+    /// Failure of the last attempt
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub delivered_at: Option<i64>,
+}
+
+/// Body of every delivery, the audit event that triggered it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookPayload {
+    pub event: String,
+    pub org_id: String,
+    pub deployment_id: String,
+    pub actor_id: String,
+    /// Changed fields by path, each with its value before and after
+    #[schema(value_type = std::collections::HashMap<String, FieldChange>)]
+    pub diff: serde_json::Value,
+    pub request_id: Option<String>,
+    /// Id of the audit event, see `/deployments/{id}/audit`
+    pub audit_event_id: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    /// Gave up after the last retry
+    Failed,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct WebhookDto {
+    pub id: String,
+    pub org_id: String,
+    pub url: String,
+    pub events: String,
+    pub secret: String,
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct WebhookDeliveryDto {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: i64,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub delivered_at: Option<i64>,
+}
+
+// implementations =============================================
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Succeeded => "succeeded",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "succeeded" => Ok(DeliveryStatus::Succeeded),
+            "failed" => Ok(DeliveryStatus::Failed),
+            _ => Err(format!("Unknown delivery status {}", s)),
+        }
+    }
+}
+
+impl WebhookPayload {
+    pub fn new(org_id: &str, event: &AuditEvent) -> Self {
+        Self {
+            event: event.action.clone(),
+            org_id: org_id.to_string(),
+            deployment_id: event.deployment_id.clone(),
+            actor_id: event.actor_id.clone(),
+            diff: event.diff.clone(),
+            request_id: event.request_id.clone(),
+            audit_event_id: event.id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+impl From<WebhookDto> for Webhook {
+    fn from(webhook: WebhookDto) -> Self {
+        Self {
+            id: webhook.id,
+            org_id: webhook.org_id,
+            url: webhook.url,
+            events: serde_json::from_str(&webhook.events).unwrap_or_default(),
+            secret: webhook.secret,
+            created_by: webhook.created_by,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+impl TryFrom<WebhookDeliveryDto> for WebhookDelivery {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(delivery: WebhookDeliveryDto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: delivery.id,
+            webhook_id: delivery.webhook_id,
+            event: delivery.event,
+            payload: serde_json::from_str(&delivery.payload)?,
+            status: delivery.status.parse()?,
+            attempts: delivery.attempts,
+            next_attempt_at: delivery.next_attempt_at,
+            response_status: delivery.response_status,
+            error: delivery.error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        })
+    }
+}
+
+impl SqlWebhookRepository {
+    pub fn new(client: DbPool) -> Self {
+        Self { client }
+    }
+
+    fn columns(&self) -> String {
+        format!(
+            "id, org_id, url, events, secret, created_by, {} AS created_at",
+            self.client.epoch("created_at"),
+        )
+    }
+
+    fn delivery_columns(&self) -> String {
+        format!(
+            "id, webhook_id, event, {} AS payload, status, attempts, {} AS next_attempt_at, \
+            response_status, error, {} AS created_at, {} AS delivered_at",
+            self.client.cast("payload", "TEXT"),
+            self.client.epoch("next_attempt_at"),
+            self.client.epoch("created_at"),
+            self.client.epoch("delivered_at"),
+        )
+    }
+
+    pub async fn create(
+        &self,
+        id: &str,
+        org_id: &str,
+        url: &str,
+        secret: &str,
+        events: &[String],
+        created_by: &str,
+    ) -> Result<Webhook, Box<dyn std::error::Error>> {
+        let insert = format!(
+            "INSERT INTO webhooks (id, org_id, url, secret, events, created_by) \
+            VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+            self.columns()
+        );
+        let events = serde_json::to_string(events)?;
+
+        let webhook: WebhookDto = with_pool!(&self.client, pool => {
+            sqlx::query_as(&insert)
+                .bind(id)
+                .bind(org_id)
+                .bind(url)
+                .bind(secret)
+                .bind(&events)
+                .bind(created_by)
+                .fetch_one(pool)
+                .await?
+        });
+
+        Ok(webhook.into())
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<Webhook>, Box<dyn std::error::Error>> {
+        let select = format!("SELECT {} FROM webhooks WHERE id = $1", self.columns());
+
+        let webhook: Option<WebhookDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select).bind(id).fetch_optional(pool).await?
+        });
+
+        Ok(webhook.map(Webhook::from))
+    }
+
+    pub async fn find_by_org(&self, org_id: &str) -> Result<Vec<Webhook>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT {} FROM webhooks WHERE org_id = $1 ORDER BY created_at, id",
+            self.columns()
+        );
+
+        let webhooks: Vec<WebhookDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select).bind(org_id).fetch_all(pool).await?
+        });
+
+        Ok(webhooks.into_iter().map(Webhook::from).collect())
+    }
+
+    /// Deletes the webhook along with its deliveries
+    pub async fn delete(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let result = with_pool!(&self.client, pool => {
+            sqlx::query("DELETE FROM webhooks WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await?
+                .rows_affected()
+        });
+
+        Ok(result > 0)
+    }
+
+    /// Queues a delivery of the event to every org webhook subscribed to it, returns how many were queued
+    pub async fn enqueue(&self, org_id: &str, event: &AuditEvent) -> Result<usize, Box<dyn std::error::Error>> {
+        let webhooks: Vec<Webhook> = self
+            .find_by_org(org_id)
+            .await?
+            .into_iter()
+            .filter(|webhook| webhook.subscribes_to(&event.action))
+            .collect();
+        if webhooks.is_empty() {
+            return Ok(0);
+        }
+
+        let insert = format!(
+            "INSERT INTO webhook_deliveries (id, webhook_id, event, payload, status) \
+            VALUES ($1, $2, $3, {}, $5)",
+            self.client.cast("$4", "JSONB")
+        );
+        let payload = serde_json::to_string(&WebhookPayload::new(org_id, event))?;
+
+        with_pool!(&self.client, pool => {
+            let mut tx = pool.begin().await?;
+
+            for webhook in &webhooks {
+                sqlx::query(&insert)
+                    .bind(uuid::Uuid::new_v4().to_string())
+                    .bind(&webhook.id)
+                    .bind(&event.action)
+                    .bind(&payload)
+                    .bind(DeliveryStatus::Pending.as_str())
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            tx.commit().await?;
+        });
+
+        Ok(webhooks.len())
+    }
+
+    /// Claims up to `limit` pending deliveries due now, pushing their next attempt to `lease_until`
+    /// so a crashed attempt is retried but concurrent dispatchers skip them meanwhile
+    pub async fn claim_due(
+        &self,
+        limit: i64,
+        lease_until: i64,
+    ) -> Result<Vec<WebhookDelivery>, Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE webhook_deliveries SET next_attempt_at = {lease} \
+            WHERE status = $2 AND next_attempt_at <= {now} AND id IN ( \
+                SELECT id FROM webhook_deliveries WHERE status = $2 AND next_attempt_at <= {now} \
+                ORDER BY next_attempt_at LIMIT $3 \
+            ) RETURNING {columns}",
+            lease = self.client.timestamp("$1"),
+            now = self.client.now(),
+            columns = self.delivery_columns()
+        );
+
+        let deliveries: Vec<WebhookDeliveryDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&update)
+                .bind(lease_until as f64)
+                .bind(DeliveryStatus::Pending.as_str())
+                .bind(limit)
+                .fetch_all(pool)
+                .await?
+        });
+
+        deliveries
+            .into_iter()
+            .map(WebhookDelivery::try_from)
+            .collect()
+    }
+
+    pub async fn succeed(&self, id: &str, response_status: i32) -> Result<(), Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE webhook_deliveries SET status = $1, attempts = attempts + 1, response_status = $2, \
+            error = NULL, delivered_at = {} WHERE id = $3",
+            self.client.now()
+        );
+
+        with_pool!(&self.client, pool => {
+            sqlx::query(&update)
+                .bind(DeliveryStatus::Succeeded.as_str())
+                .bind(response_status)
+                .bind(id)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    /// Records a failed attempt, retried at `retry_at` or failed for good if none
+    pub async fn fail_attempt(
+        &self,
+        id: &str,
+        response_status: Option<i32>,
+        error: &str,
+        retry_at: Option<i64>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let update = format!(
+            "UPDATE webhook_deliveries SET status = $1, attempts = attempts + 1, response_status = $2, \
+            error = $3, next_attempt_at = COALESCE({}, next_attempt_at) WHERE id = $5",
+            self.client.timestamp("$4"),
+        );
+        let status = match retry_at {
+            Some(_) => DeliveryStatus::Pending,
+            None => DeliveryStatus::Failed,
+        };
+
+        with_pool!(&self.client, pool => {
+            sqlx::query(&update)
+                .bind(status.as_str())
+                .bind(response_status)
+                .bind(error)
+                .bind(retry_at.map(|t| t as f64))
+                .bind(id)
+                .execute(pool)
+                .await?;
+        });
+
+        Ok(())
+    }
+
+    /// Newest first, optionally only those in `status`
+    pub async fn find_deliveries(
+        &self,
+        webhook_id: &str,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, Box<dyn std::error::Error>> {
+        let select = format!(
+            "SELECT {} FROM webhook_deliveries WHERE webhook_id = $1 AND ({} IS NULL OR status = $2) \
+            ORDER BY created_at DESC, id LIMIT $3",
+            self.delivery_columns(),
+            self.client.cast("$2", "TEXT")
+        );
+
+        let deliveries: Vec<WebhookDeliveryDto> = with_pool!(&self.client, pool => {
+            sqlx::query_as(&select)
+                .bind(webhook_id)
+                .bind(status.map(|s| s.as_str()))
+                .bind(limit)
+                .fetch_all(pool)
+                .await?
+        });
+
+        deliveries
+            .into_iter()
+            .map(WebhookDelivery::try_from)
+            .collect()
+    }
+}
@@ -0,0 +1,190 @@
+use super::{JobContext, JobSecrets};
+use crate::{
+    infrastructure::{
+        database::DbPool,
+        domain::{
+            audit::SqlAuditRepository,
+            deployment::{ArtifactStorage, SqlDeploymentRepository},
+            job::{InfraJobSpec, Job, JobSpec},
+            webhook::SqlWebhookRepository,
+        },
+    },
+    utils::audit,
+};
+use opraas_core::{
+    application::{
+        deployment::{deploy_contracts::ContractsDeployerService, deploy_infra::InfraDeployerService},
+        CreateProjectService,
+    },
+    config::{ArtifactsConfig, CoreConfig},
+    domain::{DeploymentError, DeploymentKind, DeploymentOptions, TDeploymentRepository},
+    infrastructure::{
+        deployment::{DockerContractsDeployer, TerraformBackend, TerraformDeployer},
+        project::{GitVersionControl, InMemoryProjectInfraRepository, InMemoryProjectRepository},
+        release::{DockerReleaseRepository, DockerReleaseRunner},
+    },
+};
+use std::{error::Error, sync::Arc};
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+/// Performs a job, runs on its own thread so it may block
+#[async_trait::async_trait(?Send)]
+pub trait TJobExecutor: Send + Sync {
+    async fn execute(&self, job: &Job, secrets: &JobSecrets, ctx: &JobContext) -> Result<(), Box<dyn Error>>;
+}
+
+/// Runs jobs with the same services as `opruaas deploy`, docker and terraform must be available to the server
+pub struct CoreJobExecutor {
+    db_pool: DbPool,
+    artifact_storage: ArtifactStorage,
+    terraform_backend: TerraformBackend,
+}
+
+// implementations =============================================
+
+impl CoreJobExecutor {
+    pub fn new(db_pool: DbPool, artifact_storage: ArtifactStorage, terraform_backend: TerraformBackend) -> Self {
+        Self {
+            db_pool,
+            artifact_storage,
+            terraform_backend,
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl TJobExecutor for CoreJobExecutor {
+    async fn execute(&self, job: &Job, secrets: &JobSecrets, ctx: &JobContext) -> Result<(), Box<dyn Error>> {
+        let deployments_repo = SqlDeploymentRepository::new(self.db_pool.clone());
+        let mut deployment = deployments_repo
+            .find_by_id(&job.deployment_id)
+            .await?
+            .ok_or(DeploymentError::NotFound(job.deployment_id.clone()))?;
+        let before = serde_json::to_value(&deployment)?;
+        secrets.apply(&mut deployment);
+
+        // the deployers work on a project checkout, generated from the deployment config
+        ctx.checkpoint()?;
+        ctx.log("Generating project").await;
+        let project_dir = TempDir::new()?;
+        let project = CreateProjectService::new(
+            InMemoryProjectRepository::new(),
+            GitVersionControl::new(),
+            InMemoryProjectInfraRepository::new(),
+        )
+        .create(
+            project_dir.path(),
+            &CoreConfig {
+                artifacts: ArtifactsConfig::null(),
+                accounts: deployment.accounts_config.clone(),
+                network: deployment.network_config.clone(),
+            },
+            false,
+        )?;
+
+        ctx.checkpoint()?;
+        match &job.spec {
+            JobSpec::Contracts {
+                deploy_deterministic_deployer,
+            } => {
+                ctx.log(format!(
+                    "Deploying contracts with {}/op-contracts:{}",
+                    deployment.release_registry, deployment.release_tag
+                ))
+                .await;
+
+                ContractsDeployerService::new(
+                    SqlDeploymentRepository::new(self.db_pool.clone()),
+                    self.artifact_storage.clone(),
+                    DockerContractsDeployer::new(
+                        Box::new(DockerReleaseRepository::new()),
+                        Box::new(DockerReleaseRunner::new()),
+                    ),
+                )
+                .deploy(
+                    &project,
+                    &mut deployment,
+                    *deploy_deterministic_deployer,
+                    true,
+                )
+                .await?;
+
+                ctx.log("Contracts deployed").await;
+            }
+            JobSpec::Infra(spec) => {
+                ctx.log(format!("Deploying infra to {}", spec.host)).await;
+
+                // terraform output goes to the job log as it's produced, the deployer drops the sender once done
+                let (lines, mut output) = mpsc::unbounded_channel::<String>();
+                let deploy = InfraDeployerService::new(
+                    TerraformDeployer::new(Box::new(self.artifact_storage.clone()))
+                        .with_output(Arc::new(move |_, line: &str| {
+                            let _ = lines.send(line.to_string());
+                        }))
+                        .with_cancel(ctx.cancellation())
+                        .with_backend(self.terraform_backend.clone()),
+                    SqlDeploymentRepository::new(self.db_pool.clone()),
+                    InMemoryProjectInfraRepository::new(),
+                );
+                let (deployed, _) = tokio::join!(
+                    async {
+                        let deployed = deploy
+                            .deploy(&project, &mut deployment, &spec.options())
+                            .await;
+                        drop(deploy);
+                        deployed
+                    },
+                    async {
+                        while let Some(line) = output.recv().await {
+                            ctx.log(line).await;
+                        }
+                    }
+                );
+                deployed?;
+
+                ctx.log(format!(
+                    "Infra deployed, your chain is live at {}",
+                    spec.host
+                ))
+                .await;
+            }
+        }
+
+        let event = SqlAuditRepository::new(self.db_pool.clone())
+            .append(
+                &job.created_by,
+                "deployment.deployed",
+                &deployment.id,
+                &audit::diff(&before, &serde_json::to_value(&deployment)?),
+                None,
+            )
+            .await?;
+        SqlWebhookRepository::new(self.db_pool.clone())
+            .enqueue(&deployment.owner_id, &event)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl InfraJobSpec {
+    pub fn options(&self) -> DeploymentOptions {
+        DeploymentOptions {
+            host: self.host.clone(),
+            kind: if self.replica {
+                DeploymentKind::Replica
+            } else {
+                DeploymentKind::Sequencer
+            },
+            monitoring: self.monitoring,
+            explorer: self.explorer,
+            release_tag: self.release_tag.clone(),
+            release_namespace: self.release_namespace.clone(),
+            storage_class_name: self.storage_class_name.clone(),
+            sequencer_url: self.sequencer_url.clone(),
+            values_path: None,
+            tls: self.tls.clone(),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod executor;
+pub mod runner;
+
+pub use executor::*;
+pub use runner::*;
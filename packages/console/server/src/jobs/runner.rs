@@ -0,0 +1,199 @@
+use super::TJobExecutor;
+use crate::infrastructure::domain::job::{Job, JobStatus, SqlJobRepository};
+use opraas_core::domain::Deployment;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{watch, Semaphore};
+use utoipa::ToSchema;
+
+/// Runs deployment jobs in the background, at most `workers` at a time and in submission order
+pub struct JobRunner {
+    job_repo: Arc<SqlJobRepository>,
+    executor: Arc<dyn TJobExecutor>,
+    workers: usize,
+    permits: Arc<Semaphore>,
+    cancellations: Arc<Mutex<HashMap<String, watch::Sender<bool>>>>,
+}
+
+/// Secrets a job needs, kept in memory until the job runs and never persisted.
+/// Unset values fall back to the server environment, as with the cli
+#[derive(Clone, Default, Deserialize, ToSchema)]
+pub struct JobSecrets {
+    pub l1_rpc_url: Option<String>,
+    pub deployer_private_key: Option<String>,
+}
+
+/// Handed to the executor to report progress and observe cancellation
+#[derive(Clone)]
+pub struct JobContext {
+    job_repo: Arc<SqlJobRepository>,
+    job_id: String,
+    cancelled: watch::Receiver<bool>,
+}
+
+// implementations =============================================
+
+impl JobRunner {
+    pub fn new(job_repo: Arc<SqlJobRepository>, executor: Arc<dyn TJobExecutor>, workers: usize) -> Self {
+        Self {
+            job_repo,
+            executor,
+            workers,
+            permits: Arc::new(Semaphore::new(workers)),
+            cancellations: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Jobs need a long running server, a runner without workers refuses them
+    pub fn is_enabled(&self) -> bool {
+        self.workers > 0
+    }
+
+    /// Fails the jobs a previous server process left unfinished, to be called once on startup.
+    /// A runner without workers leaves them alone, they may belong to another console sharing the database
+    pub async fn recover(&self) -> Result<u64, Box<dyn Error>> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+
+        self.job_repo
+            .fail_unfinished("Interrupted by a console restart, please submit it again")
+            .await
+    }
+
+    /// Queues the job, it starts as soon as a worker is free
+    pub fn submit(&self, job: Job, secrets: JobSecrets) {
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        self.cancellations
+            .lock()
+            .unwrap()
+            .insert(job.id.clone(), cancel_tx);
+
+        let job_repo = self.job_repo.clone();
+        let executor = self.executor.clone();
+        let permits = self.permits.clone();
+        let cancellations = self.cancellations.clone();
+        tokio::spawn(async move {
+            let job_id = job.id.clone();
+            if let Ok(_permit) = permits.acquire_owned().await {
+                run(job_repo, executor, job, secrets, cancel_rx).await;
+            }
+            cancellations.lock().unwrap().remove(&job_id);
+        });
+    }
+
+    /// Signals a running job to stop, false if it isn't running in this process
+    pub fn cancel(&self, job_id: &str) -> bool {
+        self.cancellations
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .is_some_and(|cancel| cancel.send(true).is_ok())
+    }
+}
+
+impl JobSecrets {
+    /// Overrides the deployment values read from the server environment
+    pub fn apply(&self, deployment: &mut Deployment) {
+        if let Some(l1_rpc_url) = &self.l1_rpc_url {
+            deployment.network_config.l1_rpc_url = Some(l1_rpc_url.clone());
+        }
+        if let Some(deployer_private_key) = &self.deployer_private_key {
+            deployment.accounts_config.deployer_private_key = Some(deployer_private_key.clone());
+        }
+    }
+}
+
+async fn run(
+    job_repo: Arc<SqlJobRepository>,
+    executor: Arc<dyn TJobExecutor>,
+    job: Job,
+    secrets: JobSecrets,
+    cancelled: watch::Receiver<bool>,
+) {
+    // cancelled while queued
+    match job_repo.start(&job.id).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!("Could not start job {}. {}", job.id, e);
+            return;
+        }
+    }
+
+    let ctx = JobContext {
+        job_repo: job_repo.clone(),
+        job_id: job.id.clone(),
+        cancelled,
+    };
+    ctx.log(format!("Job {} started", job.id)).await;
+
+    // deployers block on external commands, keep them off the runtime workers
+    let handle = tokio::runtime::Handle::current();
+    let job_ctx = ctx.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        handle.block_on(async {
+            executor
+                .execute(&job, &secrets, &job_ctx)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Job crashed. {}", e)));
+
+    let (status, error) = match result {
+        Ok(()) => (JobStatus::Succeeded, None),
+        Err(_) if ctx.is_cancelled() => (JobStatus::Cancelled, None),
+        Err(e) => (JobStatus::Failed, Some(e)),
+    };
+    match &error {
+        Some(e) => ctx.log(format!("Job failed. {}", e)).await,
+        None => ctx.log(format!("Job {}", status.as_str())).await,
+    }
+
+    if let Err(e) = job_repo.finish(&ctx.job_id, status, error.as_deref()).await {
+        error!("Could not finish job {}. {}", ctx.job_id, e);
+    }
+}
+
+impl JobContext {
+    /// Appends a line to the job log, failures are reported to the server log only
+    pub async fn log<T>(&self, line: T)
+    where
+        T: Into<String>,
+    {
+        let line = line.into();
+        if let Err(e) = self.job_repo.append_log(&self.job_id, &line).await {
+            error!("Could not append to job {} log. {}", self.job_id, e);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Stops the job if cancelled, to be called between steps
+    pub fn checkpoint(&self) -> Result<(), Box<dyn Error>> {
+        if self.is_cancelled() {
+            return Err("Job cancelled".into());
+        }
+
+        Ok(())
+    }
+
+    /// Turns true once the job is cancelled, for commands to be killed mid-step
+    pub fn cancellation(&self) -> watch::Receiver<bool> {
+        self.cancelled.clone()
+    }
+
+    /// Resolves once the job is cancelled
+    pub async fn cancelled(&self) {
+        let mut cancelled = self.cancelled.clone();
+        let _ = cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+}
@@ -0,0 +1,13 @@
+#[macro_use]
+extern crate log;
+
+pub mod config;
+pub mod error;
+pub mod handlers;
+pub mod infrastructure;
+pub mod jobs;
+pub mod middlewares;
+pub mod openapi;
+pub mod router;
+pub mod utils;
+pub mod webhooks;
@@ -1,32 +1,25 @@
-#[macro_use]
-extern crate log;
-
-mod error;
-mod handlers;
-mod infrastructure;
-mod middlewares;
-mod utils;
-
 use aws_config::Region;
-use axum::extract::DefaultBodyLimit;
-use axum::http::Method;
-use axum::routing::{get, post, put};
-use axum::{middleware, Extension, Router};
-use handlers::health;
-use infrastructure::database::get_db_pool;
-use infrastructure::domain::deployment::{S3DeploymentArtifactsRepository, SqlDeploymentRepository};
+use axum::http::{header, HeaderName, Method};
 use lambda_http::{run, Error};
-use opraas_core::application::deployment::manager::DeploymentManagerService;
-use opraas_core::infrastructure::project::InMemoryProjectInfraRepository;
-use opraas_core::{
-    application::CreateProjectService,
-    infrastructure::project::{GitVersionControl, InMemoryProjectRepository},
+use opraas_server::{
+    config::{ServerConfig, StorageConfig},
+    handlers,
+    infrastructure::{
+        database::DbPool,
+        domain::{
+            deployment::{ArtifactStorage, FsDeploymentArtifactsRepository, S3DeploymentArtifactsRepository},
+            job::SqlJobRepository,
+            webhook::SqlWebhookRepository,
+        },
+    },
+    jobs::{CoreJobExecutor, JobRunner},
+    middlewares::auth::AuthConfig,
+    router::router,
+    webhooks::{RetryPolicy, TargetPolicy, WebhookDispatcher},
 };
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
-use tower_http::limit::RequestBodyLimitLayer;
-use tower_http::trace::{self, TraceLayer};
-use tracing::{level_filters::LevelFilter, Level};
+use tracing::level_filters::LevelFilter;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -34,88 +27,73 @@ async fn main() -> Result<(), Error> {
         .with_max_level(LevelFilter::INFO)
         .init();
 
-    let aws_region = std::env::var("AWS_REGION").expect("AWS_REGION not set");
-    let artifacts_bucket = std::env::var("ARTIFACTS_BUCKET").expect("ARTIFACTS_BUCKET not set");
+    let config = ServerConfig::from_env().expect("Invalid server configuration");
 
-    // s3 bucket
-    let cfg = aws_config::from_env()
-        .region(Region::new(aws_region.clone()))
-        .load()
-        .await;
-    let s3_client = aws_sdk_s3::Client::new(&cfg);
+    // artifacts storage
+    let artifact_storage = match &config.storage {
+        StorageConfig::S3 { region, bucket } => {
+            let cfg = aws_config::from_env()
+                .region(Region::new(region.clone()))
+                .load()
+                .await;
+            ArtifactStorage::S3(S3DeploymentArtifactsRepository::new(
+                aws_sdk_s3::Client::new(&cfg),
+                bucket.clone(),
+            ))
+        }
+        StorageConfig::Fs { root } => ArtifactStorage::Fs(FsDeploymentArtifactsRepository::new(root.clone())),
+    };
 
     // db pool
-    let db_pool = get_db_pool()
+    let db_pool = DbPool::connect(&config.database_url)
         .await
         .expect("Unable to connect to the database");
 
     // Run database migrations
-    sqlx::migrate!()
-        .run(&db_pool)
+    db_pool
+        .migrate()
         .await
         .expect("Failed to run database migrations");
 
-    // authorizer
-    let authorizer = middlewares::auth::Authorizer::new().unwrap();
-    let authorizer_layer = middleware::from_fn(move |req, next| {
-        let authorizer = authorizer.clone();
-        async move { authorizer.authorize(req, next).await }
-    });
-
-    // services
-    let create_service = Arc::new(CreateProjectService::new(
-        InMemoryProjectRepository::new(),
-        GitVersionControl::new(),
-        InMemoryProjectInfraRepository::new(),
-    ));
-    let deployment_manager_service = Arc::new(DeploymentManagerService::new(
-        SqlDeploymentRepository::new(db_pool),
-        S3DeploymentArtifactsRepository::new(s3_client, artifacts_bucket),
+    // deployment jobs, a restart loses the secrets of unfinished ones
+    let job_runner = Arc::new(JobRunner::new(
+        Arc::new(SqlJobRepository::new(db_pool.clone())),
+        Arc::new(CoreJobExecutor::new(
+            db_pool.clone(),
+            artifact_storage.clone(),
+            config.terraform_backend.clone(),
+        )),
+        config.job_workers,
     ));
+    job_runner
+        .recover()
+        .await
+        .expect("Failed to recover unfinished jobs");
+
+    // webhooks can only reach private networks when explicitly allowed
+    let webhook_targets = TargetPolicy::from_env();
+
+    // a lambda can't work in the background, the scheduled webhook_dispatcher sends deliveries in prod
+    if !config.prod {
+        let dispatcher = Arc::new(
+            WebhookDispatcher::new(
+                Arc::new(SqlWebhookRepository::new(db_pool.clone())),
+                RetryPolicy::default(),
+            )
+            .with_targets(webhook_targets),
+        );
+        tokio::spawn(dispatcher.run());
+    }
 
-    let router = Router::new()
-        .route("/health", get(health::health))
-        .route("/projects", post(handlers::projects::create))
-        .route(
-            "/deployments",
-            get(handlers::deployments::list)
-                .layer(authorizer_layer.clone())
-                .post(handlers::deployments::create)
-                .layer(authorizer_layer.clone()),
-        )
-        .route(
-            "/deployments/{id}",
-            get(handlers::deployments::get_by_id)
-                .layer(authorizer_layer.clone())
-                .delete(handlers::deployments::delete)
-                .layer(authorizer_layer.clone())
-                .put(handlers::deployments::update)
-                .layer(authorizer_layer.clone()),
-        )
-        .route(
-            "/deployments/{id}/artifact",
-            put(handlers::deployments_artifacts::create)
-                .layer(authorizer_layer.clone())
-                .get(handlers::deployments_artifacts::get_by_id)
-                .layer(authorizer_layer.clone())
-                .delete(handlers::deployments_artifacts::delete)
-                .layer(authorizer_layer.clone())
-                .head(handlers::deployments_artifacts::head)
-                .layer(authorizer_layer.clone()),
-        )
-        .layer(
-            TraceLayer::new_for_http()
-                .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(trace::DefaultOnResponse::new().level(Level::INFO)),
-        )
-        .layer(Extension(create_service))
-        .layer(Extension(deployment_manager_service))
-        .layer(DefaultBodyLimit::disable())
-        .layer(RequestBodyLimitLayer::new(
-            250 * 1024 * 1024, /* 250mb */
-        ));
+    let router = router(
+        db_pool,
+        artifact_storage,
+        AuthConfig::from_env(),
+        job_runner,
+        webhook_targets,
+    );
 
-    if std::env::var("ENV").unwrap_or_else(|_| "dev".into()) == "prod" {
+    if config.prod {
         run(router).await?;
     } else {
         let router = router.layer(
@@ -128,10 +106,17 @@ async fn main() -> Result<(), Error> {
                     Method::DELETE,
                     Method::HEAD,
                     Method::PUT,
+                    Method::PATCH,
+                ])
+                .expose_headers([
+                    header::ETAG,
+                    HeaderName::from_static(handlers::deployments_artifacts::CHECKSUM_HEADER),
                 ]),
         );
 
-        let listener = tokio::net::TcpListener::bind("0.0.0.0:4000").await.unwrap();
+        let listener = tokio::net::TcpListener::bind(&config.listen_addr)
+            .await
+            .unwrap();
         axum::serve(listener, router).await?;
     }
 
@@ -1,20 +1,103 @@
-use crate::error::ApiError;
+use crate::{
+    error::ApiError,
+    infrastructure::domain::auth::{SqlApiTokenRepository, SqlAuthRepository},
+};
 use axum::{body::Body, extract::Request, http, http::Response, middleware::Next};
-use hex::FromHex;
-use siwe::{Message, VerificationOpts};
-use std::str::FromStr;
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::Duration};
+
+const DEFAULT_SIWE_DOMAINS: &str = "localhost:3000,localhost:4000";
+const NONCE_TTL: Duration = Duration::from_secs(10 * 60);
+const SESSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Api tokens are told apart from session tokens by this prefix
+pub const API_TOKEN_PREFIX: &str = "opr_";
+pub const SCOPE_DEPLOYMENTS_READ: &str = "deployments:read";
+pub const SCOPE_DEPLOYMENTS_WRITE: &str = "deployments:write";
+pub const SCOPES: [&str; 2] = [SCOPE_DEPLOYMENTS_READ, SCOPE_DEPLOYMENTS_WRITE];
+
+#[derive(Clone)]
+pub enum AuthMethod {
+    /// Signed in with ethereum, full access
+    Session,
+    /// Personal api token, limited to its scopes
+    ApiToken { scopes: Vec<String> },
+}
 
 #[derive(Clone)]
 pub struct AuthCurrentUser {
     pub id: String,
+    pub method: AuthMethod,
+}
+
+/// Settings for the SIWE flow
+#[derive(Clone, Debug)]
+pub struct AuthConfig {
+    /// Domains (host[:port]) clients are allowed to sign messages for
+    pub domains: Vec<String>,
+    pub nonce_ttl: Duration,
+    pub session_ttl: Duration,
 }
 
 #[derive(Clone)]
-pub struct Authorizer {}
+pub struct Authorizer {
+    auth_repo: Arc<SqlAuthRepository>,
+    api_token_repo: Arc<SqlApiTokenRepository>,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Self {
+        let domains = std::env::var("SIWE_DOMAINS").unwrap_or_else(|_| DEFAULT_SIWE_DOMAINS.into());
+
+        Self {
+            domains: domains
+                .split(',')
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty())
+                .collect(),
+            nonce_ttl: NONCE_TTL,
+            session_ttl: SESSION_TTL,
+        }
+    }
+}
+
+/// Session and api tokens are only persisted hashed
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl AuthCurrentUser {
+    pub fn require_scope(&self, scope: &str) -> Result<(), ApiError> {
+        match &self.method {
+            AuthMethod::Session => Ok(()),
+            AuthMethod::ApiToken { scopes } if scopes.iter().any(|s| s == scope) => Ok(()),
+            AuthMethod::ApiToken { .. } => Err(ApiError::Forbidden(format!(
+                "Token is missing the {} scope",
+                scope
+            ))),
+        }
+    }
+
+    /// Account management is not available to api tokens
+    pub fn require_session(&self) -> Result<(), ApiError> {
+        match &self.method {
+            AuthMethod::Session => Ok(()),
+            AuthMethod::ApiToken { .. } => Err(ApiError::Forbidden(
+                "Sign in with ethereum is required".into(),
+            )),
+        }
+    }
+}
 
 impl Authorizer {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Self {})
+    pub fn new(
+        auth_repo: Arc<SqlAuthRepository>,
+        api_token_repo: Arc<SqlApiTokenRepository>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            auth_repo,
+            api_token_repo,
+        })
     }
 
     pub async fn authorize(&self, mut req: Request, next: Next) -> Result<Response<Body>, ApiError> {
@@ -33,34 +116,37 @@ impl Authorizer {
             return Err(ApiError::AuthError("No token in headers".to_string()));
         }
 
-        let decoded = base64::decode(token.unwrap())
-            .map_err(|_| ApiError::AuthError("Invalid token. Could not decode token".to_string()))?;
-        let token_str = std::str::from_utf8(&decoded)
-            .map_err(|_| ApiError::AuthError("Invalid token. Could not decode token".to_string()))?;
-        let parts: Vec<&str> = token_str.split("||").collect();
-        if parts.len() != 2 {
-            return Err(ApiError::AuthError("Invalid token".to_string()));
-        }
-        let message = Message::from_str(parts[0])
-            .map_err(|_| ApiError::AuthError("Invalid token. Could not recover message.".to_string()))?;
-        let signature = <[u8; 65]>::from_hex(parts[1].strip_prefix("0x").unwrap())
-            .map_err(|e| ApiError::AuthError(e.to_string()))?;
-
-        if let Err(e) = message
-            .verify(
-                &signature,
-                &VerificationOpts {
-                    ..Default::default()
+        let token = token.unwrap();
+        let current_user = if token.starts_with(API_TOKEN_PREFIX) {
+            let api_token = self
+                .api_token_repo
+                .use_by_hash(&hash_token(token))
+                .await
+                .map_err(ApiError::from)?
+                .ok_or(ApiError::AuthError(
+                    "Api token is invalid or expired".to_string(),
+                ))?;
+
+            AuthCurrentUser {
+                id: api_token.user_id,
+                method: AuthMethod::ApiToken {
+                    scopes: api_token.scopes,
                 },
-            )
-            .await
-        {
-            // message cannot be correctly authenticated at this time
-            return Err(ApiError::AuthError(e.to_string()));
-        }
+            }
+        } else {
+            let user_id = self
+                .auth_repo
+                .find_session_user(&hash_token(token))
+                .await
+                .map_err(ApiError::from)?
+                .ok_or(ApiError::AuthError(
+                    "Session is invalid or expired".to_string(),
+                ))?;
 
-        let current_user = AuthCurrentUser {
-            id: hex::encode(message.address).to_string(),
+            AuthCurrentUser {
+                id: user_id,
+                method: AuthMethod::Session,
+            }
         };
 
         req.extensions_mut().insert(current_user);
@@ -0,0 +1,133 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::org::SqlOrgRepository,
+    middlewares::auth::{AuthCurrentUser, SCOPE_DEPLOYMENTS_READ, SCOPE_DEPLOYMENTS_WRITE},
+};
+use opraas_core::domain::Deployment;
+use std::{str::FromStr, sync::Arc};
+
+/// Role of a user within an org, ordered by privileges
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Operator,
+    Owner,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    ReadDeployments,
+    WriteDeployments,
+    ManageOrg,
+}
+
+/// Decides what a user can do within an org based on its membership role
+#[derive(Clone)]
+pub struct OrgAuthorizer {
+    org_repo: Arc<SqlOrgRepository>,
+}
+
+// implementations ================================================
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Owner => "owner",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "operator" => Ok(Role::Operator),
+            "owner" => Ok(Role::Owner),
+            _ => Err(ApiError::BadRequest(format!(
+                "Unknown role {}, available roles are owner, operator, viewer",
+                s
+            ))),
+        }
+    }
+}
+
+impl Permission {
+    fn min_role(&self) -> Role {
+        match self {
+            Permission::ReadDeployments => Role::Viewer,
+            Permission::WriteDeployments => Role::Operator,
+            Permission::ManageOrg => Role::Owner,
+        }
+    }
+}
+
+impl OrgAuthorizer {
+    pub fn new(org_repo: Arc<SqlOrgRepository>) -> Self {
+        Self { org_repo }
+    }
+
+    /// Returns the user role in the org if it grants the permission
+    pub async fn authorize(
+        &self,
+        user: &AuthCurrentUser,
+        org_id: &str,
+        permission: Permission,
+    ) -> Result<Role, ApiError> {
+        self.authorize_or(user, org_id, permission, || {
+            ApiError::Forbidden("You are not a member of this org".into())
+        })
+        .await
+    }
+
+    /// Deployments of orgs the user isn't a member of are reported as missing,
+    /// so ids taken by other orgs can't be told apart from unused ones
+    pub async fn authorize_deployment(
+        &self,
+        user: &AuthCurrentUser,
+        deployment: &Deployment,
+        permission: Permission,
+    ) -> Result<Role, ApiError> {
+        self.authorize_or(user, &deployment.owner_id, permission, || {
+            ApiError::NotFound("Could not find deployment with given id".into())
+        })
+        .await
+    }
+
+    async fn authorize_or<F>(
+        &self,
+        user: &AuthCurrentUser,
+        org_id: &str,
+        permission: Permission,
+        not_member: F,
+    ) -> Result<Role, ApiError>
+    where
+        F: FnOnce() -> ApiError,
+    {
+        match permission {
+            Permission::ReadDeployments => user.require_scope(SCOPE_DEPLOYMENTS_READ)?,
+            Permission::WriteDeployments => user.require_scope(SCOPE_DEPLOYMENTS_WRITE)?,
+            Permission::ManageOrg => user.require_session()?,
+        }
+
+        let role = self
+            .org_repo
+            .find_role(org_id, &user.id)
+            .await
+            .map_err(ApiError::from)?
+            .ok_or_else(not_member)?;
+        let role = Role::from_str(&role)?;
+
+        if role < permission.min_role() {
+            return Err(ApiError::Forbidden(format!(
+                "Role {} is not allowed to perform this action",
+                role.as_str()
+            )));
+        }
+
+        Ok(role)
+    }
+}
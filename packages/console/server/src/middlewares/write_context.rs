@@ -0,0 +1,96 @@
+use crate::{
+    error::ApiError,
+    infrastructure::domain::{audit::SqlAuditRepository, webhook::SqlWebhookRepository},
+    middlewares::{
+        auth::AuthCurrentUser,
+        authorization::{OrgAuthorizer, Permission, Role},
+    },
+    utils::audit,
+};
+use axum::{extract::FromRequestParts, http::request::Parts};
+use opraas_core::domain::Deployment;
+use std::sync::Arc;
+
+/// What handlers changing deployments share: who is asking, what they may do and where their changes are recorded
+pub struct WriteContext {
+    pub user: AuthCurrentUser,
+    pub org_authorizer: Arc<OrgAuthorizer>,
+    audit_repo: Arc<SqlAuditRepository>,
+    webhook_repo: Arc<SqlWebhookRepository>,
+    request_id: Option<String>,
+}
+
+// implementations =============================================
+
+impl<S> FromRequestParts<S> for WriteContext
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            user: extension(parts)?,
+            org_authorizer: extension(parts)?,
+            audit_repo: extension(parts)?,
+            webhook_repo: extension(parts)?,
+            request_id: audit::request_id(&parts.headers),
+        })
+    }
+}
+
+impl WriteContext {
+    pub async fn authorize(&self, org_id: &str, permission: Permission) -> Result<Role, ApiError> {
+        self.org_authorizer
+            .authorize(&self.user, org_id, permission)
+            .await
+    }
+
+    pub async fn authorize_deployment(
+        &self,
+        deployment: &Deployment,
+        permission: Permission,
+    ) -> Result<Role, ApiError> {
+        self.org_authorizer
+            .authorize_deployment(&self.user, deployment, permission)
+            .await
+    }
+
+    /// Appends the change to the deployment audit log and queues it for the webhooks of its org
+    pub async fn record(
+        &self,
+        action: &str,
+        deployment: &Deployment,
+        diff: &serde_json::Value,
+    ) -> Result<(), ApiError> {
+        let event = self
+            .audit_repo
+            .append(
+                &self.user.id,
+                action,
+                &deployment.id,
+                diff,
+                self.request_id.as_deref(),
+            )
+            .await
+            .map_err(ApiError::from)?;
+        self.webhook_repo
+            .enqueue(&deployment.owner_id, &event)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(())
+    }
+}
+
+/// Set by the router and the auth middleware, missing only if a route was wired without them
+fn extension<T>(parts: &Parts) -> Result<T, ApiError>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    parts
+        .extensions
+        .get::<T>()
+        .cloned()
+        .ok_or_else(|| ApiError::InternalServerError(format!("Missing {} extension", std::any::type_name::<T>())))
+}
@@ -0,0 +1,171 @@
+use crate::handlers;
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        Content, ObjectBuilder, OpenApi as OpenApiDocument, RefOr, Response, Type,
+    },
+    Modify, OpenApi, ToSchema,
+};
+
+/// OpenAPI document of the console api, served at `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "opruaas console api",
+        description = "Manages deployments and their artifacts for the opruaas console and cli. \
+            Errors are returned as plain text."
+    ),
+    paths(
+        handlers::health::health,
+        handlers::auth::nonce,
+        handlers::auth::verify,
+        handlers::tokens::list,
+        handlers::tokens::create,
+        handlers::tokens::delete,
+        handlers::orgs::list,
+        handlers::orgs::create,
+        handlers::orgs::list_members,
+        handlers::orgs::save_member,
+        handlers::orgs::delete_member,
+        handlers::projects::create,
+        handlers::deployments::list,
+        handlers::deployments::create,
+        handlers::deployments::get_by_id,
+        handlers::deployments::update,
+        handlers::deployments::patch,
+        handlers::deployments::delete,
+        handlers::revisions::list,
+        handlers::revisions::diff,
+        handlers::revisions::restore,
+        handlers::audit::list,
+        handlers::deployments_artifacts::create,
+        handlers::deployments_artifacts::head,
+        handlers::deployments_artifacts::get_by_id,
+        handlers::deployments_artifacts::delete,
+        handlers::deployments_artifacts::upload_url,
+        handlers::deployments_artifacts::download_url,
+        handlers::deployments_artifacts::complete,
+        handlers::jobs::create,
+        handlers::jobs::list,
+        handlers::jobs::get_by_id,
+        handlers::jobs::cancel,
+        handlers::jobs::logs,
+        handlers::webhooks::create,
+        handlers::webhooks::list,
+        handlers::webhooks::delete,
+        handlers::webhooks::deliveries,
+    ),
+    modifiers(&BearerAuth, &ErrorResponses, &OperationIds),
+    tags(
+        (name = "auth", description = "Sign-In with Ethereum sessions"),
+        (name = "tokens", description = "Scoped api tokens for automation"),
+        (name = "orgs", description = "Orgs owning deployments and their members"),
+        (name = "projects", description = "Project generation"),
+        (name = "deployments", description = "Deployments, every change is stored as a new revision"),
+        (name = "revisions", description = "Deployment history"),
+        (name = "audit", description = "Who changed what and when"),
+        (name = "artifacts", description = "Zipped deployment artifacts, genesis and rollup config included"),
+        (name = "jobs", description = "Contracts and infra deployments run by the console"),
+        (name = "webhooks", description = "Org events delivered to external urls, signed and retried"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Raw zip archive
+#[derive(ToSchema)]
+#[schema(value_type = String, format = Binary)]
+pub struct ZipFile(pub Vec<u8>);
+
+/// Only the first field is read, whatever its name
+#[derive(ToSchema)]
+pub struct ArtifactUpload {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+/// Session tokens from `/auth/verify` and api tokens from `/tokens` are both sent as bearer tokens
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+    }
+}
+
+/// Adds the errors every authenticated route can fail with and documents error bodies as plain text
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        for path in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut path.get,
+                &mut path.put,
+                &mut path.post,
+                &mut path.delete,
+                &mut path.head,
+                &mut path.patch,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                let responses = &mut operation.responses.responses;
+                if operation.security.is_some() {
+                    responses
+                        .entry("401".into())
+                        .or_insert_with(|| Response::new("Missing, invalid or expired session or token").into());
+                    responses
+                        .entry("403".into())
+                        .or_insert_with(|| Response::new("Role or token scope does not allow this").into());
+                }
+
+                for (status, response) in responses.iter_mut() {
+                    if let RefOr::T(response) = response {
+                        if status.starts_with(['4', '5']) && response.content.is_empty() {
+                            response.content.insert(
+                                "text/plain".into(),
+                                Content::new(Some(ObjectBuilder::new().schema_type(Type::String))),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Handlers share names across modules, prefixes operation ids with the tag so generated clients get unique methods
+struct OperationIds;
+
+impl Modify for OperationIds {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        for path in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut path.get,
+                &mut path.put,
+                &mut path.post,
+                &mut path.delete,
+                &mut path.head,
+                &mut path.patch,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                let tag = operation
+                    .tags
+                    .as_ref()
+                    .and_then(|tags| tags.first())
+                    .cloned();
+                if let (Some(tag), Some(id)) = (tag, operation.operation_id.as_mut()) {
+                    *id = format!("{}_{}", tag, id);
+                }
+            }
+        }
+    }
+}
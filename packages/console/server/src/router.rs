@@ -0,0 +1,226 @@
+use crate::{
+    handlers,
+    infrastructure::{
+        database::DbPool,
+        domain::{
+            audit::SqlAuditRepository,
+            auth::{SqlApiTokenRepository, SqlAuthRepository},
+            deployment::{ArtifactStorage, SqlDeploymentRepository},
+            job::SqlJobRepository,
+            org::SqlOrgRepository,
+            webhook::SqlWebhookRepository,
+        },
+    },
+    jobs::JobRunner,
+    middlewares::{
+        auth::{AuthConfig, Authorizer},
+        authorization::OrgAuthorizer,
+    },
+    webhooks::TargetPolicy,
+};
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put},
+    Extension, Router,
+};
+use opraas_core::{
+    application::{deployment::manager::DeploymentManagerService, CreateProjectService},
+    infrastructure::project::{GitVersionControl, InMemoryProjectInfraRepository, InMemoryProjectRepository},
+};
+use std::sync::Arc;
+use tower_http::{
+    limit::RequestBodyLimitLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::{self, TraceLayer},
+};
+use tracing::Level;
+
+/// Api routes wired to their repositories, shared by the server binary and the integration tests
+pub fn router(
+    db_pool: DbPool,
+    artifact_storage: ArtifactStorage,
+    auth_config: AuthConfig,
+    job_runner: Arc<JobRunner>,
+    webhook_targets: TargetPolicy,
+) -> Router {
+    // authorizer
+    let auth_config = Arc::new(auth_config);
+    let auth_repo = Arc::new(SqlAuthRepository::new(db_pool.clone()));
+    let api_token_repo = Arc::new(SqlApiTokenRepository::new(db_pool.clone()));
+    let authorizer = Authorizer::new(auth_repo.clone(), api_token_repo.clone()).unwrap();
+    let org_repo = Arc::new(SqlOrgRepository::new(db_pool.clone()));
+    let audit_repo = Arc::new(SqlAuditRepository::new(db_pool.clone()));
+    let job_repo = Arc::new(SqlJobRepository::new(db_pool.clone()));
+    let webhook_repo = Arc::new(SqlWebhookRepository::new(db_pool.clone()));
+    let org_authorizer = Arc::new(OrgAuthorizer::new(org_repo.clone()));
+    let authorizer_layer = middleware::from_fn(move |req, next| {
+        let authorizer = authorizer.clone();
+        async move { authorizer.authorize(req, next).await }
+    });
+
+    // services
+    let create_service = Arc::new(CreateProjectService::new(
+        InMemoryProjectRepository::new(),
+        GitVersionControl::new(),
+        InMemoryProjectInfraRepository::new(),
+    ));
+    let deployments_repo = Arc::new(SqlDeploymentRepository::new(db_pool.clone()));
+    let artifacts_repo = Arc::new(artifact_storage.clone());
+    let deployment_manager_service = Arc::new(DeploymentManagerService::new(
+        SqlDeploymentRepository::new(db_pool),
+        artifact_storage,
+    ));
+
+    Router::new()
+        .route("/health", get(handlers::health::health))
+        .route("/openapi.json", get(handlers::openapi::openapi))
+        .route("/auth/nonce", get(handlers::auth::nonce))
+        .route("/auth/verify", post(handlers::auth::verify))
+        .route(
+            "/tokens",
+            get(handlers::tokens::list)
+                .layer(authorizer_layer.clone())
+                .post(handlers::tokens::create)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/tokens/{id}",
+            delete(handlers::tokens::delete).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/orgs",
+            get(handlers::orgs::list)
+                .layer(authorizer_layer.clone())
+                .post(handlers::orgs::create)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/orgs/{id}/members",
+            get(handlers::orgs::list_members).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/orgs/{id}/members/{member_id}",
+            put(handlers::orgs::save_member)
+                .layer(authorizer_layer.clone())
+                .delete(handlers::orgs::delete_member)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/orgs/{id}/webhooks",
+            get(handlers::webhooks::list)
+                .layer(authorizer_layer.clone())
+                .post(handlers::webhooks::create)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/orgs/{id}/webhooks/{webhook_id}",
+            delete(handlers::webhooks::delete).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/orgs/{id}/webhooks/{webhook_id}/deliveries",
+            get(handlers::webhooks::deliveries).layer(authorizer_layer.clone()),
+        )
+        .route("/projects", post(handlers::projects::create))
+        .route(
+            "/deployments",
+            get(handlers::deployments::list)
+                .layer(authorizer_layer.clone())
+                .post(handlers::deployments::create)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}",
+            get(handlers::deployments::get_by_id)
+                .layer(authorizer_layer.clone())
+                .delete(handlers::deployments::delete)
+                .layer(authorizer_layer.clone())
+                .put(handlers::deployments::update)
+                .layer(authorizer_layer.clone())
+                .patch(handlers::deployments::patch)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/revisions",
+            get(handlers::revisions::list).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/revisions/{revision}/restore",
+            post(handlers::revisions::restore).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/diff",
+            get(handlers::revisions::diff).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/audit",
+            get(handlers::audit::list).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/artifact",
+            put(handlers::deployments_artifacts::create)
+                .layer(authorizer_layer.clone())
+                .get(handlers::deployments_artifacts::get_by_id)
+                .layer(authorizer_layer.clone())
+                .delete(handlers::deployments_artifacts::delete)
+                .layer(authorizer_layer.clone())
+                .head(handlers::deployments_artifacts::head)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/artifact/upload-url",
+            post(handlers::deployments_artifacts::upload_url).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/artifact/download-url",
+            get(handlers::deployments_artifacts::download_url).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/artifact/complete",
+            post(handlers::deployments_artifacts::complete).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/deployments/{id}/jobs",
+            get(handlers::jobs::list)
+                .layer(authorizer_layer.clone())
+                .post(handlers::jobs::create)
+                .layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/jobs/{id}",
+            get(handlers::jobs::get_by_id).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/jobs/{id}/cancel",
+            post(handlers::jobs::cancel).layer(authorizer_layer.clone()),
+        )
+        .route(
+            "/jobs/{id}/logs",
+            get(handlers::jobs::logs).layer(authorizer_layer.clone()),
+        )
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
+                .on_response(trace::DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .layer(Extension(auth_repo))
+        .layer(Extension(auth_config))
+        .layer(Extension(api_token_repo))
+        .layer(Extension(org_repo))
+        .layer(Extension(org_authorizer))
+        .layer(Extension(audit_repo))
+        .layer(Extension(job_repo))
+        .layer(Extension(job_runner))
+        .layer(Extension(webhook_repo))
+        .layer(Extension(webhook_targets))
+        .layer(Extension(create_service))
+        .layer(Extension(deployments_repo))
+        .layer(Extension(artifacts_repo))
+        .layer(Extension(deployment_manager_service))
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(
+            handlers::deployments_artifacts::MAX_ARTIFACT_SIZE as usize,
+        ))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
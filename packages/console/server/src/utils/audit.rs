@@ -0,0 +1,88 @@
+use axum::http::HeaderMap;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const REDACTED: &str = "[redacted]";
+const SECRET_KEYS: [&str; 5] = ["private_key", "secret", "password", "token", "mnemonic"];
+
+/// Documents each entry of a `diff`, a missing side is null
+#[derive(ToSchema)]
+pub struct FieldChange {
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Request id set by the request id layer
+pub fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// Changed leaves as `{ "path.to.field": { "before": .., "after": .. } }` with secrets redacted
+pub fn diff(before: &Value, after: &Value) -> Value {
+    let mut changes = Map::new();
+    collect_changes("", before, after, &mut changes);
+
+    Value::Object(changes)
+}
+
+fn collect_changes(path: &str, before: &Value, after: &Value, changes: &mut Map<String, Value>) {
+    // created and deleted objects are compared against an empty one so secrets are still redacted
+    let empty = Map::new();
+    let objects = match (before, after) {
+        (Value::Object(before), Value::Object(after)) => Some((before, after)),
+        (Value::Object(before), Value::Null) => Some((before, &empty)),
+        (Value::Null, Value::Object(after)) => Some((&empty, after)),
+        _ => None,
+    };
+
+    if let Some((before, after)) = objects {
+        let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        for key in keys {
+            let key_path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            collect_changes(
+                &key_path,
+                before.get(key).unwrap_or(&Value::Null),
+                after.get(key).unwrap_or(&Value::Null),
+                changes,
+            );
+        }
+        return;
+    }
+
+    if before != after {
+        let (before, after) = if is_secret(path) {
+            (redact(before), redact(after))
+        } else {
+            (before.clone(), after.clone())
+        };
+
+        let mut change = Map::new();
+        change.insert("before".into(), before);
+        change.insert("after".into(), after);
+        changes.insert(path.to_string(), Value::Object(change));
+    }
+}
+
+fn is_secret(path: &str) -> bool {
+    let key = path.rsplit('.').next().unwrap_or(path).to_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+fn redact(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        _ => Value::String(REDACTED.into()),
+    }
+}
@@ -1 +1,3 @@
+pub mod audit;
+pub mod merge_patch;
 pub mod zip;
@@ -1,28 +1,85 @@
 use std::{
     fs,
-    io::{Cursor, Write},
+    io::{Cursor, Seek, Write},
+    path::Path,
 };
-use zip::ZipWriter;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
 
-pub fn zip_folder(folder: &std::path::PathBuf) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+/// Files the helm charts expect at the root of a deployment artifact
+pub const ARTIFACT_REQUIRED_FILES: [&str; 2] = ["genesis.json", "rollup-config.json"];
+
+/// Zips the folder recursively, entries are relative to it so the archive extracts to the same tree
+pub fn zip_folder(folder: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     let mut buffer = Vec::new();
 
     {
         let mut zip = ZipWriter::new(Cursor::new(&mut buffer));
+        add_folder(&mut zip, folder, folder)?;
+        zip.finish()?;
+    }
+
+    Ok(buffer)
+}
+
+fn add_folder<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    root: &Path,
+    folder: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // sorted so the same project always produces the same archive
+    let mut entries = fs::read_dir(folder)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        // zip entries always use forward slashes
+        let name = path
+            .strip_prefix(root)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
 
-        for entry in fs::read_dir(folder)? {
-            let path = entry.unwrap().path();
-            if path.is_file() {
-                zip.start_file(
-                    path.file_name().unwrap().to_string_lossy(),
-                    Default::default(),
-                )?;
-                let _ = zip.write(&fs::read(path)?)?;
-            }
+        if path.is_dir() {
+            zip.add_directory(name, FileOptions::default())?;
+            add_folder(zip, root, &path)?;
+        } else if path.is_file() {
+            zip.start_file(
+                name,
+                FileOptions::default().unix_permissions(file_mode(&path)?),
+            )?;
+            zip.write_all(&fs::read(&path)?)?;
         }
+    }
 
-        zip.finish()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn file_mode(path: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Ok(fs::metadata(path)?.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    Ok(0o644)
+}
+
+/// Ensures the file is a zip archive containing every required file
+pub fn verify_zip(path: &Path, required_files: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?).map_err(|_| "Artifact is not a valid zip archive")?;
+
+    let missing: Vec<&str> = required_files
+        .iter()
+        .filter(|name| archive.by_name(name).is_err())
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Artifact is missing {}", missing.join(", ")).into());
     }
 
-    Ok(buffer)
+    Ok(())
 }
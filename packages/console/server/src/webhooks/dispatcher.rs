@@ -0,0 +1,171 @@
+use super::{sign, TargetPolicy, TargetResolver, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER};
+use crate::infrastructure::domain::webhook::{SqlWebhookRepository, WebhookDelivery};
+use futures_util::future::join_all;
+use reqwest::{header::CONTENT_TYPE, redirect, Client, Url};
+use std::{error::Error, sync::Arc, time::Duration};
+use time::OffsetDateTime;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a claimed delivery is hidden from other dispatchers, well above the delivery timeout
+const CLAIM_LEASE: Duration = Duration::from_secs(60);
+const BATCH_SIZE: i64 = 20;
+
+/// Sends queued webhook deliveries, retrying failed ones with exponential backoff
+pub struct WebhookDispatcher {
+    webhook_repo: Arc<SqlWebhookRepository>,
+    http: Client,
+    policy: RetryPolicy,
+    targets: TargetPolicy,
+}
+
+/// Attempts are spaced `base_delay * 2^(attempt - 1)` apart, up to `max_delay`
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+// implementations =============================================
+
+impl Default for RetryPolicy {
+    /// Gives up about an hour after the first attempt
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after `attempts` failed attempts, none once exhausted
+    pub fn retry_delay(&self, attempts: i32) -> Option<Duration> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1) as u32);
+        Some(self.base_delay.saturating_mul(factor).min(self.max_delay))
+    }
+}
+
+impl WebhookDispatcher {
+    pub fn new(webhook_repo: Arc<SqlWebhookRepository>, policy: RetryPolicy) -> Self {
+        let targets = TargetPolicy::default();
+        Self {
+            webhook_repo,
+            http: http_client(targets),
+            policy,
+            targets,
+        }
+    }
+
+    /// Addresses deliveries may be sent to, only public ones by default
+    pub fn with_targets(mut self, targets: TargetPolicy) -> Self {
+        self.http = http_client(targets);
+        self.targets = targets;
+        self
+    }
+
+    /// Dispatches due deliveries until the process exits, to be spawned once on startup
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            if let Err(e) = self.dispatch_due().await {
+                error!("Could not dispatch webhook deliveries. {}", e);
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Attempts every delivery due now, returns how many were attempted
+    pub async fn dispatch_due(&self) -> Result<usize, Box<dyn Error>> {
+        let mut attempted = 0;
+
+        loop {
+            let lease_until = now() + CLAIM_LEASE.as_secs() as i64;
+            let deliveries = self.webhook_repo.claim_due(BATCH_SIZE, lease_until).await?;
+            if deliveries.is_empty() {
+                return Ok(attempted);
+            }
+
+            attempted += deliveries.len();
+            join_all(deliveries.iter().map(|d| self.deliver(d))).await;
+        }
+    }
+
+    /// A delivery that can't be recorded is retried once its claim expires
+    async fn deliver(&self, delivery: &WebhookDelivery) {
+        if let Err(e) = self.attempt(delivery).await {
+            error!("Webhook delivery {} failed. {}", delivery.id, e);
+        }
+    }
+
+    async fn attempt(&self, delivery: &WebhookDelivery) -> Result<(), Box<dyn Error>> {
+        // deliveries of deleted webhooks are deleted along with them
+        let webhook = match self.webhook_repo.find_by_id(&delivery.webhook_id).await? {
+            Some(webhook) => webhook,
+            None => return Ok(()),
+        };
+
+        // the client resolves hosts again with the same policy, ip urls are only checked here
+        let url = Url::parse(&webhook.url)?;
+        let (response_status, error) = match self.targets.resolve(&url).await {
+            Ok(_) => {
+                let body = serde_json::to_string(&delivery.payload)?;
+                let response = self
+                    .http
+                    .post(url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .header(EVENT_HEADER, &delivery.event)
+                    .header(DELIVERY_HEADER, &delivery.id)
+                    .header(SIGNATURE_HEADER, sign(&webhook.secret, now(), &body))
+                    .body(body)
+                    .send()
+                    .await;
+
+                match response {
+                    Ok(response) if response.status().is_success() => {
+                        return self
+                            .webhook_repo
+                            .succeed(&delivery.id, response.status().as_u16() as i32)
+                            .await;
+                    }
+                    // only the status is kept, the body is up to the receiver
+                    Ok(response) => (
+                        Some(response.status().as_u16() as i32),
+                        format!("Receiver responded {}", response.status()),
+                    ),
+                    Err(e) => (None, format!("Request failed. {}", e)),
+                }
+            }
+            Err(e) => (None, e.to_string()),
+        };
+
+        let retry_at = self
+            .policy
+            .retry_delay(delivery.attempts + 1)
+            .map(|delay| now() + delay.as_secs() as i64);
+        self.webhook_repo
+            .fail_attempt(&delivery.id, response_status, &error, retry_at)
+            .await
+    }
+}
+
+/// Redirects are not followed, they could point anywhere
+fn http_client(targets: TargetPolicy) -> Client {
+    Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(redirect::Policy::none())
+        .no_proxy()
+        .dns_resolver(Arc::new(TargetResolver(targets)))
+        .build()
+        .expect("Failed to build webhooks http client")
+}
+
+fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
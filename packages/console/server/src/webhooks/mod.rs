@@ -0,0 +1,7 @@
+pub mod dispatcher;
+pub mod signature;
+pub mod target;
+
+pub use dispatcher::*;
+pub use signature::*;
+pub use target::*;
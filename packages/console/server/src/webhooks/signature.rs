@@ -0,0 +1,21 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// `t=<unix seconds>,v1=<hex hmac>`, see [`sign`]
+pub const SIGNATURE_HEADER: &str = "x-opruaas-signature";
+pub const EVENT_HEADER: &str = "x-opruaas-event";
+/// Same on every attempt of a delivery
+pub const DELIVERY_HEADER: &str = "x-opruaas-delivery";
+
+/// Hmac-sha256 of `<timestamp>.<body>` keyed with the webhook secret, formatted as the signature header.
+/// Signing the timestamp lets receivers reject replayed deliveries
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+
+    format!(
+        "t={},v1={}",
+        timestamp,
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
@@ -0,0 +1,124 @@
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    Url,
+};
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+use thiserror::Error as ThisError;
+
+/// Which addresses webhooks may be delivered to, so they can't be used to reach the console network.
+/// Link-local addresses, cloud metadata endpoints among them, are never allowed
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TargetPolicy {
+    /// Also allows loopback and private networks, for receivers next to a self hosted console
+    pub allow_private: bool,
+}
+
+#[derive(Debug, ThisError)]
+pub enum TargetError {
+    #[error("Webhook url must have a host")]
+    MissingHost,
+    #[error("Could not resolve webhook host {0}")]
+    Unresolved(String),
+    #[error("Webhook host {0} resolves to a non public address")]
+    NotAllowed(String),
+}
+
+/// Resolves hosts for the delivery client with the same policy, so a host can't be switched to an internal
+/// address after it was checked
+pub struct TargetResolver(pub TargetPolicy);
+
+// implementations =============================================
+
+impl TargetPolicy {
+    /// Private networks are allowed with `WEBHOOKS_ALLOW_PRIVATE_TARGETS=true`
+    pub fn from_env() -> Self {
+        Self {
+            allow_private: env::var("WEBHOOKS_ALLOW_PRIVATE_TARGETS").is_ok_and(|v| v == "true"),
+        }
+    }
+
+    /// Addresses the url points to, an error if any of them is not allowed
+    pub async fn resolve(&self, url: &Url) -> Result<Vec<SocketAddr>, TargetError> {
+        let host = url.host_str().ok_or(TargetError::MissingHost)?;
+        let port = url.port_or_known_default().unwrap_or_default();
+
+        // ipv6 hosts are bracketed in urls
+        match host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            Ok(ip) => self.check(host, vec![SocketAddr::new(ip, port)]),
+            Err(_) => self.lookup(host, port).await,
+        }
+    }
+
+    async fn lookup(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, TargetError> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| TargetError::Unresolved(host.to_string()))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(TargetError::Unresolved(host.to_string()));
+        }
+
+        self.check(host, addrs)
+    }
+
+    fn check(&self, host: &str, addrs: Vec<SocketAddr>) -> Result<Vec<SocketAddr>, TargetError> {
+        if addrs.iter().any(|addr| !self.allows(addr.ip())) {
+            return Err(TargetError::NotAllowed(host.to_string()));
+        }
+
+        Ok(addrs)
+    }
+
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => self.allows_v4(ip),
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => self.allows_v4(ip),
+                None => self.allows_v6(ip),
+            },
+        }
+    }
+
+    fn allows_v4(&self, ip: Ipv4Addr) -> bool {
+        if ip.is_unspecified() || ip.is_link_local() || ip.is_broadcast() || ip.is_multicast() {
+            return false;
+        }
+
+        // 100.64.0.0/10 is carrier grade nat, private to the provider network
+        let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64;
+        self.allow_private || !(ip.is_loopback() || ip.is_private() || shared)
+    }
+
+    fn allows_v6(&self, ip: Ipv6Addr) -> bool {
+        let link_local = (ip.segments()[0] & 0xffc0) == 0xfe80;
+        if ip.is_unspecified() || ip.is_multicast() || link_local {
+            return false;
+        }
+
+        // fd00:ec2::254 is the ec2 metadata endpoint
+        if ip.segments()[..2] == [0xfd00, 0x0ec2] {
+            return false;
+        }
+
+        // fc00::/7 are unique local addresses
+        let unique_local = (ip.segments()[0] & 0xfe00) == 0xfc00;
+        self.allow_private || !(ip.is_loopback() || unique_local)
+    }
+}
+
+impl Resolve for TargetResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.0;
+        Box::pin(async move {
+            let addrs = policy.lookup(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
@@ -0,0 +1,1242 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use opraas_core::{
+    config::{AccountsConfig, ArtifactsConfig, NetworkConfig},
+    domain::{Deployment, DeploymentError, TDeploymentRepository},
+};
+use opraas_server::{
+    handlers::deployments_artifacts::{CHECKSUM_HEADER, MAX_ARTIFACT_SIZE},
+    infrastructure::{
+        database::DbPool,
+        domain::{
+            auth::SqlAuthRepository,
+            deployment::{ArtifactStorage, FsDeploymentArtifactsRepository, SqlDeploymentRepository},
+            job::{Job, JobSpec, JobStatus, SqlJobRepository},
+            webhook::SqlWebhookRepository,
+        },
+    },
+    jobs::{JobContext, JobRunner, JobSecrets, TJobExecutor},
+    middlewares::auth::{hash_token, AuthConfig},
+    router::router,
+    webhooks::{sign, RetryPolicy, TargetPolicy, WebhookDispatcher, EVENT_HEADER, SIGNATURE_HEADER},
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    io::{Cursor, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+const ALICE: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+const BOB: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+const MULTIPART_BOUNDARY: &str = "opruaas-test-boundary";
+
+/// The api running in process against a throwaway sqlite database and artifacts directory
+struct TestApp {
+    router: Router,
+    db_pool: DbPool,
+    _dir: TempDir,
+}
+
+struct TestResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+/// Stands in for docker and terraform, the l1 rpc url secret scripts the outcome
+struct ScriptedExecutor;
+
+#[async_trait::async_trait(?Send)]
+impl TJobExecutor for ScriptedExecutor {
+    async fn execute(&self, _job: &Job, secrets: &JobSecrets, ctx: &JobContext) -> Result<(), Box<dyn Error>> {
+        ctx.log("deploying").await;
+
+        match secrets.l1_rpc_url.as_deref() {
+            Some("http://fails") => Err("forge script failed".into()),
+            Some("http://hangs") => {
+                ctx.cancelled().await;
+                ctx.checkpoint()
+            }
+            _ => {
+                ctx.log("deployed").await;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl TestApp {
+    async fn new() -> Self {
+        Self::with_webhook_targets(TargetPolicy::default()).await
+    }
+
+    /// Receivers served by the tests are local, they need private targets
+    async fn with_webhook_targets(webhook_targets: TargetPolicy) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let db_pool = DbPool::connect(&format!(
+            "sqlite://{}",
+            dir.path().join("console.db").display()
+        ))
+        .await
+        .unwrap();
+        db_pool.migrate().await.unwrap();
+        let artifact_storage = ArtifactStorage::Fs(FsDeploymentArtifactsRepository::new(
+            dir.path().join("artifacts"),
+        ));
+
+        let job_runner = Arc::new(JobRunner::new(
+            Arc::new(SqlJobRepository::new(db_pool.clone())),
+            Arc::new(ScriptedExecutor),
+            1,
+        ));
+
+        Self {
+            router: router(
+                db_pool.clone(),
+                artifact_storage,
+                AuthConfig::from_env(),
+                job_runner,
+                webhook_targets,
+            ),
+            db_pool,
+            _dir: dir,
+        }
+    }
+
+    /// Skips the SIWE flow by storing a session straight away, returns its token
+    async fn sign_in(&self, user_id: &str) -> String {
+        let token = format!("session-{}", user_id);
+        let expires_at = time::OffsetDateTime::now_utc().unix_timestamp() + 3600;
+        SqlAuthRepository::new(self.db_pool.clone())
+            .create_session(&hash_token(&token), user_id, expires_at)
+            .await
+            .unwrap();
+
+        token
+    }
+
+    async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec();
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    async fn request(&self, method: Method, uri: &str, token: &str, body: Option<Value>) -> TestResponse {
+        self.request_with_headers(method, uri, token, body, &[])
+            .await
+    }
+
+    async fn request_with_headers(
+        &self,
+        method: Method,
+        uri: &str,
+        token: &str,
+        body: Option<Value>,
+        headers: &[(&str, &str)],
+    ) -> TestResponse {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token));
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        };
+
+        self.send(request.unwrap()).await
+    }
+
+    async fn upload_artifact(&self, deployment_id: &str, token: &str, artifact: &[u8]) -> TestResponse {
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"artifact.zip\"\r\n\
+            Content-Type: application/zip\r\n\r\n",
+            boundary = MULTIPART_BOUNDARY
+        )
+        .into_bytes();
+        body.extend_from_slice(artifact);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", MULTIPART_BOUNDARY).as_bytes());
+
+        let request = Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/deployments/{}/artifact", deployment_id))
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY),
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+        self.send(request).await
+    }
+
+    /// Creates a deployment in the user personal org, returns its id
+    async fn create_deployment(&self, token: &str, name: &str) -> String {
+        let response = self
+            .request(Method::POST, "/deployments", token, Some(deployment(name)))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.header("etag"), Some("\"1\""));
+
+        response.json()["id"].as_str().unwrap().to_string()
+    }
+
+    /// Polls the job until `done` holds, returns it
+    async fn wait_for_job(&self, token: &str, job_id: &str, done: impl Fn(&Value) -> bool) -> Value {
+        for _ in 0..100 {
+            let job = self
+                .request(Method::GET, &format!("/jobs/{}", job_id), token, None)
+                .await
+                .json();
+            if done(&job) {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        panic!("job {} did not reach the expected state", job_id);
+    }
+}
+
+impl TestResponse {
+    fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+}
+
+fn deployment(name: &str) -> Value {
+    let deployment = Deployment::new(
+        "",
+        name,
+        "",
+        "v0.0.1",
+        "wakeuplabs",
+        NetworkConfig::null(),
+        AccountsConfig::null(),
+    )
+    .unwrap();
+
+    serde_json::to_value(deployment).unwrap()
+}
+
+/// Deliveries received by a webhook receiver, with their headers and body
+type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+/// Serves a webhook receiver on a local port failing the first `failures` deliveries, returns its url
+async fn webhook_receiver(failures: usize) -> (String, Received) {
+    let received: Received = Arc::new(Mutex::new(Vec::new()));
+    let receiver = Router::new().route(
+        "/hook",
+        post({
+            let received = received.clone();
+            move |headers: HeaderMap, body: String| {
+                let received = received.clone();
+                async move {
+                    let mut received = received.lock().unwrap();
+                    received.push((headers, body));
+                    if received.len() <= failures {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                }
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+    (url, received)
+}
+
+fn artifact(files: &[&str]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        for file in files {
+            zip.start_file(*file, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(b"{}").unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    buffer
+}
+
+#[tokio::test]
+async fn health_does_not_require_a_session() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .send(Request::get("/health").body(Body::empty()).unwrap())
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn openapi_document_is_public() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .send(Request::get("/openapi.json").body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let document = response.json();
+    assert!(document["paths"]["/deployments/{id}"]["patch"].is_object());
+    assert!(document["components"]["securitySchemes"]["bearer"].is_object());
+}
+
+#[tokio::test]
+async fn projects_are_validated_before_being_generated() {
+    let app = TestApp::new().await;
+    let address = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    let response = app
+        .send(
+            Request::post("/projects")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                        "name": "my-chain",
+                        "artifacts": ArtifactsConfig::null(),
+                        "accounts": {
+                            "admin_address": address,
+                            "batcher_address": address,
+                            "sequencer_address": address,
+                            "proposer_address": address,
+                            "deployer_address": address,
+                            "challenger_address": "not-an-address",
+                        },
+                        "network": NetworkConfig::null(),
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let message = String::from_utf8(response.body).unwrap();
+    assert!(message.contains("challenger_address"), "{}", message);
+}
+
+#[tokio::test]
+async fn unknown_sessions_are_rejected() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .request(Method::GET, "/deployments", "not-a-session", None)
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn creates_and_lists_deployments_in_the_personal_org() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+
+    let id = app.create_deployment(&alice, "devnet").await;
+
+    let response = app.request(Method::GET, "/deployments", &alice, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json().as_array().unwrap().len(), 1);
+
+    let response = app
+        .request(Method::GET, &format!("/deployments/{}", id), &alice, None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("etag"), Some("\"1\""));
+    assert_eq!(response.json()["owner_id"], ALICE);
+    assert_eq!(response.json()["name"], "devnet");
+}
+
+#[tokio::test]
+async fn client_chosen_ids_are_validated_and_unique_across_orgs() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let bob = app.sign_in(BOB).await;
+
+    let mut invalid = deployment("devnet");
+    invalid["id"] = json!("../devnet");
+    let response = app
+        .request(Method::POST, "/deployments", &alice, Some(invalid))
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let mut synced = deployment("devnet");
+    synced["id"] = json!("devnet");
+    let response = app
+        .request(Method::POST, "/deployments", &alice, Some(synced.clone()))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .request(Method::POST, "/deployments", &bob, Some(synced))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    // taken by another org or missing look the same
+    let taken = app
+        .request(Method::GET, "/deployments/devnet", &bob, None)
+        .await;
+    let missing = app
+        .request(Method::GET, "/deployments/mainnet", &bob, None)
+        .await;
+    assert_eq!(taken.status, StatusCode::NOT_FOUND);
+    assert_eq!(taken.body, missing.body);
+
+    let response = app
+        .request(Method::GET, "/deployments/devnet", &alice, None)
+        .await;
+    assert_eq!(response.json()["owner_id"], ALICE);
+}
+
+#[tokio::test]
+async fn saves_never_move_deployments_between_orgs() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+    let deployments_repo = SqlDeploymentRepository::new(app.db_pool.clone());
+
+    let mut deployment = deployments_repo.find_by_id(&id).await.unwrap().unwrap();
+    deployment.owner_id = BOB.to_string();
+    deployments_repo.save(&deployment).await.unwrap();
+    assert_eq!(
+        deployments_repo
+            .find_by_id(&id)
+            .await
+            .unwrap()
+            .unwrap()
+            .owner_id,
+        ALICE
+    );
+
+    deployment.id = "unknown".to_string();
+    assert!(matches!(
+        deployments_repo.save(&deployment).await,
+        Err(DeploymentError::NotFound(_))
+    ));
+    assert!(deployments_repo
+        .find_by_id("unknown")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn unreadable_deployments_are_errors() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+    let DbPool::Sqlite(pool) = &app.db_pool else {
+        unreachable!("tests run on sqlite")
+    };
+    // revisions are immutable, a row written by an older or broken version is simulated
+    sqlx::query("DROP TRIGGER deployment_revisions_immutable")
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE deployment_revisions SET infra_outputs = 'not json' WHERE deployment_id = $1")
+        .bind(&id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let deployments_repo = SqlDeploymentRepository::new(app.db_pool.clone());
+    assert!(matches!(
+        deployments_repo.find_by_id(&id).await,
+        Err(DeploymentError::Serialization(_))
+    ));
+
+    let response = app
+        .request(Method::GET, &format!("/deployments/{}", id), &alice, None)
+        .await;
+    assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn patch_requires_the_current_etag() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+    let uri = format!("/deployments/{}", id);
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &uri,
+            &alice,
+            Some(json!({ "name": "testnet" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let response = app
+        .request_with_headers(
+            Method::PATCH,
+            &uri,
+            &alice,
+            Some(json!({ "name": "testnet" })),
+            &[("if-match", "\"1\"")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("etag"), Some("\"2\""));
+    assert_eq!(response.json()["name"], "testnet");
+
+    let response = app
+        .request_with_headers(
+            Method::PATCH,
+            &uri,
+            &alice,
+            Some(json!({ "name": "mainnet" })),
+            &[("if-match", "\"1\"")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+
+    let response = app
+        .request_with_headers(
+            Method::PATCH,
+            &uri,
+            &alice,
+            Some(json!({ "name": "" })),
+            &[("if-match", "\"2\"")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn revisions_are_diffed_and_restored() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+
+    let mut update = deployment("testnet");
+    update["id"] = json!(id);
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/deployments/{}", id),
+            &alice,
+            Some(update.clone()),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let response = app
+        .request_with_headers(
+            Method::PUT,
+            &format!("/deployments/{}", id),
+            &alice,
+            Some(update),
+            &[("if-match", "\"1\"")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("etag"), Some("\"2\""));
+
+    let response = app
+        .request(
+            Method::GET,
+            &format!("/deployments/{}/revisions", id),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.json().as_array().unwrap().len(), 2);
+
+    let response = app
+        .request(
+            Method::GET,
+            &format!("/deployments/{}/diff?from=1", id),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json()["name"],
+        json!({ "before": "devnet", "after": "testnet" })
+    );
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/deployments/{}/revisions/1/restore", id),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .request(Method::GET, &format!("/deployments/{}", id), &alice, None)
+        .await;
+    assert_eq!(response.json()["name"], "devnet");
+    assert_eq!(response.header("etag"), Some("\"3\""));
+}
+
+#[tokio::test]
+async fn deployments_are_shared_through_org_roles() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let bob = app.sign_in(BOB).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+    let uri = format!("/deployments/{}", id);
+
+    let response = app.request(Method::GET, &uri, &bob, None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/orgs/{}/members/{}", ALICE, BOB),
+            &alice,
+            Some(json!({ "role": "viewer" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.request(Method::GET, &uri, &bob, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.request(Method::DELETE, &uri, &bob, None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn api_tokens_are_limited_to_their_scopes() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/tokens",
+            &alice,
+            Some(json!({ "name": "ci", "scopes": ["deployments:read"] })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let token = response.json()["token"].as_str().unwrap().to_string();
+
+    let response = app.request(Method::GET, "/deployments", &token, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .request(
+            Method::POST,
+            "/deployments",
+            &token,
+            Some(deployment("devnet")),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app.request(Method::GET, "/tokens", &token, None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn artifacts_are_verified_and_checksummed() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+    let uri = format!("/deployments/{}/artifact", id);
+
+    let response = app
+        .upload_artifact(&id, &alice, &artifact(&["genesis.json"]))
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app.request(Method::HEAD, &uri, &alice, None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let zip = artifact(&["genesis.json", "rollup-config.json"]);
+    let checksum = hex::encode(Sha256::digest(&zip));
+    let response = app.upload_artifact(&id, &alice, &zip).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header(CHECKSUM_HEADER), Some(checksum.as_str()));
+
+    let response = app.request(Method::GET, &uri, &alice, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body, zip);
+    assert_eq!(response.header(CHECKSUM_HEADER), Some(checksum.as_str()));
+
+    // filesystem storage can't hand out presigned urls
+    let response = app
+        .request(
+            Method::POST,
+            &format!("{}/upload-url?size={}", uri, zip.len()),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_IMPLEMENTED);
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("{}/upload-url?size={}", uri, MAX_ARTIFACT_SIZE + 1),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    let response = app.request(Method::DELETE, &uri, &alice, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.request(Method::HEAD, &uri, &alice, None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn mutations_are_audited() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let id = app.create_deployment(&alice, "devnet").await;
+
+    let response = app
+        .request_with_headers(
+            Method::PATCH,
+            &format!("/deployments/{}", id),
+            &alice,
+            Some(json!({ "name": "testnet" })),
+            &[("if-match", "*")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .request(
+            Method::GET,
+            &format!("/deployments/{}/audit", id),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let actions: Vec<Value> = response.json()["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["action"].clone())
+        .collect();
+    assert_eq!(
+        actions,
+        vec![json!("deployment.updated"), json!("deployment.created")]
+    );
+}
+
+fn contracts_job(l1_rpc_url: &str) -> Value {
+    json!({
+        "spec": { "kind": "contracts" },
+        "secrets": { "l1_rpc_url": l1_rpc_url, "deployer_private_key": "0xsecret" }
+    })
+}
+
+#[tokio::test]
+async fn jobs_are_validated_before_being_queued() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let deployment_id = app.create_deployment(&alice, "sepolia").await;
+    let jobs_uri = format!("/deployments/{}/jobs", deployment_id);
+
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &alice,
+            Some(json!({ "spec": { "kind": "contracts" } })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &alice,
+            Some(json!({ "spec": { "kind": "infra", "host": "chain.example.com" } })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let bob = app.sign_in(BOB).await;
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &bob,
+            Some(contracts_job("http://l1")),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jobs_run_in_the_background_and_stream_their_logs() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let deployment_id = app.create_deployment(&alice, "sepolia").await;
+    let jobs_uri = format!("/deployments/{}/jobs", deployment_id);
+
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &alice,
+            Some(contracts_job("http://l1")),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    assert!(!String::from_utf8_lossy(&response.body).contains("0xsecret"));
+    let job_id = response.json()["id"].as_str().unwrap().to_string();
+
+    let job = app
+        .wait_for_job(&alice, &job_id, |job| job["finished_at"].is_i64())
+        .await;
+    assert_eq!(job["status"], "succeeded");
+    assert!(job["started_at"].is_i64());
+
+    let logs = app
+        .request(Method::GET, &format!("/jobs/{}/logs", job_id), &alice, None)
+        .await;
+    assert_eq!(logs.status, StatusCode::OK);
+    let logs = String::from_utf8(logs.body).unwrap();
+    assert!(logs.contains("data: deploying"));
+    assert!(logs.contains("data: deployed"));
+    assert!(logs.contains("event: end\ndata: succeeded"));
+
+    // resuming skips the lines already received
+    let first_id = logs
+        .lines()
+        .find_map(|line| line.strip_prefix("id: "))
+        .unwrap()
+        .to_string();
+    let resumed = app
+        .request_with_headers(
+            Method::GET,
+            &format!("/jobs/{}/logs", job_id),
+            &alice,
+            None,
+            &[("last-event-id", &first_id)],
+        )
+        .await;
+    let resumed = String::from_utf8(resumed.body).unwrap();
+    assert!(!resumed.contains(&format!("id: {}\n", first_id)));
+    assert!(resumed.contains("event: end"));
+
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &alice,
+            Some(contracts_job("http://fails")),
+        )
+        .await;
+    let job_id = response.json()["id"].as_str().unwrap().to_string();
+    let job = app
+        .wait_for_job(&alice, &job_id, |job| job["finished_at"].is_i64())
+        .await;
+    assert_eq!(job["status"], "failed");
+    assert!(job["error"]
+        .as_str()
+        .unwrap()
+        .contains("forge script failed"));
+
+    let jobs = app
+        .request(Method::GET, &jobs_uri, &alice, None)
+        .await
+        .json();
+    assert_eq!(jobs.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn running_jobs_can_be_cancelled() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let deployment_id = app.create_deployment(&alice, "sepolia").await;
+    let jobs_uri = format!("/deployments/{}/jobs", deployment_id);
+
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &alice,
+            Some(contracts_job("http://hangs")),
+        )
+        .await;
+    let job_id = response.json()["id"].as_str().unwrap().to_string();
+    app.wait_for_job(&alice, &job_id, |job| job["status"] == "running")
+        .await;
+
+    // one job at a time per deployment
+    let response = app
+        .request(
+            Method::POST,
+            &jobs_uri,
+            &alice,
+            Some(contracts_job("http://l1")),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let cancel_uri = format!("/jobs/{}/cancel", job_id);
+    let response = app.request(Method::POST, &cancel_uri, &alice, None).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    assert_eq!(response.json()["cancel_requested"], true);
+
+    let job = app
+        .wait_for_job(&alice, &job_id, |job| job["finished_at"].is_i64())
+        .await;
+    assert_eq!(job["status"], "cancelled");
+
+    let response = app.request(Method::POST, &cancel_uri, &alice, None).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn one_unfinished_job_per_deployment_is_enforced_by_the_database() {
+    let app = TestApp::new().await;
+    let job_repo = SqlJobRepository::new(app.db_pool.clone());
+    let spec = JobSpec::Contracts {
+        deploy_deterministic_deployer: false,
+    };
+
+    // as two requests that both found no job in progress would
+    let (first, second) = tokio::join!(
+        job_repo.create("first", "devnet", &spec, ALICE),
+        job_repo.create("second", "devnet", &spec, ALICE),
+    );
+    let created: Vec<Job> = [first.unwrap(), second.unwrap()]
+        .into_iter()
+        .flatten()
+        .collect();
+    assert_eq!(created.len(), 1);
+    assert!(job_repo
+        .create("other", "testnet", &spec, ALICE)
+        .await
+        .unwrap()
+        .is_some());
+
+    job_repo
+        .finish(&created[0].id, JobStatus::Failed, Some("failed"))
+        .await
+        .unwrap();
+    assert!(job_repo
+        .create("third", "devnet", &spec, ALICE)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn consoles_without_workers_leave_jobs_alone() {
+    let app = TestApp::new().await;
+    let job_repo = Arc::new(SqlJobRepository::new(app.db_pool.clone()));
+    let spec = JobSpec::Contracts {
+        deploy_deterministic_deployer: false,
+    };
+    let job = job_repo
+        .create("queued", "devnet", &spec, ALICE)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // as the lambda api starting next to a self hosted console
+    let runner = JobRunner::new(job_repo.clone(), Arc::new(ScriptedExecutor), 0);
+    assert!(!runner.is_enabled());
+    assert_eq!(runner.recover().await.unwrap(), 0);
+    assert_eq!(
+        job_repo.find_by_id(&job.id).await.unwrap().unwrap().status,
+        JobStatus::Queued
+    );
+
+    let runner = JobRunner::new(job_repo.clone(), Arc::new(ScriptedExecutor), 1);
+    assert_eq!(runner.recover().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn webhooks_are_validated_and_managed_by_org_owners() {
+    let app = TestApp::new().await;
+    let alice = app.sign_in(ALICE).await;
+    let bob = app.sign_in(BOB).await;
+    app.request(Method::GET, "/orgs", &alice, None).await;
+    let uri = format!("/orgs/{}/webhooks", ALICE);
+
+    for payload in [
+        json!({ "url": "ftp://example.com" }),
+        json!({ "url": "https://example.com", "secret": " " }),
+        json!({ "url": "https://example.com", "events": ["deployment.exploded"] }),
+        // webhooks can't reach the console network or the cloud metadata endpoint
+        json!({ "url": "http://127.0.0.1:4000/hook" }),
+        json!({ "url": "http://10.0.0.1/hook" }),
+        json!({ "url": "http://169.254.169.254/latest/meta-data" }),
+        json!({ "url": "http://[::1]/hook" }),
+        json!({ "url": "http://[fd00:ec2::254]/hook" }),
+    ] {
+        let response = app.request(Method::POST, &uri, &alice, Some(payload)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+
+    let response = app
+        .request(
+            Method::POST,
+            &uri,
+            &bob,
+            Some(json!({ "url": "https://example.com" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    // an ip url, the tests can't resolve hosts
+    let response = app
+        .request(
+            Method::POST,
+            &uri,
+            &alice,
+            Some(json!({ "url": "https://203.0.113.10/hook" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let webhook = response.json();
+    assert_eq!(webhook["secret"].as_str().unwrap().len(), 64);
+
+    let webhooks = app.request(Method::GET, &uri, &alice, None).await.json();
+    assert_eq!(webhooks.as_array().unwrap().len(), 1);
+    assert!(webhooks[0].get("secret").is_none());
+
+    let webhook_uri = format!("{}/{}", uri, webhook["id"].as_str().unwrap());
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/orgs/{}/webhooks/{}", BOB, webhook["id"].as_str().unwrap()),
+            &bob,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let response = app
+        .request(Method::DELETE, &webhook_uri, &alice, None)
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    let response = app
+        .request(Method::DELETE, &webhook_uri, &alice, None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deployment_events_are_signed_retried_and_logged() {
+    let targets = TargetPolicy {
+        allow_private: true,
+    };
+    let app = TestApp::with_webhook_targets(targets).await;
+    let alice = app.sign_in(ALICE).await;
+    app.request(Method::GET, "/orgs", &alice, None).await;
+    let uri = format!("/orgs/{}/webhooks", ALICE);
+
+    let (url, received) = webhook_receiver(1).await;
+    let webhook = app
+        .request(
+            Method::POST,
+            &uri,
+            &alice,
+            Some(json!({
+                "url": url,
+                "secret": "s3cret",
+                "events": ["deployment.created", "deployment.deleted"],
+            })),
+        )
+        .await
+        .json();
+    let unreachable = app
+        .request(
+            Method::POST,
+            &uri,
+            &alice,
+            Some(json!({ "url": "http://127.0.0.1:1/hook" })),
+        )
+        .await
+        .json();
+
+    let id = app.create_deployment(&alice, "devnet").await;
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/deployments/{}", id),
+            &alice,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let dispatcher = WebhookDispatcher::new(
+        Arc::new(SqlWebhookRepository::new(app.db_pool.clone())),
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        },
+    )
+    .with_targets(targets);
+    dispatcher.dispatch_due().await.unwrap();
+
+    // one delivery failed once and was retried, both end up delivered and signed
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 3);
+    for (headers, body) in &received {
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        let timestamp = signature
+            .trim_start_matches("t=")
+            .split(',')
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(signature, sign("s3cret", timestamp, body));
+
+        let payload: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], headers[EVENT_HEADER].to_str().unwrap());
+        assert_eq!(payload["deployment_id"], json!(id));
+        assert_eq!(payload["org_id"], json!(ALICE));
+    }
+
+    let deliveries = app
+        .request(
+            Method::GET,
+            &format!("{}/{}/deliveries", uri, webhook["id"].as_str().unwrap()),
+            &alice,
+            None,
+        )
+        .await
+        .json();
+    let mut events: Vec<&str> = deliveries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["event"].as_str().unwrap())
+        .collect();
+    events.sort();
+    assert_eq!(events, vec!["deployment.created", "deployment.deleted"]);
+    for delivery in deliveries.as_array().unwrap() {
+        assert_eq!(delivery["status"], "succeeded");
+        assert_eq!(delivery["response_status"], 204);
+    }
+    let attempts: i64 = deliveries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["attempts"].as_i64().unwrap())
+        .sum();
+    assert_eq!(attempts, 3);
+
+    // the unreachable webhook gets every event and gives up after the last attempt
+    let failed = app
+        .request(
+            Method::GET,
+            &format!(
+                "{}/{}/deliveries?status=failed",
+                uri,
+                unreachable["id"].as_str().unwrap()
+            ),
+            &alice,
+            None,
+        )
+        .await
+        .json();
+    let failed = failed.as_array().unwrap();
+    assert_eq!(failed.len(), 2);
+    assert!(failed
+        .iter()
+        .all(|d| d["attempts"] == 3 && d["error"].is_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deliveries_to_private_addresses_are_refused() {
+    // saved by a console allowing them, delivered by one that doesn't
+    let app = TestApp::with_webhook_targets(TargetPolicy {
+        allow_private: true,
+    })
+    .await;
+    let alice = app.sign_in(ALICE).await;
+    app.request(Method::GET, "/orgs", &alice, None).await;
+    let uri = format!("/orgs/{}/webhooks", ALICE);
+
+    let (url, received) = webhook_receiver(0).await;
+    let webhook = app
+        .request(Method::POST, &uri, &alice, Some(json!({ "url": url })))
+        .await
+        .json();
+    app.create_deployment(&alice, "devnet").await;
+
+    let dispatcher = WebhookDispatcher::new(
+        Arc::new(SqlWebhookRepository::new(app.db_pool.clone())),
+        RetryPolicy {
+            max_attempts: 1,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        },
+    );
+    dispatcher.dispatch_due().await.unwrap();
+
+    assert!(received.lock().unwrap().is_empty());
+    let failed = app
+        .request(
+            Method::GET,
+            &format!(
+                "{}/{}/deliveries?status=failed",
+                uri,
+                webhook["id"].as_str().unwrap()
+            ),
+            &alice,
+            None,
+        )
+        .await
+        .json();
+    assert_eq!(failed.as_array().unwrap().len(), 1);
+    assert!(failed[0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("non public address"));
+}
@@ -0,0 +1,34 @@
+use opraas_server::utils::zip::zip_folder;
+use std::{fs, io::Cursor};
+use zip::ZipArchive;
+
+#[test]
+fn zip_folder_keeps_the_directory_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("infra/helm/sequencer/templates")).unwrap();
+    fs::create_dir_all(dir.path().join("infra/aws")).unwrap();
+    fs::write(dir.path().join("config.toml"), "[network]").unwrap();
+    fs::write(
+        dir.path().join("infra/helm/sequencer/templates/node.yaml"),
+        "kind: Deployment",
+    )
+    .unwrap();
+    fs::write(dir.path().join("infra/aws/main.tf"), "terraform {}").unwrap();
+
+    let archive = ZipArchive::new(Cursor::new(zip_folder(dir.path()).unwrap())).unwrap();
+    let names: Vec<&str> = archive.file_names().collect();
+
+    for expected in [
+        "config.toml",
+        "infra/",
+        "infra/aws/main.tf",
+        "infra/helm/sequencer/templates/node.yaml",
+    ] {
+        assert!(
+            names.contains(&expected),
+            "{} missing from {:?}",
+            expected,
+            names
+        );
+    }
+}
@@ -27,6 +27,19 @@ export default $config({
     // api bucket for deployment artifacts
     const bucket = new sst.aws.Bucket(`${PROJECT_NAME}-artifacts`);
 
+    // direct uploads wait under staging/ until the api verifies them, drop the ones never completed
+    new aws.s3.BucketLifecycleConfigurationV2(`${PROJECT_NAME}-artifacts-staging`, {
+      bucket: bucket.name,
+      rules: [
+        {
+          id: "expire-staged-artifacts",
+          status: "Enabled",
+          filter: { prefix: "staging/" },
+          expiration: { days: 1 },
+        },
+      ],
+    });
+
     // vpc for api-db
     const vpc = new sst.aws.Vpc(`${PROJECT_NAME}-vpc`, {
       nat: "ec2", // sharing vpc with api
@@ -43,20 +56,42 @@ export default $config({
     });
     const DATABASE_URL = $interpolate`postgres://${db.username}:${db.password}@${db.host}:${db.port}/${db.database}`;
 
-    // api
+    // api, deployment jobs need a self hosted console and are refused here
     const api = new sst.aws.Function(`${PROJECT_NAME}-api`, {
       vpc,
       handler: "bootstrap",
       bundle: "../../target/lambda/opraas_server",
       architecture: "arm64", // or x86_64
       runtime: "provided.al2023",
-      url: true,
+      url: {
+        // the ui sends the deployment etag back as If-Match
+        cors: { exposeHeaders: ["etag"] },
+      },
       link: [db, bucket],
       timeout: "10 seconds",
       environment: {
         ENV: "prod",
         DATABASE_URL,
         ARTIFACTS_BUCKET: bucket.name,
+        SIWE_DOMAINS: process.env.SIWE_DOMAINS ?? `${PROJECT_NAME}.wakeuplabs.link`,
+      },
+    });
+
+    // the api can't send webhooks in the background as a lambda, due deliveries are sent every minute instead
+    new sst.aws.Cron(`${PROJECT_NAME}-webhooks`, {
+      schedule: "rate(1 minute)",
+      job: {
+        vpc,
+        handler: "bootstrap",
+        bundle: "../../target/lambda/webhook_dispatcher",
+        architecture: "arm64", // or x86_64
+        runtime: "provided.al2023",
+        link: [db],
+        // deliveries time out after 10 seconds, each batch is sent at once
+        timeout: "1 minute",
+        environment: {
+          DATABASE_URL,
+        },
       },
     });
 
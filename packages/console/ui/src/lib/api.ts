@@ -12,8 +12,7 @@ export const api = axios.create({
 
 api.interceptors.request.use(
   async (config) => {
-    const { message, signature } = safeParseJSON(window.localStorage.getItem(SIWE_LOCALSTORAGE_KEY));
-    const token =  btoa(`${message}||${signature}`);
+    const { token } = safeParseJSON(window.localStorage.getItem(SIWE_LOCALSTORAGE_KEY));
 
     if (token) {
       config.headers.Authorization = `Bearer ${token}`;
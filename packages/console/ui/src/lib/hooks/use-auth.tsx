@@ -3,6 +3,10 @@ import { createContext, useContext, useEffect, useState } from "react";
 import { useAccount, useSignMessage } from "wagmi";
 import { safeParseJSON } from "../utils";
 import { SIWE_LOCALSTORAGE_KEY } from "@/shared/constants/siwe";
+import { api } from "../api";
+
+// signed messages are only exchanged once for a session token
+const SIWE_MESSAGE_TTL_MS = 5 * 60 * 1000;
 
 export type AuthUser = {
   id: string;
@@ -24,7 +28,7 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
   const [loading, setLoading] = useState<boolean>(true);
   const { signMessageAsync } = useSignMessage();
 
-  function createSiweMessage(address: string, statement: string) {
+  function createSiweMessage(address: string, statement: string, nonce: string) {
     const message = new SiweMessage({
       domain: window.location.host,
       address,
@@ -32,6 +36,8 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
       uri: origin,
       version: "1",
       chainId: 1,
+      nonce,
+      expirationTime: new Date(Date.now() + SIWE_MESSAGE_TTL_MS).toISOString(),
     });
     return message.prepareMessage();
   }
@@ -43,9 +49,11 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
       throw new Error("Wallet not connected");
     }
 
+    const { data: nonce } = await api.get("/auth/nonce");
     const message = await createSiweMessage(
       address,
-      "Sign in with Ethereum to the app."
+      "Sign in with Ethereum to the app.",
+      nonce.nonce
     );
 
     const signature = await signMessageAsync({
@@ -53,9 +61,18 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
       account: address,
     });
 
+    const { data: session } = await api.post("/auth/verify", {
+      message,
+      signature,
+    });
+
     window.localStorage.setItem(
       SIWE_LOCALSTORAGE_KEY,
-      JSON.stringify({ message, signature })
+      JSON.stringify({
+        token: session.token,
+        address,
+        expiresAt: session.expires_at,
+      })
     );
     setUser({ id: address });
     setLoading(false);
@@ -69,27 +86,27 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
   };
 
   const getToken = () => {
-    const { message, signature } = safeParseJSON(
+    const { token } = safeParseJSON(
       window.localStorage.getItem(SIWE_LOCALSTORAGE_KEY)
     );
-    return btoa(`${message}||${signature}`);
+    return token;
   };
 
   useEffect(() => {
     try {
-      const { message, signature } = safeParseJSON(
+      const session = safeParseJSON(
         window.localStorage.getItem(SIWE_LOCALSTORAGE_KEY)
       );
 
-      const SIWEObject = new SiweMessage(message);
-      SIWEObject.validate(signature).then((r) => {
-        if (address !== r.address) {
-          throw new Error("Address mismatch");
-        }
+      if (!session?.token || session.expiresAt * 1000 <= Date.now()) {
+        throw new Error("Session expired");
+      }
+      if (address !== session.address) {
+        throw new Error("Address mismatch");
+      }
 
-        setUser({ id: r.address });
-        setLoading(false);
-      });
+      setUser({ id: session.address });
+      setLoading(false);
     } catch (e) {
       window.localStorage.removeItem(SIWE_LOCALSTORAGE_KEY);
       setUser(null);
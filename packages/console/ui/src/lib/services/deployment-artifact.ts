@@ -1,21 +1,71 @@
+import axios, { HttpStatusCode } from "axios";
 import { api } from "../api";
 
 export type DeploymentArtifact = Blob;
 
+type PresignedUrl = {
+  url: string;
+  method: string;
+  headers: Record<string, string>;
+  expires_at: number;
+};
+
+// storage backends that can't be reached directly answer presigned url requests with 501
+const presign = async (request: Promise<{ data: PresignedUrl }>): Promise<PresignedUrl | null> => {
+  try {
+    const { data } = await request;
+    return data;
+  } catch (e) {
+    if (axios.isAxiosError(e) && e.response?.status === HttpStatusCode.NotImplemented) {
+      return null;
+    }
+    throw e;
+  }
+};
+
 export class DeploymentArtifactService {
+  // artifacts go straight to storage when possible, the api only signs the request and verifies the result
   static async set(deploymentId: string, artifact: File): Promise<void> {
-    const data = new FormData();
-    data.append("artifact.zip", artifact, "artifact.zip");
+    const presigned = await presign(
+      api.post<PresignedUrl>(`deployments/${deploymentId}/artifact/upload-url`)
+    );
+    if (!presigned) {
+      const data = new FormData();
+      data.append("artifact.zip", artifact, "artifact.zip");
 
-    await api.put(`deployments/${deploymentId}/artifact`, data, {
-      headers: {
-        'content-type': 'multipart/form-data'
-      }
+      await api.put(`deployments/${deploymentId}/artifact`, data, {
+        headers: {
+          'content-type': 'multipart/form-data'
+        }
+      });
+      return;
+    }
+
+    await axios.request({
+      url: presigned.url,
+      method: presigned.method,
+      headers: presigned.headers,
+      data: artifact,
     });
+
+    await api.post(`deployments/${deploymentId}/artifact/complete`);
   }
 
   static async findById(deploymentId: string): Promise<Blob> {
-    const res = await api.get(`deployments/${deploymentId}/artifact`, {
+    const presigned = await presign(
+      api.get<PresignedUrl>(`deployments/${deploymentId}/artifact/download-url`)
+    );
+    if (!presigned) {
+      const res = await api.get(`deployments/${deploymentId}/artifact`, {
+        responseType: "blob",
+      });
+      return res.data;
+    }
+
+    const res = await axios.request({
+      url: presigned.url,
+      method: presigned.method,
+      headers: presigned.headers,
       responseType: "blob",
     });
 
@@ -1,5 +1,11 @@
 import { api } from "../api";
 
+export type InfraOutputs = {
+  cluster_name: string | null;
+  region: string | null;
+  load_balancer_hostname: string | null;
+};
+
 export type Deployment = {
   id: string;
   name: string;
@@ -7,9 +13,12 @@ export type Deployment = {
   release_tag: string;
   release_registry: string;
   infra_base_url: string | null;
+  infra_outputs?: InfraOutputs | null;
   contracts_addresses: string | null;
   network_config: any;
   accounts_config: any;
+  /** Revision the deployment was read at, sent back as If-Match when updating it */
+  etag?: string;
 };
 
 export class DeploymentService {
@@ -29,12 +38,19 @@ export class DeploymentService {
 
   static async findById(id: string): Promise<Deployment> {
     const res = await api.get(`deployments/${id}`); 
-    return res.data;
+    return { ...res.data, etag: res.headers["etag"] };
   }
 
-  static async update(deployment: Deployment): Promise<Deployment> {
-    const res = await api.put(`deployments/${deployment.id}`, deployment);
-    return res.data;
+  // fails with 412 if the deployment changed since it was read, it has to be read again
+  static async update({ etag, ...deployment }: Deployment): Promise<Deployment> {
+    if (!etag) {
+      throw new Error("Deployment must be read with findById before updating it");
+    }
+
+    const res = await api.put(`deployments/${deployment.id}`, deployment, {
+      headers: { "If-Match": etag },
+    });
+    return { ...res.data, etag: res.headers["etag"] };
   }
 
   static async delete(id: string): Promise<Deployment> {
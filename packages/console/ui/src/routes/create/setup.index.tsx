@@ -5,7 +5,9 @@ import { Input } from "@/components/ui/input";
 import { Switch } from "@/components/ui/switch";
 import { useToast } from "@/lib/hooks/use-toast";
 import { useCreateProjectMutation } from "@/lib/queries/project";
+import { PROJECT_ROLES, ProjectRole } from "@/lib/services/project";
 import { createFileRoute, useRouter } from "@tanstack/react-router";
+import { AxiosError } from "axios";
 import { DownloadIcon } from "lucide-react";
 import React, { useCallback, useMemo, useState } from "react";
 
@@ -17,6 +19,7 @@ enum SetupStep {
   L1_CHAIN,
   L2_CHAIN,
   GOVERNANCE,
+  ROLES,
   DOWNLOAD,
 }
 
@@ -24,6 +27,7 @@ const steps = [
   { step: SetupStep.L1_CHAIN, label: "L1" },
   { step: SetupStep.L2_CHAIN, label: "L2" },
   { step: SetupStep.GOVERNANCE, label: "L2 Governance" },
+  { step: SetupStep.ROLES, label: "Roles" },
   { step: SetupStep.DOWNLOAD, label: "Download" },
 ];
 
@@ -31,31 +35,47 @@ function RouteComponent() {
   const router = useRouter();
   const { toast } = useToast();
   const [step, setStep] = useState<SetupStep>(SetupStep.L1_CHAIN);
+  const [name, setName] = useState<string>("opruaas-project");
   const [mainnet, setMainnet] = useState<boolean>(false);
   const [chainId, setChainId] = useState<number>(128930);
   const [governanceSymbol, setGovernanceSymbol] = useState<string>("");
   const [governanceName, setGovernanceName] = useState<string>("");
+  const [roles, setRoles] = useState<Record<ProjectRole, string>>({
+    admin: "",
+    batcher: "",
+    sequencer: "",
+    proposer: "",
+    deployer: "",
+    challenger: "",
+  });
 
   const { mutateAsync, isPending } = useCreateProjectMutation();
 
   const onDownload = useCallback(async () => {
     try {
       const res = await mutateAsync({
+        name,
         mainnet,
         chainId,
         governanceSymbol,
         governanceName,
+        roles,
       });
       const url = window.URL.createObjectURL(res);
       window.open(url, "_blank");
     } catch (e) {
+      // the blob response type also applies to errors, the validation message is in it
+      const message =
+        e instanceof AxiosError && e.response?.data instanceof Blob
+          ? await e.response.data.text()
+          : "Failed to create project";
       toast({
         title: "Error",
-        description: "Failed to create project",
+        description: message,
         variant: "destructive",
       });
     }
-  }, [mainnet, chainId, governanceName, governanceSymbol]);
+  }, [name, mainnet, chainId, governanceName, governanceSymbol, roles]);
 
   const currentStepIndex = useMemo(
     () => steps.findIndex((s) => s.step === step),
@@ -83,7 +103,12 @@ function RouteComponent() {
           <L1ChainStep mainnet={mainnet} setMainnet={setMainnet} />
         )}
         {step == SetupStep.L2_CHAIN && (
-          <L2ChainStep chainId={chainId} setChainId={setChainId} />
+          <L2ChainStep
+            name={name}
+            setName={setName}
+            chainId={chainId}
+            setChainId={setChainId}
+          />
         )}
         {step == SetupStep.GOVERNANCE && (
           <L2GovernanceStep
@@ -93,6 +118,9 @@ function RouteComponent() {
             setSymbol={setGovernanceSymbol}
           />
         )}
+        {step == SetupStep.ROLES && (
+          <RolesStep roles={roles} setRoles={setRoles} />
+        )}
         {step == SetupStep.DOWNLOAD && (
           <DownloadStep onDownload={onDownload} isPending={isPending} />
         )}
@@ -128,9 +156,11 @@ const L1ChainStep: React.FC<{
 };
 
 const L2ChainStep: React.FC<{
+  name: string;
+  setName: (name: string) => void;
   chainId: number;
   setChainId: (chainId: number) => void;
-}> = ({ chainId, setChainId }) => {
+}> = ({ name, setName, chainId, setChainId }) => {
   return (
     <Card>
       <CardTitle>Your L2 Chain Details</CardTitle>
@@ -139,11 +169,18 @@ const L2ChainStep: React.FC<{
         tune the generated config file before deployment.
       </CardDescription>
 
+      <Input
+        value={name}
+        onChange={(e) => setName(e.target.value)}
+        className="mt-10"
+        placeholder="Project name"
+      />
+
       <Input
         type="number"
         value={chainId}
         onChange={(e) => setChainId(Number(e.target.value))}
-        className="mt-10"
+        className="mt-2"
         placeholder="L2 chain id"
       />
     </Card>
@@ -181,6 +218,31 @@ const L2GovernanceStep: React.FC<{
   );
 };
 
+const RolesStep: React.FC<{
+  roles: Record<ProjectRole, string>;
+  setRoles: (roles: Record<ProjectRole, string>) => void;
+}> = ({ roles, setRoles }) => {
+  return (
+    <Card>
+      <CardTitle>Roles</CardTitle>
+      <CardDescription className="mt-4 md:mt-6">
+        Addresses operating your chain. Private keys are never sent, add them
+        to the generated project .env file before deploying.
+      </CardDescription>
+
+      {PROJECT_ROLES.map((role, i) => (
+        <Input
+          key={role}
+          value={roles[role]}
+          onChange={(e) => setRoles({ ...roles, [role]: e.target.value })}
+          className={i === 0 ? "mt-10" : "mt-2"}
+          placeholder={`${role[0].toUpperCase()}${role.slice(1)} address`}
+        />
+      ))}
+    </Card>
+  );
+};
+
 const DownloadStep: React.FC<{
   onDownload: () => void;
   isPending: boolean;
@@ -1,4 +1,4 @@
-use crate::domain::{self, artifact::Artifact};
+use crate::domain::{self, artifact::Artifact, ArtifactError};
 
 pub struct ArtifactBuilderService<AR, ASR>
 where
@@ -21,7 +21,7 @@ where
         }
     }
 
-    pub fn build(&self, artifact: &Artifact) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn build(&self, artifact: &Artifact) -> Result<(), ArtifactError> {
         if !self.artifact_source_repository.exists(artifact) {
             self.artifact_source_repository.pull(artifact)?;
         }
@@ -1,5 +1,5 @@
 use crate::domain;
-use crate::domain::artifact::Artifact;
+use crate::domain::{artifact::Artifact, ArtifactError};
 
 pub struct ArtifactInitializer<SR>
 where
@@ -16,7 +16,7 @@ where
         Self { source_repository }
     }
 
-    pub fn initialize(&self, artifact: &Artifact) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn initialize(&self, artifact: &Artifact) -> Result<(), ArtifactError> {
         if self.source_repository.exists(artifact) {
             return Ok(());
         }
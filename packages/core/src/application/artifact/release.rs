@@ -1,4 +1,4 @@
-use crate::domain::{self, artifact::Artifact, Release};
+use crate::domain::{self, artifact::Artifact, Release, ReleaseError};
 
 pub struct ArtifactReleaserService<T>
 where
@@ -20,7 +20,7 @@ where
         artifact: &Artifact,
         release_name: &str,
         registry_url: &str,
-    ) -> Result<Release, Box<dyn std::error::Error>> {
+    ) -> Result<Release, ReleaseError> {
         self.release_repository
             .create_for_artifact(artifact, release_name, registry_url)
     }
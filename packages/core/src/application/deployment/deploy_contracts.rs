@@ -1,4 +1,4 @@
-use crate::domain::{self, Deployment, Project};
+use crate::domain::{self, Deployment, DeploymentError, Project};
 
 pub struct ContractsDeployerService<DR, DAR, CDP>
 where
@@ -31,7 +31,7 @@ where
         deployment: &mut Deployment,
         deploy_deterministic_deployer: bool,
         slow: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
         let deployment_artifact =
             self.contracts_deployer
                 .deploy(project, deployment, deploy_deterministic_deployer, slow)?;
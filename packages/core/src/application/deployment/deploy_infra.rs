@@ -1,4 +1,5 @@
-use crate::domain::{self, Deployment, DeploymentOptions, Project};
+use crate::domain::{self, Deployment, DeploymentError, DeploymentOptions, InfraPlan, Project};
+use std::path::Path;
 
 pub struct InfraDeployerService<ID, DR, PIR>
 where
@@ -30,7 +31,7 @@ where
         project: &Project,
         deployment: &mut Deployment,
         opts: &DeploymentOptions,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
         self.project_infra_repository.pull(project)?;
 
         self.infra_deployer
@@ -42,3 +43,42 @@ where
         Ok(())
     }
 }
+
+impl<ID, DR, PIR> InfraDeployerService<ID, DR, PIR>
+where
+    ID: domain::deployment::TInfraDeployerProvider + domain::deployment::TInfraPlanner,
+    DR: domain::deployment::TDeploymentRepository,
+    PIR: domain::project::TProjectInfraRepository,
+{
+    pub async fn plan(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        plan_file: &Path,
+    ) -> Result<InfraPlan, DeploymentError> {
+        self.project_infra_repository.pull(project)?;
+
+        self.infra_deployer
+            .plan(project, deployment, opts, plan_file)
+            .await
+    }
+
+    pub async fn apply_plan(
+        &self,
+        project: &Project,
+        deployment: &mut Deployment,
+        opts: &DeploymentOptions,
+        plan_file: &Path,
+    ) -> Result<(), DeploymentError> {
+        self.project_infra_repository.pull(project)?;
+
+        self.infra_deployer
+            .apply_plan(project, deployment, opts, plan_file)
+            .await?;
+
+        self.deployment_repository.save(deployment).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    domain::{Deployment, DeploymentOptions, DnsError, DnsRecord, TDnsProvider, DEFAULT_DNS_TTL},
+    system,
+};
+use std::path::Path;
+
+pub struct DeploymentDnsService {
+    dns_provider: Option<Box<dyn TDnsProvider>>,
+}
+
+// implementations ========================================================
+
+impl Default for DeploymentDnsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeploymentDnsService {
+    pub fn new() -> Self {
+        Self { dns_provider: None }
+    }
+
+    /// Also applies the records through `dns_provider` instead of only writing them
+    pub fn with_provider(mut self, dns_provider: Box<dyn TDnsProvider>) -> Self {
+        self.dns_provider = Some(dns_provider);
+        self
+    }
+
+    /// Writes the records of the deployment hostnames to `dir` as `dns.zone` and `dns.json`.
+    /// Empty if the load balancer address isn't known yet
+    pub async fn publish(
+        &self,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        dir: &Path,
+    ) -> Result<Vec<DnsRecord>, DnsError> {
+        let records = DnsRecord::for_deployment(deployment, opts, DEFAULT_DNS_TTL)?;
+        if records.is_empty() {
+            return Ok(records);
+        }
+
+        system::write_file(dir.join("dns.zone"), DnsRecord::to_zone(&records))?;
+        system::write_file(
+            dir.join("dns.json"),
+            serde_json::to_string_pretty(&records)?,
+        )?;
+
+        if let Some(dns_provider) = &self.dns_provider {
+            dns_provider.upsert(&records).await?;
+        }
+
+        Ok(records)
+    }
+}
@@ -1,4 +1,6 @@
-use crate::domain::{Deployment, DeploymentArtifact, TDeploymentArtifactsRepository, TDeploymentRepository};
+use crate::domain::{
+    Deployment, DeploymentArtifact, DeploymentError, TDeploymentArtifactsRepository, TDeploymentRepository,
+};
 
 pub struct DeploymentManagerService<T, U>
 where
@@ -21,19 +23,19 @@ where
         }
     }
 
-    pub async fn save(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn save(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
         self.deployment_repository.save(deployment).await
     }
 
-    pub async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, Box<dyn std::error::Error>> {
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, DeploymentError> {
         self.deployment_repository.find_by_id(id).await
     }
 
-    pub async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, Box<dyn std::error::Error>> {
+    pub async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, DeploymentError> {
         self.deployment_repository.find_by_owner(owner_id).await
     }
 
-    pub async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
         self.deployment_repository.delete(deployment).await
     }
 
@@ -41,28 +43,25 @@ where
         &self,
         deployment: &Deployment,
         artifact: DeploymentArtifact,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
         self.deployment_artifacts_repository
             .save(deployment, artifact)
             .await
     }
 
-    pub async fn find_artifact(
-        &self,
-        deployment: &Deployment,
-    ) -> Result<Option<DeploymentArtifact>, Box<dyn std::error::Error>> {
+    pub async fn find_artifact(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError> {
         self.deployment_artifacts_repository
             .find_one(deployment)
             .await
     }
 
-    pub async fn exists_artifact(&self, deployment: &Deployment) -> Result<bool, Box<dyn std::error::Error>> {
+    pub async fn exists_artifact(&self, deployment: &Deployment) -> Result<bool, DeploymentError> {
         self.deployment_artifacts_repository
             .exists(deployment)
             .await
     }
 
-    pub async fn delete_artifact(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn delete_artifact(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
         self.deployment_artifacts_repository
             .delete(deployment)
             .await
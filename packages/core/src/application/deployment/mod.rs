@@ -1,5 +1,7 @@
 pub mod deploy_contracts;
 pub mod deploy_infra;
+pub mod dns;
 pub mod manager;
 pub mod monitor;
 pub mod run;
+pub mod template;
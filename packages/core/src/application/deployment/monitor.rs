@@ -1,4 +1,4 @@
-use crate::domain::{Deployment, DeploymentMonitorOptions, Project, TDeploymentMonitorRunner};
+use crate::domain::{Deployment, DeploymentError, DeploymentMonitorOptions, Project, TDeploymentMonitorRunner};
 
 pub struct DeploymentMonitorRunnerService<DR>
 where
@@ -22,7 +22,7 @@ where
         project: &Project,
         deployment: &Deployment,
         opts: &DeploymentMonitorOptions,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
         self.deployment_monitor_runner
             .run(project, deployment, opts)
             .await?;
@@ -30,7 +30,7 @@ where
         Ok(())
     }
 
-    pub fn stop(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn stop(&self) -> Result<(), DeploymentError> {
         self.deployment_monitor_runner.stop()?;
 
         Ok(())
@@ -1,4 +1,6 @@
-use crate::domain::{Deployment, DeploymentOptions, Project, TDeploymentRunner, TProjectInfraRepository};
+use crate::domain::{
+    Deployment, DeploymentError, DeploymentOptions, Project, TDeploymentRunner, TProjectInfraRepository,
+};
 
 pub struct DeploymentRunnerService<DR, PIR>
 where
@@ -26,7 +28,7 @@ where
         project: &Project,
         deployment: &Deployment,
         opts: &DeploymentOptions,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
         self.project_infra_repository.pull(project)?;
 
         self.deployment_runner
@@ -36,9 +38,10 @@ where
         Ok(())
     }
 
-    pub fn stop(&self, release_tag: &str, release_namespace: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn stop(&self, release_tag: &str, release_namespace: &str) -> Result<(), DeploymentError> {
         self.deployment_runner
-            .stop(release_tag, release_namespace)?;
+            .stop(release_tag, release_namespace)
+            .await?;
 
         Ok(())
     }
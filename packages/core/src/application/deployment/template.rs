@@ -0,0 +1,40 @@
+use crate::domain::{
+    Deployment, DeploymentError, DeploymentOptions, Project, TDeploymentRenderer, TProjectInfraRepository,
+};
+use std::path::{Path, PathBuf};
+
+pub struct DeploymentTemplateService<DR, PIR>
+where
+    DR: TDeploymentRenderer,
+    PIR: TProjectInfraRepository,
+{
+    deployment_renderer: DR,
+    project_infra_repository: PIR,
+}
+
+impl<DR, PIR> DeploymentTemplateService<DR, PIR>
+where
+    DR: TDeploymentRenderer,
+    PIR: TProjectInfraRepository,
+{
+    pub fn new(deployment_renderer: DR, project_infra_repository: PIR) -> Self {
+        Self {
+            deployment_renderer,
+            project_infra_repository,
+        }
+    }
+
+    pub async fn render(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>, DeploymentError> {
+        self.project_infra_repository.pull(project)?;
+
+        self.deployment_renderer
+            .render(project, deployment, opts, output_dir)
+            .await
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     config::CoreConfig,
-    domain::{self, Project, TProjectInfraRepository},
+    domain::{self, Project, ProjectError, TProjectInfraRepository},
 };
 use std::path::Path;
 
@@ -29,12 +29,7 @@ where
         }
     }
 
-    pub fn create(
-        &self,
-        root: &Path,
-        config: &CoreConfig,
-        init_git: bool,
-    ) -> Result<Project, Box<dyn std::error::Error>> {
+    pub fn create(&self, root: &Path, config: &CoreConfig, init_git: bool) -> Result<Project, ProjectError> {
         let project = self.repository.create(root, config)?;
 
         self.project_infra_repository.pull(&project)?;
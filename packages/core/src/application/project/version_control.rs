@@ -1,4 +1,4 @@
-use crate::domain::TProjectVersionControl;
+use crate::domain::{ProjectError, TProjectVersionControl};
 
 pub struct VersionControlProjectService<T>
 where
@@ -15,16 +15,11 @@ where
         Self { version_control }
     }
 
-    pub fn commit(
-        &self,
-        project: &crate::domain::Project,
-        message: &str,
-        initial: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn commit(&self, project: &crate::domain::Project, message: &str, initial: bool) -> Result<(), ProjectError> {
         self.version_control.commit(&project.root, message, initial)
     }
 
-    pub fn tag(&self, project: &crate::domain::Project, tag: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn tag(&self, project: &crate::domain::Project, tag: &str) -> Result<(), ProjectError> {
         self.version_control.tag(&project.root, tag)
     }
 }
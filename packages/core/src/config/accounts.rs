@@ -1,6 +1,8 @@
+use crate::config::{ConfigError, ConfigValidator};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct AccountsConfig {
     #[serde(default = "defaults::admin_address")]
     pub admin_address: String,
@@ -88,4 +90,15 @@ impl AccountsConfig {
             challenger_private_key: Some("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".into()),
         }
     }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        ConfigValidator::new()
+            .address("admin_address", &self.admin_address)
+            .address("batcher_address", &self.batcher_address)
+            .address("sequencer_address", &self.sequencer_address)
+            .address("proposer_address", &self.proposer_address)
+            .address("deployer_address", &self.deployer_address)
+            .address("challenger_address", &self.challenger_address)
+            .finish("accounts")
+    }
 }
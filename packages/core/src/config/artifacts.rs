@@ -1,6 +1,8 @@
+use crate::config::{ConfigError, ConfigValidator};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ArtifactsConfig {
     pub node: ArtifactConfig,
     pub geth: ArtifactConfig,
@@ -10,6 +12,7 @@ pub struct ArtifactsConfig {
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ArtifactConfig {
     pub source_repo: String,
     pub source_tag: String,
@@ -43,4 +46,22 @@ impl ArtifactsConfig {
             },
         }
     }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut validator = ConfigValidator::new();
+
+        for (name, artifact) in [
+            ("node", &self.node),
+            ("geth", &self.geth),
+            ("contracts", &self.contracts),
+            ("batcher", &self.batcher),
+            ("proposer", &self.proposer),
+        ] {
+            validator
+                .not_empty(&format!("{}.source_repo", name), &artifact.source_repo)
+                .not_empty(&format!("{}.source_tag", name), &artifact.source_tag);
+        }
+
+        validator.finish("artifacts")
+    }
 }
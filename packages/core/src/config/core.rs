@@ -1,4 +1,4 @@
-use crate::config::{AccountsConfig, ArtifactsConfig, NetworkConfig};
+use crate::config::{AccountsConfig, ArtifactsConfig, ConfigError, NetworkConfig};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -9,9 +9,9 @@ pub struct CoreConfig {
 }
 
 impl CoreConfig {
-    pub fn new_from_toml<P: AsRef<std::path::Path>>(p: &P) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new_from_toml<P: AsRef<std::path::Path>>(p: &P) -> Result<Self, ConfigError> {
         if !p.as_ref().exists() {
-            return Err("Config file not found".into());
+            return Err(ConfigError::NotFound(p.as_ref().to_path_buf()));
         }
 
         let config_content = std::fs::read_to_string(p)?;
@@ -20,7 +20,7 @@ impl CoreConfig {
         Ok(config)
     }
 
-    pub fn to_toml<P: AsRef<std::path::Path>>(&self, p: &P) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn to_toml<P: AsRef<std::path::Path>>(&self, p: &P) -> Result<(), ConfigError> {
         let config_content = toml::to_string(&self)?;
         std::fs::write(p, config_content)?;
 
@@ -34,4 +34,12 @@ impl CoreConfig {
             network: NetworkConfig::null(),
         }
     }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.artifacts.validate()?;
+        self.accounts.validate()?;
+        self.network.validate()?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum ConfigError {
+    #[error("Config file {} not found", .0.display())]
+    NotFound(PathBuf),
+
+    /// Every problem found by the validator, see `ConfigValidator::finish`
+    #[error("Invalid {name} config: {}", .errors.join("; "))]
+    Invalid { name: String, errors: Vec<String> },
+
+    #[error("Could not parse config. {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Could not serialize config. {0}")]
+    Serialize(#[from] toml::ser::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
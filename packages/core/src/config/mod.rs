@@ -1,9 +1,13 @@
 pub mod accounts;
 pub mod artifacts;
 pub mod core;
+pub mod error;
 pub mod network;
+pub mod validation;
 
 pub use accounts::AccountsConfig;
 pub use artifacts::ArtifactsConfig;
 pub use core::CoreConfig;
+pub use error::ConfigError;
 pub use network::NetworkConfig;
+pub use validation::ConfigValidator;
@@ -1,6 +1,8 @@
+use crate::config::{ConfigError, ConfigValidator};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct NetworkConfig {
     #[serde(default = "defaults::l1_rpc_url", skip_serializing)]
     pub l1_rpc_url: Option<String>,
@@ -108,4 +110,106 @@ impl NetworkConfig {
             batch_inbox_address: "0xff69000000000000000000000000001201101712".into(),
         }
     }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut validator = ConfigValidator::new();
+
+        validator
+            .positive("l1_chain_id", self.l1_chain_id)
+            .positive("l2_chain_id", self.l2_chain_id)
+            .check(
+                self.l1_chain_id != self.l2_chain_id,
+                "l1_chain_id and l2_chain_id must differ",
+            )
+            .positive("l1_block_time", self.l1_block_time)
+            .positive("l2_block_time", self.l2_block_time)
+            .check(
+                self.l2_block_time <= self.l1_block_time,
+                "l2_block_time must not exceed l1_block_time",
+            )
+            .positive("max_sequencer_drift", self.max_sequencer_drift)
+            .positive("sequencer_window_size", self.sequencer_window_size)
+            .positive("channel_timeout", self.channel_timeout)
+            .positive(
+                "l2_output_oracle_submission_interval",
+                self.l2_output_oracle_submission_interval,
+            )
+            .positive("eip1559_denominator", self.eip1559_denominator)
+            .positive(
+                "eip1559_denominator_canyon",
+                self.eip1559_denominator_canyon,
+            )
+            .positive("eip1559_elasticity", self.eip1559_elasticity)
+            .check(
+                self.fault_game_split_depth < self.fault_game_max_depth,
+                "fault_game_split_depth must be lower than fault_game_max_depth",
+            )
+            .hex_quantity(
+                "base_fee_vault_minimum_withdrawal_amount",
+                &self.base_fee_vault_minimum_withdrawal_amount,
+            )
+            .hex_quantity(
+                "l1_fee_vault_minimum_withdrawal_amount",
+                &self.l1_fee_vault_minimum_withdrawal_amount,
+            )
+            .hex_quantity(
+                "sequencer_fee_vault_minimum_withdrawal_amount",
+                &self.sequencer_fee_vault_minimum_withdrawal_amount,
+            )
+            .hex_quantity(
+                "l2_genesis_block_gas_limit",
+                &self.l2_genesis_block_gas_limit,
+            )
+            .hex_quantity(
+                "l2_genesis_block_base_fee_per_gas",
+                &self.l2_genesis_block_base_fee_per_gas,
+            )
+            .hex_quantity(
+                "l2_genesis_regolith_time_offset",
+                &self.l2_genesis_regolith_time_offset,
+            )
+            .hex_quantity(
+                "l2_genesis_canyon_time_offset",
+                &self.l2_genesis_canyon_time_offset,
+            )
+            .bytes32("required_protocol_version", &self.required_protocol_version)
+            .bytes32(
+                "recommended_protocol_version",
+                &self.recommended_protocol_version,
+            )
+            .bytes32(
+                "fault_game_absolute_prestate",
+                &self.fault_game_absolute_prestate,
+            )
+            .bytes32(
+                "fault_game_genesis_output_root",
+                &self.fault_game_genesis_output_root,
+            )
+            .address("batch_inbox_address", &self.batch_inbox_address);
+
+        for (field, network) in [
+            (
+                "base_fee_vault_withdrawal_network",
+                self.base_fee_vault_withdrawal_network,
+            ),
+            (
+                "l1_fee_vault_withdrawal_network",
+                self.l1_fee_vault_withdrawal_network,
+            ),
+            (
+                "sequencer_fee_vault_withdrawal_network",
+                self.sequencer_fee_vault_withdrawal_network,
+            ),
+        ] {
+            validator.check(network <= 1, &format!("{} must be 0 (L1) or 1 (L2)", field));
+        }
+
+        if self.enable_governance {
+            validator
+                .not_empty("governance_token_symbol", &self.governance_token_symbol)
+                .not_empty("governance_token_name", &self.governance_token_name);
+        }
+
+        validator.finish("network")
+    }
 }
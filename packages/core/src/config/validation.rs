@@ -0,0 +1,127 @@
+use super::ConfigError;
+
+/// Collects every problem found in a config so they can be reported at once
+#[derive(Debug, Default)]
+pub struct ConfigValidator {
+    errors: Vec<String>,
+}
+
+impl ConfigValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check(&mut self, condition: bool, message: &str) -> &mut Self {
+        if !condition {
+            self.errors.push(message.to_string());
+        }
+        self
+    }
+
+    pub fn address(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(
+            is_hex(value, Some(40)),
+            &format!("{} must be a 0x prefixed address", field),
+        )
+    }
+
+    pub fn hex_quantity(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(
+            is_hex(value, None),
+            &format!("{} must be a 0x prefixed hex quantity", field),
+        )
+    }
+
+    pub fn bytes32(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(
+            is_hex(value, Some(64)),
+            &format!("{} must be 0x prefixed 32 bytes", field),
+        )
+    }
+
+    pub fn not_empty(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(
+            !value.trim().is_empty(),
+            &format!("{} must not be empty", field),
+        )
+    }
+
+    pub fn positive(&mut self, field: &str, value: u32) -> &mut Self {
+        self.check(value > 0, &format!("{} must be greater than 0", field))
+    }
+
+    /// Fails with every error found, prefixed by the config name
+    pub fn finish(&self, name: &str) -> Result<(), ConfigError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConfigError::Invalid {
+            name: name.to_string(),
+            errors: self.errors.clone(),
+        })
+    }
+}
+
+fn is_hex(value: &str, len: Option<usize>) -> bool {
+    let Some(digits) = value.strip_prefix("0x") else {
+        return false;
+    };
+
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()) && len.is_none_or(|len| digits.len() == len)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::{AccountsConfig, ArtifactsConfig, ConfigError, CoreConfig, NetworkConfig};
+
+    #[test]
+    fn null_configs_are_valid() {
+        assert!(CoreConfig::null().validate().is_ok());
+    }
+
+    #[test]
+    fn reports_every_network_error() {
+        let mut network = NetworkConfig::null();
+        network.l2_chain_id = network.l1_chain_id;
+        network.l2_block_time = 0;
+        network.batch_inbox_address = "0x123".into();
+
+        let err = network.validate().unwrap_err().to_string();
+
+        assert!(err.starts_with("Invalid network config"));
+        assert!(err.contains("l1_chain_id and l2_chain_id must differ"));
+        assert!(err.contains("l2_block_time must be greater than 0"));
+        assert!(err.contains("batch_inbox_address must be a 0x prefixed address"));
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        let mut accounts = AccountsConfig::null();
+        accounts.admin_address = "f39Fd6e51aad88F6F4ce6aB8827279cffFb92266".into();
+
+        assert!(accounts.validate().is_err());
+    }
+
+    #[test]
+    fn exposes_errors_for_callers() {
+        let mut accounts = AccountsConfig::null();
+        accounts.admin_address = "".into();
+        accounts.batcher_address = "0x123".into();
+
+        let Err(ConfigError::Invalid { name, errors }) = accounts.validate() else {
+            panic!("expected an invalid config error");
+        };
+
+        assert_eq!(name, "accounts");
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn rejects_empty_artifact_sources() {
+        let mut artifacts = ArtifactsConfig::null();
+        artifacts.node.source_tag = "".into();
+
+        assert!(artifacts.validate().is_err());
+    }
+}
@@ -1,10 +1,15 @@
-use crate::config::{artifacts::ArtifactConfig, CoreConfig};
+use crate::{
+    config::{artifacts::ArtifactConfig, CoreConfig},
+    git::GitError,
+    system::{exit_status, CommandError},
+};
 use mockall::automock;
 use std::{
     fmt,
     path::{Path, PathBuf},
     sync::Arc,
 };
+use thiserror::Error as ThisError;
 
 use super::Project;
 
@@ -61,9 +66,22 @@ impl fmt::Display for Artifact {
 
 pub struct ArtifactFactory {}
 
+#[derive(Debug, ThisError)]
+pub enum ArtifactError {
+    #[error("Could not pull artifact source. {0}")]
+    Source(#[from] GitError),
+
+    #[error("{tool} failed with {}. {stderr}", exit_status(.exit_code))]
+    ExternalToolFailed {
+        tool: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+}
+
 #[automock]
 pub trait TArtifactSourceRepository: Send + Sync {
-    fn pull(&self, artifact: &Artifact) -> Result<(), Box<dyn std::error::Error>>;
+    fn pull(&self, artifact: &Artifact) -> Result<(), ArtifactError>;
     fn exists(&self, artifact: &Artifact) -> bool;
 }
 
@@ -75,7 +93,17 @@ pub trait TArtifactFactory {
 #[automock]
 pub trait TArtifactRepository: Send + Sync {
     fn exists(&self, artifact: &Artifact) -> bool;
-    fn create(&self, artifact: &Artifact) -> Result<(), Box<dyn std::error::Error>>;
+    fn create(&self, artifact: &Artifact) -> Result<(), ArtifactError>;
+}
+
+impl From<CommandError> for ArtifactError {
+    fn from(err: CommandError) -> Self {
+        Self::ExternalToolFailed {
+            tool: err.tool().to_string(),
+            exit_code: err.exit_code(),
+            stderr: err.stderr(),
+        }
+    }
 }
 
 impl ArtifactData {
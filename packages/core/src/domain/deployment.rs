@@ -1,10 +1,15 @@
-use super::Project;
-use crate::config::{AccountsConfig, NetworkConfig};
+use super::{Project, ProjectError, ReleaseError};
+use crate::{
+    config::{AccountsConfig, ConfigError, ConfigValidator, NetworkConfig},
+    system::{exit_status, CommandError},
+};
 use serde::{Deserialize, Serialize};
-use std::{error::Error, path::PathBuf};
+use std::path::{Path, PathBuf};
+use thiserror::Error as ThisError;
 use url::Url;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Deployment {
     pub id: String,
     pub name: String, // holensky, sepolia, mumbai, etc
@@ -12,11 +17,24 @@ pub struct Deployment {
     pub release_tag: String,
     pub release_registry: String,
     pub infra_base_url: Option<String>,
+    /// Set by deployers that provision the cluster
+    #[serde(default)]
+    pub infra_outputs: Option<InfraOutputs>,
     pub contracts_addresses: Option<String>,
     pub network_config: NetworkConfig,
     pub accounts_config: AccountsConfig,
 }
 
+/// What was provisioned for the deployment, as reported by terraform
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InfraOutputs {
+    pub cluster_name: Option<String>,
+    pub region: Option<String>,
+    /// Load balancer in front of the ingress, domain records point to it. An ip if it has no hostname
+    pub load_balancer_hostname: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeploymentOptions {
     pub host: String,
@@ -28,6 +46,36 @@ pub struct DeploymentOptions {
     pub storage_class_name: String,
     pub sequencer_url: Option<String>,
     pub values_path: Option<PathBuf>,
+    #[serde(default)]
+    pub tls: DeploymentTls,
+}
+
+/// Where the certificates of the rpc, explorer and monitoring ingresses come from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum DeploymentTls {
+    /// Plain http, for local clusters
+    Disabled,
+    /// Let's Encrypt certificates, issued by cert-manager through a ClusterIssuer of the release
+    Acme {
+        /// Contact for expiry notices
+        email: Option<String>,
+        /// Untrusted certificates with much higher rate limits, to test the setup
+        #[serde(default)]
+        staging: bool,
+    },
+    /// Existing kubernetes.io/tls secret in the release namespace, covering every hostname
+    Secret { name: String },
+}
+
+/// Infra changes saved for review before applying them
+#[derive(Debug, Clone)]
+pub struct InfraPlan {
+    pub plan_file: PathBuf,
+    pub summary_file: PathBuf,
+    /// What would change, as shown by the provider
+    pub summary: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +97,12 @@ pub enum MonitorKind {
     Dispute,
 }
 
+pub const LETSENCRYPT_PRODUCTION: &str = "https://acme-v02.api.letsencrypt.org/directory";
+pub const LETSENCRYPT_STAGING: &str = "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+/// Deployment ids end up in paths, urls and release names
+pub const MAX_DEPLOYMENT_ID_LEN: usize = 64;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DeploymentKind {
     Sequencer,
@@ -57,20 +111,91 @@ pub enum DeploymentKind {
 
 pub type DeploymentArtifact = Vec<u8>;
 
+/// TLS settings as the charts and their subcharts take them
+struct TlsValues {
+    protocol: &'static str,
+    websocket_protocol: &'static str,
+    enabled: bool,
+    secret_name: String,
+    acme_enabled: bool,
+    cluster_issuer: String,
+    acme_server: &'static str,
+    acme_email: String,
+    /// Annotation line for ingresses, a comment if cert-manager doesn't issue the certificates
+    issuer_annotation: String,
+    /// Grafana ingress tls list
+    monitoring: String,
+    explorer_secret_name: String,
+}
+
+#[derive(Debug, ThisError)]
+pub enum DeploymentError {
+    #[error("Deployment {0} not found")]
+    NotFound(String),
+
+    /// The deployment changed or already exists where it's being saved
+    #[error("Deployment conflict. {0}")]
+    Conflict(String),
+
+    #[error(transparent)]
+    Invalid(#[from] ConfigError),
+
+    /// A value that's optional in the deployment is needed for this operation
+    #[error("{0} is required")]
+    Missing(&'static str),
+
+    #[error("Contracts are not deployed yet, deploy them first")]
+    ContractsNotDeployed,
+
+    #[error("Inputs changed since {0} was planned, plan again")]
+    PlanOutdated(PathBuf),
+
+    /// Terraform state from before deployments had their own workspace, it can't be told whose it is
+    #[error("The default terraform workspace in {} still holds the state of an older deployment. Move it to the workspace of that deployment with `terraform state pull > default.tfstate` and `terraform workspace new -state=default.tfstate <deployment-id>`, then empty `default` before deploying {deployment_id}", .dir.display())]
+    DefaultWorkspaceInUse { dir: PathBuf, deployment_id: String },
+
+    #[error("{tool} failed with {}. {stderr}", exit_status(.exit_code))]
+    ExternalToolFailed {
+        tool: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+
+    #[error(transparent)]
+    Release(#[from] ReleaseError),
+
+    #[error(transparent)]
+    Project(#[from] ProjectError),
+
+    /// The store behind a repository failed, e.g. a database or a remote api
+    #[error("Deployment storage failed. {0}")]
+    Storage(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// The cluster the deployment runs in couldn't be reached or the release didn't become ready
+    #[error(transparent)]
+    Cluster(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
 #[async_trait::async_trait]
 pub trait TDeploymentRepository: Send + Sync {
-    async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, Box<dyn std::error::Error>>;
-    async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, Box<dyn std::error::Error>>;
-    async fn save(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>>;
-    async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, DeploymentError>;
+    async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, DeploymentError>;
+    async fn save(&self, deployment: &Deployment) -> Result<(), DeploymentError>;
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError>;
 }
 
 #[async_trait::async_trait]
 pub trait TDeploymentArtifactsRepository: Send + Sync {
-    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, Box<dyn Error>>;
-    async fn exists(&self, deployment: &Deployment) -> Result<bool, Box<dyn Error>>;
-    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), Box<dyn Error>>;
-    async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn Error>>;
+    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError>;
+    async fn exists(&self, deployment: &Deployment) -> Result<bool, DeploymentError>;
+    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), DeploymentError>;
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError>;
 }
 
 #[async_trait::async_trait]
@@ -80,7 +205,7 @@ pub trait TInfraDeployerProvider: Send + Sync {
         project: &Project,
         deployment: &mut Deployment,
         opts: &DeploymentOptions,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), DeploymentError>;
 }
 
 pub trait TContractsDeployerProvider: Send + Sync {
@@ -90,7 +215,7 @@ pub trait TContractsDeployerProvider: Send + Sync {
         deployment: &mut Deployment,
         deploy_deterministic_deployer: bool,
         slow: bool,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error>>; // DeploymentArtifact
+    ) -> Result<Vec<u8>, DeploymentError>; // DeploymentArtifact
 }
 
 #[async_trait::async_trait]
@@ -100,8 +225,42 @@ pub trait TDeploymentRunner {
         project: &Project,
         deployment: &Deployment,
         opts: &DeploymentOptions,
-    ) -> Result<(), Box<dyn std::error::Error>>;
-    fn stop(&self, release_tag: &str, release_namespace: &str) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), DeploymentError>;
+    /// Runs to the end even if cancelled, it's meant to clean up
+    async fn stop(&self, release_tag: &str, release_namespace: &str) -> Result<(), DeploymentError>;
+}
+
+#[async_trait::async_trait]
+pub trait TInfraPlanner: Send + Sync {
+    /// Saves the changes `deploy` would make to `plan_file`, along with a readable summary, without making them
+    async fn plan(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        plan_file: &Path,
+    ) -> Result<InfraPlan, DeploymentError>;
+
+    /// Applies exactly the saved plan, refuses to if the deployment, options or infra changed since planning
+    async fn apply_plan(
+        &self,
+        project: &Project,
+        deployment: &mut Deployment,
+        opts: &DeploymentOptions,
+        plan_file: &Path,
+    ) -> Result<(), DeploymentError>;
+}
+
+#[async_trait::async_trait]
+pub trait TDeploymentRenderer {
+    /// Writes the manifests `run` would apply to `output_dir` without touching the cluster, returns the written files
+    async fn render(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>, DeploymentError>;
 }
 
 #[async_trait::async_trait]
@@ -111,12 +270,153 @@ pub trait TDeploymentMonitorRunner {
         project: &Project,
         deployment: &Deployment,
         opts: &DeploymentMonitorOptions,
-    ) -> Result<(), Box<dyn std::error::Error>>;
-    fn stop(&self) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), DeploymentError>;
+    fn stop(&self) -> Result<(), DeploymentError>;
 }
 
 // implementations ========================================================
 
+impl DeploymentError {
+    pub fn storage<E>(err: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::Storage(err.into())
+    }
+}
+
+/// Lets callers pick the provider at runtime
+#[async_trait::async_trait]
+impl TInfraDeployerProvider for Box<dyn TInfraDeployerProvider> {
+    async fn deploy(
+        &self,
+        project: &Project,
+        deployment: &mut Deployment,
+        opts: &DeploymentOptions,
+    ) -> Result<(), DeploymentError> {
+        self.as_ref().deploy(project, deployment, opts).await
+    }
+}
+
+impl From<CommandError> for DeploymentError {
+    fn from(err: CommandError) -> Self {
+        Self::ExternalToolFailed {
+            tool: err.tool().to_string(),
+            exit_code: err.exit_code(),
+            stderr: err.stderr(),
+        }
+    }
+}
+
+impl DeploymentOptions {
+    /// ClusterIssuer created with the release, cluster scoped so it's named after the release and its namespace
+    pub fn cluster_issuer(&self) -> Option<String> {
+        self.tls.acme_server().map(|_| {
+            format!(
+                "{}-{}-letsencrypt",
+                self.release_namespace, self.release_tag
+            )
+        })
+    }
+
+    /// Secret holding the certificate of `hostname`, the same for all of them when it's brought in
+    pub fn tls_secret_name(&self, hostname: &str) -> String {
+        match &self.tls {
+            DeploymentTls::Secret { name } => name.clone(),
+            _ => format!("{}-tls", hostname.replace('.', "-")),
+        }
+    }
+
+    /// `rpc`, `explorer` or `monitoring` host, replicas are prefixed so they can share the domain with the sequencer
+    pub fn hostname(&self, subdomain: &str) -> String {
+        match self.kind {
+            DeploymentKind::Replica => format!("replica-{}.{}", subdomain, self.host),
+            DeploymentKind::Sequencer => format!("{}.{}", subdomain, self.host),
+        }
+    }
+
+    /// Hostnames the ingresses of the enabled components answer to
+    pub fn hostnames(&self) -> Vec<String> {
+        let subdomains = [
+            ("rpc", true),
+            ("explorer", self.explorer),
+            ("monitoring", self.monitoring),
+        ];
+
+        subdomains
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(subdomain, _)| self.hostname(subdomain))
+            .collect()
+    }
+}
+
+impl Default for DeploymentTls {
+    fn default() -> Self {
+        DeploymentTls::Acme {
+            email: None,
+            staging: false,
+        }
+    }
+}
+
+impl DeploymentTls {
+    pub fn protocol(&self) -> &'static str {
+        match self {
+            DeploymentTls::Disabled => "http",
+            _ => "https",
+        }
+    }
+
+    pub fn acme_server(&self) -> Option<&'static str> {
+        match self {
+            DeploymentTls::Acme { staging: true, .. } => Some(LETSENCRYPT_STAGING),
+            DeploymentTls::Acme { staging: false, .. } => Some(LETSENCRYPT_PRODUCTION),
+            _ => None,
+        }
+    }
+}
+
+impl From<&DeploymentOptions> for TlsValues {
+    fn from(opts: &DeploymentOptions) -> Self {
+        let enabled = opts.tls != DeploymentTls::Disabled;
+        let cluster_issuer = opts.cluster_issuer().unwrap_or_default();
+        let monitoring_host = opts.hostname("monitoring");
+
+        Self {
+            protocol: opts.tls.protocol(),
+            websocket_protocol: if enabled { "wss" } else { "ws" },
+            enabled,
+            secret_name: match &opts.tls {
+                DeploymentTls::Secret { name } => name.clone(),
+                _ => String::new(),
+            },
+            acme_enabled: opts.tls.acme_server().is_some(),
+            acme_server: opts.tls.acme_server().unwrap_or_default(),
+            acme_email: match &opts.tls {
+                DeploymentTls::Acme { email, .. } => email.clone().unwrap_or_default(),
+                _ => String::new(),
+            },
+            issuer_annotation: if cluster_issuer.is_empty() {
+                "# certificates aren't issued by cert-manager".to_string()
+            } else {
+                format!("cert-manager.io/cluster-issuer: \"{}\"", cluster_issuer)
+            },
+            cluster_issuer,
+            monitoring: if enabled {
+                format!(
+                    "[{{secretName: {}, hosts: [{}]}}]",
+                    opts.tls_secret_name(&monitoring_host),
+                    monitoring_host
+                )
+            } else {
+                "[]".to_string()
+            },
+            explorer_secret_name: opts.tls_secret_name(&opts.hostname("explorer")),
+        }
+    }
+}
+
 impl Deployment {
     pub fn new<T>(
         id: T,
@@ -126,7 +426,7 @@ impl Deployment {
         release_registry: T,
         network_config: NetworkConfig,
         accounts_config: AccountsConfig,
-    ) -> Result<Self, Box<dyn std::error::Error>>
+    ) -> Result<Self, DeploymentError>
     where
         T: Into<String>,
     {
@@ -140,10 +440,54 @@ impl Deployment {
             accounts_config,
             contracts_addresses: None,
             infra_base_url: None,
+            infra_outputs: None,
         })
     }
 
-    pub fn build_deploy_config(&self) -> Result<String, Box<dyn std::error::Error>> {
+    /// Ids are letters, digits, `-` and `_`, starting with a letter or digit
+    pub fn validate_id(id: &str) -> Result<(), DeploymentError> {
+        let mut validator = ConfigValidator::new();
+        validator
+            .check(
+                (1..=MAX_DEPLOYMENT_ID_LEN).contains(&id.len()),
+                &format!("id must be 1 to {} characters long", MAX_DEPLOYMENT_ID_LEN),
+            )
+            .check(
+                id.starts_with(|c: char| c.is_ascii_alphanumeric())
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                "id must only contain letters, digits, - and _, starting with a letter or digit",
+            )
+            .finish("deployment")?;
+
+        Ok(())
+    }
+
+    /// Validates everything needed to deploy, reporting every problem found
+    pub fn validate(&self) -> Result<(), DeploymentError> {
+        let mut validator = ConfigValidator::new();
+        validator
+            .not_empty("name", &self.name)
+            .not_empty("release_tag", &self.release_tag)
+            .not_empty("release_registry", &self.release_registry)
+            .finish("deployment")?;
+
+        self.network_config.validate()?;
+        self.accounts_config.validate()?;
+
+        Ok(())
+    }
+
+    /// Deployments stored without secrets have no l1 rpc url until one is provided
+    pub fn l1_rpc_url(&self) -> Result<&str, DeploymentError> {
+        self.network_config
+            .l1_rpc_url
+            .as_deref()
+            .ok_or(DeploymentError::Missing("l1_rpc_url"))
+    }
+
+    pub fn build_deploy_config(&self) -> Result<String, DeploymentError> {
         let json = format!(
             r#"{{
                 "l1ChainID": {l1_chain_id},
@@ -269,7 +613,20 @@ impl Deployment {
         Ok(json)
     }
 
-    pub fn build_sequencer_values_yaml(&self, opts: &DeploymentOptions) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn build_sequencer_values_yaml(&self, opts: &DeploymentOptions) -> Result<String, DeploymentError> {
+        let l1_rpc_url = self.l1_rpc_url()?;
+        let tls = TlsValues::from(opts);
+        let batcher_private_key = self
+            .accounts_config
+            .batcher_private_key
+            .as_ref()
+            .ok_or(DeploymentError::Missing("batcher_private_key"))?;
+        let proposer_private_key = self
+            .accounts_config
+            .proposer_private_key
+            .as_ref()
+            .ok_or(DeploymentError::Missing("proposer_private_key"))?;
+
         let yaml = format!(
             r#"
 # NOTE: 
@@ -280,9 +637,17 @@ impl Deployment {
 
 global:
   host: {host}
-  protocol: http
-  email: email@email.com
+  protocol: {protocol}
   storageClassName: "{storage_class_name}"
+  tls:
+    enabled: {tls_enabled}
+    # brought in, covers every hostname. Otherwise there's a secret per hostname
+    secretName: "{tls_secret_name}"
+    acme:
+      enabled: {acme_enabled}
+      issuer: "{cluster_issuer}"
+      server: "{acme_server}"
+      email: "{acme_email}"
   image:
     pullPolicy: IfNotPresent
 
@@ -391,10 +756,12 @@ grafana:
     ingressClassName: "nginx"
     annotations:
       kubernetes.io/ingress.class: "nginx"
-      nginx.ingress.kubernetes.io/force-ssl-redirect: "true"
+      nginx.ingress.kubernetes.io/force-ssl-redirect: "{tls_enabled}"
       nginx.ingress.kubernetes.io/rewrite-target: /$2
+      {issuer_annotation}
     hosts:
       - monitoring.{host}
+    tls: {monitoring_tls}
 
     grafana.ini:
       server:
@@ -446,6 +813,10 @@ blockscout-stack:
       enabled: true
       className: "nginx"
       hostname: explorer.{host}
+      # the frontend ingress requests the certificate for the shared hostname
+      tls:
+        enabled: {tls_enabled}
+        secretName: {explorer_tls_secret_name}
 
     env:
       CHAIN_ID: "{l2_chain_id}"
@@ -467,25 +838,53 @@ blockscout-stack:
       enabled: true
       className: "nginx"
       hostname: explorer.{host}
+      annotations:
+        {issuer_annotation}
+      tls:
+        enabled: {tls_enabled}
+        secretName: {explorer_tls_secret_name}
     env:
-      NEXT_PUBLIC_API_PROTOCOL: http
+      NEXT_PUBLIC_API_PROTOCOL: {protocol}
+      NEXT_PUBLIC_API_WEBSOCKET_PROTOCOL: {websocket_protocol}
             "#,
-            l1_rpc_url = self.network_config.l1_rpc_url.as_ref().unwrap(),
+            l1_rpc_url = l1_rpc_url,
             l2_chain_id = self.network_config.l2_chain_id,
-            batcher_private_key = self.accounts_config.batcher_private_key.as_ref().unwrap(),
-            proposer_private_key = self.accounts_config.proposer_private_key.as_ref().unwrap(),
+            batcher_private_key = batcher_private_key,
+            proposer_private_key = proposer_private_key,
             storage_class_name = opts.storage_class_name,
             monitoring_enabled = opts.monitoring,
             explorer_enabled = opts.explorer,
             host = opts.host,
             release_registry = self.release_registry,
             release_tag = self.release_tag,
+            protocol = tls.protocol,
+            websocket_protocol = tls.websocket_protocol,
+            tls_enabled = tls.enabled,
+            tls_secret_name = tls.secret_name,
+            acme_enabled = tls.acme_enabled,
+            cluster_issuer = tls.cluster_issuer,
+            acme_server = tls.acme_server,
+            acme_email = tls.acme_email,
+            issuer_annotation = tls.issuer_annotation,
+            monitoring_tls = tls.monitoring,
+            explorer_tls_secret_name = tls.explorer_secret_name,
         );
 
         Ok(yaml)
     }
 
-    pub fn build_replica_values_yaml(&self, opts: &DeploymentOptions) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn build_replica_values_yaml(&self, opts: &DeploymentOptions) -> Result<String, DeploymentError> {
+        let l1_rpc_url = self.l1_rpc_url()?;
+        let tls = TlsValues::from(opts);
+        let sequencer_url = opts
+            .sequencer_url
+            .as_ref()
+            .ok_or(DeploymentError::Missing("sequencer_url"))?;
+        let sequencer_host = Url::parse(sequencer_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .ok_or(DeploymentError::Missing("a sequencer_url with a host"))?;
+
         let yaml = format!(
             r#"
 # NOTE: 
@@ -496,9 +895,17 @@ blockscout-stack:
 
 global:
   host: {host}
-  protocol: http
-  email: email@email.com
+  protocol: {protocol}
   storageClassName: "{storage_class_name}"
+  tls:
+    enabled: {tls_enabled}
+    # brought in, covers every hostname. Otherwise there's a secret per hostname
+    secretName: "{tls_secret_name}"
+    acme:
+      enabled: {acme_enabled}
+      issuer: "{cluster_issuer}"
+      server: "{acme_server}"
+      email: "{acme_email}"
   image:
     pullPolicy: IfNotPresent
 
@@ -606,10 +1013,12 @@ grafana:
     ingressClassName: "nginx"
     annotations:
       kubernetes.io/ingress.class: "nginx"
-      nginx.ingress.kubernetes.io/force-ssl-redirect: "true"
+      nginx.ingress.kubernetes.io/force-ssl-redirect: "{tls_enabled}"
       nginx.ingress.kubernetes.io/rewrite-target: /$2
+      {issuer_annotation}
     hosts:
       - replica-monitoring.{host}
+    tls: {monitoring_tls}
       
   grafana.ini:
     server:
@@ -661,6 +1070,10 @@ blockscout-stack:
       enabled: true
       className: "nginx"
       hostname: replica-explorer.{host}
+      # the frontend ingress requests the certificate for the shared hostname
+      tls:
+        enabled: {tls_enabled}
+        secretName: {explorer_tls_secret_name}
 
     env:
       CHAIN_ID: "{l2_chain_id}"
@@ -681,24 +1094,173 @@ blockscout-stack:
       enabled: true
       className: "nginx"
       hostname: replica-explorer.{host}
+      annotations:
+        {issuer_annotation}
+      tls:
+        enabled: {tls_enabled}
+        secretName: {explorer_tls_secret_name}
     env:
-      NEXT_PUBLIC_API_PROTOCOL: http
+      NEXT_PUBLIC_API_PROTOCOL: {protocol}
+      NEXT_PUBLIC_API_WEBSOCKET_PROTOCOL: {websocket_protocol}
             "#,
             host = opts.host,
-            l1_rpc_url = self.network_config.l1_rpc_url.as_ref().unwrap(),
+            l1_rpc_url = l1_rpc_url,
             l2_chain_id = self.network_config.l2_chain_id,
             storage_class_name = opts.storage_class_name,
             monitoring_enabled = opts.monitoring,
             explorer_enabled = opts.explorer,
             release_registry = self.release_registry,
             release_tag = self.release_tag,
-            sequencer_url = opts.sequencer_url.as_ref().unwrap(),
-            sequencer_host = Url::parse(opts.sequencer_url.as_ref().unwrap())
-                .unwrap()
-                .host_str()
-                .unwrap(),
+            sequencer_url = sequencer_url,
+            sequencer_host = sequencer_host,
+            protocol = tls.protocol,
+            websocket_protocol = tls.websocket_protocol,
+            tls_enabled = tls.enabled,
+            tls_secret_name = tls.secret_name,
+            acme_enabled = tls.acme_enabled,
+            cluster_issuer = tls.cluster_issuer,
+            acme_server = tls.acme_server,
+            acme_email = tls.acme_email,
+            issuer_annotation = tls.issuer_annotation,
+            monitoring_tls = tls.monitoring,
+            explorer_tls_secret_name = tls.explorer_secret_name,
         );
 
         Ok(yaml)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_yaml::Value;
+
+    #[test]
+    fn validate_id_accepts_cli_and_generated_ids() {
+        assert!(Deployment::validate_id("testnet").is_ok());
+        assert!(Deployment::validate_id("my_chain-2").is_ok());
+        assert!(Deployment::validate_id(&uuid::Uuid::new_v4().to_string()).is_ok());
+    }
+
+    #[test]
+    fn validate_id_rejects_paths_and_oversized_ids() {
+        for id in [
+            "",
+            "../other",
+            "a b",
+            "-leading",
+            "a/b",
+            &"a".repeat(MAX_DEPLOYMENT_ID_LEN + 1),
+        ] {
+            assert!(
+                matches!(
+                    Deployment::validate_id(id),
+                    Err(DeploymentError::Invalid(_))
+                ),
+                "{}",
+                id
+            );
+        }
+    }
+
+    fn replica_values(tls: DeploymentTls) -> Value {
+        let mut network_config = NetworkConfig::null();
+        network_config.l1_rpc_url = Some("http://localhost:8545".into());
+        let deployment = Deployment::new(
+            "testnet",
+            "Testnet",
+            "owner",
+            "v1.0.0",
+            "wakeuplabs",
+            network_config,
+            AccountsConfig::null(),
+        )
+        .unwrap();
+
+        let yaml = deployment
+            .build_replica_values_yaml(&DeploymentOptions {
+                host: "example.com".into(),
+                kind: DeploymentKind::Replica,
+                monitoring: true,
+                explorer: true,
+                release_tag: "opruaas".into(),
+                release_namespace: "replica".into(),
+                storage_class_name: "gp2".into(),
+                sequencer_url: Some("https://rpc.example.com".into()),
+                values_path: None,
+                tls,
+            })
+            .unwrap();
+
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn issues_certificates_for_every_ingress() {
+        let values = replica_values(DeploymentTls::Acme {
+            email: Some("ops@example.com".into()),
+            staging: true,
+        });
+
+        assert_eq!(values["global"]["protocol"], "https");
+        assert_eq!(
+            values["global"]["tls"]["acme"]["issuer"],
+            "replica-opruaas-letsencrypt"
+        );
+        assert_eq!(
+            values["global"]["tls"]["acme"]["server"],
+            LETSENCRYPT_STAGING
+        );
+        assert_eq!(values["global"]["tls"]["acme"]["email"], "ops@example.com");
+        assert_eq!(
+            values["grafana"]["ingress"]["annotations"]["cert-manager.io/cluster-issuer"],
+            "replica-opruaas-letsencrypt"
+        );
+        assert_eq!(
+            values["grafana"]["ingress"]["tls"][0]["hosts"][0],
+            "replica-monitoring.example.com"
+        );
+        assert_eq!(
+            values["blockscout-stack"]["frontend"]["ingress"]["tls"]["secretName"],
+            "replica-explorer-example-com-tls"
+        );
+        assert_eq!(
+            values["blockscout-stack"]["frontend"]["env"]["NEXT_PUBLIC_API_WEBSOCKET_PROTOCOL"],
+            "wss"
+        );
+    }
+
+    #[test]
+    fn brought_in_secret_skips_cert_manager() {
+        let values = replica_values(DeploymentTls::Secret {
+            name: "wildcard-example-com".into(),
+        });
+
+        assert_eq!(
+            values["global"]["tls"]["secretName"],
+            "wildcard-example-com"
+        );
+        assert_eq!(values["global"]["tls"]["acme"]["enabled"], false);
+        assert_eq!(
+            values["grafana"]["ingress"]["tls"][0]["secretName"],
+            "wildcard-example-com"
+        );
+        assert_eq!(
+            values["blockscout-stack"]["frontend"]["ingress"]["annotations"],
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn serves_plain_http_when_disabled() {
+        let values = replica_values(DeploymentTls::Disabled);
+
+        assert_eq!(values["global"]["protocol"], "http");
+        assert_eq!(values["global"]["tls"]["enabled"], false);
+        assert_eq!(
+            values["grafana"]["ingress"]["annotations"]["nginx.ingress.kubernetes.io/force-ssl-redirect"],
+            "false"
+        );
+        assert_eq!(values["grafana"]["ingress"]["tls"], Value::Sequence(vec![]));
+    }
+}
@@ -0,0 +1,225 @@
+use super::{Deployment, DeploymentOptions};
+use crate::system::{exit_status, CommandError};
+use serde::{Deserialize, Serialize};
+use std::{fmt, net::IpAddr};
+use thiserror::Error as ThisError;
+
+/// Short enough for a replaced load balancer to be picked up quickly
+pub const DEFAULT_DNS_TTL: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordKind {
+    A,
+    Aaaa,
+    Cname,
+}
+
+/// Record pointing one of the deployment hostnames to the ingress load balancer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    /// Fully qualified, without the trailing dot
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: DnsRecordKind,
+    pub value: String,
+    pub ttl: u32,
+}
+
+#[derive(Debug, ThisError)]
+pub enum DnsError {
+    #[error("{0} is not a valid domain name")]
+    InvalidHost(String),
+
+    #[error("{tool} failed with {}. {stderr}", exit_status(.exit_code))]
+    ExternalToolFailed {
+        tool: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[async_trait::async_trait]
+pub trait TDnsProvider: Send + Sync {
+    /// Creates the records or updates the existing ones in place
+    async fn upsert(&self, records: &[DnsRecord]) -> Result<(), DnsError>;
+}
+
+// implementations ========================================================
+
+impl From<CommandError> for DnsError {
+    fn from(err: CommandError) -> Self {
+        DnsError::ExternalToolFailed {
+            tool: err.tool().to_string(),
+            exit_code: err.exit_code(),
+            stderr: err.stderr(),
+        }
+    }
+}
+
+impl DnsRecordKind {
+    /// A or AAAA for ips, CNAME for load balancers only known by name
+    pub fn for_target(target: &str) -> Self {
+        match target.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => DnsRecordKind::A,
+            Ok(IpAddr::V6(_)) => DnsRecordKind::Aaaa,
+            Err(_) => DnsRecordKind::Cname,
+        }
+    }
+}
+
+impl fmt::Display for DnsRecordKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsRecordKind::A => write!(f, "A"),
+            DnsRecordKind::Aaaa => write!(f, "AAAA"),
+            DnsRecordKind::Cname => write!(f, "CNAME"),
+        }
+    }
+}
+
+impl DnsRecord {
+    /// Records for every hostname of the deployment, none until the load balancer address is known
+    pub fn for_deployment(deployment: &Deployment, opts: &DeploymentOptions, ttl: u32) -> Result<Vec<Self>, DnsError> {
+        let Some(target) = deployment
+            .infra_outputs
+            .as_ref()
+            .and_then(|outputs| outputs.load_balancer_hostname.as_ref())
+        else {
+            return Ok(Vec::new());
+        };
+
+        let host = opts.host.trim_end_matches('.');
+        if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == '/' || c == ':') {
+            return Err(DnsError::InvalidHost(opts.host.clone()));
+        }
+
+        Ok(opts
+            .hostnames()
+            .into_iter()
+            .map(|name| Self {
+                name: name.trim_end_matches('.').to_string(),
+                kind: DnsRecordKind::for_target(target),
+                value: target.clone(),
+                ttl,
+            })
+            .collect())
+    }
+
+    /// Records as a BIND zone file fragment, with absolute names
+    pub fn to_zone(records: &[Self]) -> String {
+        records
+            .iter()
+            .map(|record| format!("{}\n", record))
+            .collect()
+    }
+}
+
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self.kind {
+            DnsRecordKind::Cname => format!("{}.", self.value.trim_end_matches('.')),
+            _ => self.value.clone(),
+        };
+
+        write!(
+            f,
+            "{}.\t{}\tIN\t{}\t{}",
+            self.name, self.ttl, self.kind, value
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{AccountsConfig, NetworkConfig},
+        domain::{DeploymentKind, DeploymentTls, InfraOutputs},
+    };
+
+    fn deployment(load_balancer: Option<&str>) -> Deployment {
+        let mut deployment = Deployment::new(
+            "testnet",
+            "Testnet",
+            "owner",
+            "v1.0.0",
+            "wakeuplabs",
+            NetworkConfig::null(),
+            AccountsConfig::null(),
+        )
+        .unwrap();
+        deployment.infra_outputs = Some(InfraOutputs {
+            load_balancer_hostname: load_balancer.map(String::from),
+            ..Default::default()
+        });
+
+        deployment
+    }
+
+    fn options(kind: DeploymentKind, explorer: bool) -> DeploymentOptions {
+        DeploymentOptions {
+            host: "example.com".into(),
+            kind,
+            monitoring: false,
+            explorer,
+            release_tag: "opruaas".into(),
+            release_namespace: "opruaas".into(),
+            storage_class_name: "gp2".into(),
+            sequencer_url: None,
+            values_path: None,
+            tls: DeploymentTls::default(),
+        }
+    }
+
+    #[test]
+    fn points_every_hostname_to_the_load_balancer() {
+        let records = DnsRecord::for_deployment(
+            &deployment(Some("a1b2.us-east-2.elb.amazonaws.com")),
+            &options(DeploymentKind::Replica, true),
+            DEFAULT_DNS_TTL,
+        )
+        .unwrap();
+
+        assert_eq!(
+            DnsRecord::to_zone(&records),
+            "replica-rpc.example.com.\t300\tIN\tCNAME\ta1b2.us-east-2.elb.amazonaws.com.\n\
+            replica-explorer.example.com.\t300\tIN\tCNAME\ta1b2.us-east-2.elb.amazonaws.com.\n"
+        );
+    }
+
+    #[test]
+    fn uses_address_records_for_ips() {
+        let records = DnsRecord::for_deployment(
+            &deployment(Some("203.0.113.10")),
+            &options(DeploymentKind::Sequencer, false),
+            60,
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&records).unwrap(),
+            serde_json::json!([
+                { "name": "rpc.example.com", "type": "A", "value": "203.0.113.10", "ttl": 60 }
+            ])
+        );
+    }
+
+    #[test]
+    fn waits_for_the_load_balancer() {
+        let records = DnsRecord::for_deployment(
+            &deployment(None),
+            &options(DeploymentKind::Sequencer, true),
+            DEFAULT_DNS_TTL,
+        )
+        .unwrap();
+
+        assert!(records.is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 pub mod artifact;
 pub mod deployment;
+pub mod dns;
 pub mod project;
 pub mod release;
 
 pub use artifact::*;
 pub use deployment::*;
+pub use dns::*;
 pub use project::*;
 pub use release::*;
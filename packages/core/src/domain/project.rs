@@ -1,6 +1,10 @@
 use std::path::{Path, PathBuf};
 
-use crate::config::CoreConfig;
+use crate::{
+    config::{ConfigError, CoreConfig},
+    git::GitError,
+};
+use thiserror::Error as ThisError;
 
 #[derive(Debug, Clone)]
 pub struct Project {
@@ -47,30 +51,51 @@ pub struct Src {
     pub explorer: PathBuf,
 }
 
+#[derive(Debug, ThisError)]
+pub enum ProjectError {
+    #[error("Could not find config.toml in {} or its parents", .0.display())]
+    NotFound(PathBuf),
+
+    #[error("{} is not inside the project root", .0.display())]
+    OutsideRoot(PathBuf),
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error("Could not download project infra. {0}")]
+    Download(#[from] GitError),
+
+    #[error("Version control failed. {0}")]
+    VersionControl(#[from] git2::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
 pub trait TProjectRepository {
-    fn create(&self, root: &Path, config: &CoreConfig) -> Result<Project, Box<dyn std::error::Error>>;
-    fn write(&self, project: &Project, filepath: &Path, content: &str) -> Result<(), Box<dyn std::error::Error>>;
+    fn create(&self, root: &Path, config: &CoreConfig) -> Result<Project, ProjectError>;
+    fn write(&self, project: &Project, filepath: &Path, content: &str) -> Result<(), ProjectError>;
     fn exists(&self, project: &Project) -> bool;
     fn has(&self, project: &Project, filepath: &Path) -> bool;
 }
 
 pub trait TProjectInfraRepository {
-    fn pull(&self, project: &Project) -> Result<(), Box<dyn std::error::Error>>;
+    fn pull(&self, project: &Project) -> Result<(), ProjectError>;
 }
 
 pub trait TProjectVersionControl {
-    fn init(&self, root: &Path) -> Result<(), Box<dyn std::error::Error>>;
-    fn stage(&self, root: &Path) -> Result<(), Box<dyn std::error::Error>>;
-    fn commit(&self, root: &Path, message: &str, initial: bool) -> Result<(), Box<dyn std::error::Error>>;
-    fn tag(&self, root: &Path, tag: &str) -> Result<(), Box<dyn std::error::Error>>;
+    fn init(&self, root: &Path) -> Result<(), ProjectError>;
+    fn stage(&self, root: &Path) -> Result<(), ProjectError>;
+    fn commit(&self, root: &Path, message: &str, initial: bool) -> Result<(), ProjectError>;
+    fn tag(&self, root: &Path, tag: &str) -> Result<(), ProjectError>;
 }
 
 impl TryFrom<PathBuf> for Project {
-    type Error = &'static str;
+    type Error = ProjectError;
 
     /// walk back to find config.toml
     fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
-        let mut root = path;
+        let mut root = path.clone();
 
         for _ in 0..10 {
             if root.join("config.toml").exists() {
@@ -116,9 +141,12 @@ impl TryFrom<PathBuf> for Project {
                 });
             }
 
-            root = root.parent().unwrap().to_path_buf()
+            match root.parent() {
+                Some(parent) => root = parent.to_path_buf(),
+                None => break,
+            }
         }
 
-        Err("Could not find config.toml")
+        Err(ProjectError::NotFound(path))
     }
 }
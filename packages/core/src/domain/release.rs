@@ -1,8 +1,10 @@
 use std::{collections::HashMap, path::Path};
 
 use super::Artifact;
+use crate::system::{exit_status, CommandError};
 use mockall::automock;
 use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Release {
@@ -11,6 +13,19 @@ pub struct Release {
     pub registry_url: String,
 }
 
+#[derive(Debug, ThisError)]
+pub enum ReleaseError {
+    #[error("Artifact {0} not found, build it first")]
+    ArtifactNotFound(String),
+
+    #[error("{tool} failed with {}. {stderr}", exit_status(.exit_code))]
+    ExternalToolFailed {
+        tool: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+}
+
 #[automock]
 pub trait TReleaseRepository: Send + Sync {
     fn create_for_artifact(
@@ -18,13 +33,13 @@ pub trait TReleaseRepository: Send + Sync {
         artifact: &Artifact,
         release_name: &str,
         registry_url: &str,
-    ) -> Result<Release, Box<dyn std::error::Error>>;
-    fn pull(&self, release: &Release) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<Release, ReleaseError>;
+    fn pull(&self, release: &Release) -> Result<(), ReleaseError>;
 }
 
 pub trait TReleaseRunner: Send + Sync {
-    fn run(&self, release: &Release, opts: ReleaseRunnerOptions) -> Result<(), Box<dyn std::error::Error>>;
-    fn stop(&self, container_name: &str) -> Result<(), Box<dyn std::error::Error>>;
+    fn run(&self, release: &Release, opts: ReleaseRunnerOptions) -> Result<(), ReleaseError>;
+    fn stop(&self, container_name: &str) -> Result<(), ReleaseError>;
 }
 
 pub struct ReleaseRunnerOptions<'a> {
@@ -36,6 +51,16 @@ pub struct ReleaseRunnerOptions<'a> {
 
 // implementations =============================================
 
+impl From<CommandError> for ReleaseError {
+    fn from(err: CommandError) -> Self {
+        Self::ExternalToolFailed {
+            tool: err.tool().to_string(),
+            exit_code: err.exit_code(),
+            stderr: err.stderr(),
+        }
+    }
+}
+
 impl Release {
     pub fn new<T>(artifact_name: T, artifact_tag: T, registry_url: T) -> Self
     where
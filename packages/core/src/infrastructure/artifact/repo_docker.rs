@@ -19,7 +19,7 @@ impl DockerArtifactRepository {
 }
 
 impl domain::TArtifactRepository for DockerArtifactRepository {
-    fn create(&self, artifact: &domain::Artifact) -> Result<(), Box<dyn std::error::Error>> {
+    fn create(&self, artifact: &domain::Artifact) -> Result<(), domain::ArtifactError> {
         let use_buildx = match system::execute_command(Command::new("docker").arg("buildx").arg("version"), true) {
             Ok(_) => true,
             Err(_) => {
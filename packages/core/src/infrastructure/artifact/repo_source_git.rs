@@ -1,6 +1,6 @@
 use crate::{
     config::artifacts::{INFRA_SOURCE_REPO, INFRA_SOURCE_REPO_VERSION},
-    domain::{self, artifact::Artifact},
+    domain::{self, artifact::Artifact, ArtifactError},
     git,
 };
 
@@ -19,7 +19,7 @@ impl GitArtifactSourceRepository {
 }
 
 impl domain::artifact::TArtifactSourceRepository for GitArtifactSourceRepository {
-    fn pull(&self, artifact: &Artifact) -> Result<(), Box<dyn std::error::Error>> {
+    fn pull(&self, artifact: &Artifact) -> Result<(), ArtifactError> {
         let (source_repo, source_tag) = artifact.source_info();
 
         git::clone_tag(source_repo, source_tag, artifact.context())?;
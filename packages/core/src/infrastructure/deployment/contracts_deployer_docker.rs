@@ -1,12 +1,12 @@
-use crate::domain::{
-    self, Deployment, DeploymentArtifact, Project, Release, ReleaseRunnerOptions, TContractsDeployerProvider,
+use crate::{
+    domain::{
+        self, Deployment, DeploymentArtifact, DeploymentError, Project, Release, ReleaseRunnerOptions,
+        TContractsDeployerProvider,
+    },
+    system,
 };
 use rand::Rng;
-use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::Read,
-};
+use std::{collections::HashMap, fs::File, io::Read};
 use tempfile::TempDir;
 
 pub struct DockerContractsDeployer {
@@ -39,7 +39,7 @@ impl TContractsDeployerProvider for DockerContractsDeployer {
         deployment: &mut Deployment,
         deploy_deterministic_deployer: bool,
         slow: bool,
-    ) -> Result<DeploymentArtifact, Box<dyn std::error::Error>> {
+    ) -> Result<DeploymentArtifact, DeploymentError> {
         // we'll create a shared volume to share data with the contracts deployer
         let volume_dir: TempDir = TempDir::new()?; // automatically removed when dropped from scope
         std::fs::create_dir_all(volume_dir.path().join("out"))?;
@@ -47,7 +47,7 @@ impl TContractsDeployerProvider for DockerContractsDeployer {
         let volume = volume_dir.path();
 
         // write contracts config to shared volume for artifact consumption
-        fs::write(
+        system::write_file(
             volume_dir.path().join(IN_NETWORK),
             deployment.build_deploy_config()?,
         )?;
@@ -55,11 +55,11 @@ impl TContractsDeployerProvider for DockerContractsDeployer {
         let mut env: HashMap<&str, String> = HashMap::new();
 
         #[rustfmt::skip]
-         env.insert("ETH_RPC_URL", deployment.network_config.l1_rpc_url.clone().expect("L1 RPC URL not set"));
+         env.insert("ETH_RPC_URL", deployment.l1_rpc_url()?.to_string());
         #[rustfmt::skip]
          env.insert("DEPLOYER_ADDRESS", deployment.accounts_config.deployer_address.clone());
         #[rustfmt::skip]
-         env.insert("DEPLOYER_PRIVATE_KEY", deployment.accounts_config.deployer_private_key.clone().expect("Deployer private key not set"));
+         env.insert("DEPLOYER_PRIVATE_KEY", deployment.accounts_config.deployer_private_key.clone().ok_or(DeploymentError::Missing("deployer_private_key"))?);
         #[rustfmt::skip]
          env.insert("IMPL_SALT", rand::thread_rng().gen::<[u8; 16]>() .iter() .map(|b| format!("{:02x}", b)) .collect::<String>());
         #[rustfmt::skip]
@@ -86,6 +86,12 @@ impl TContractsDeployerProvider for DockerContractsDeployer {
             },
         )?;
 
+        // nothing ran, there are no outputs to load
+        if system::is_dry_run() {
+            deployment.contracts_addresses = Some("{}".to_string());
+            return Ok(Vec::new());
+        }
+
         // Load outputs into deployment
         let mut artifacts_zip = File::open(volume_dir.path().join("out").join("artifacts.zip"))?;
         let mut artifacts_zip_buffer = Vec::new();
@@ -0,0 +1,90 @@
+use crate::{
+    domain::{DnsError, DnsRecord, TDnsProvider},
+    system::{self, CommandOptions},
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Route53 only acknowledges the change, it propagates on its own
+const ROUTE53_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Upserts the records in a Route53 hosted zone with the aws cli and its current credentials
+pub struct Route53DnsProvider {
+    hosted_zone_id: String,
+}
+
+#[async_trait::async_trait]
+impl TDnsProvider for Route53DnsProvider {
+    async fn upsert(&self, records: &[DnsRecord]) -> Result<(), DnsError> {
+        system::run_command(
+            Command::new("aws")
+                .arg("route53")
+                .arg("change-resource-record-sets")
+                .arg("--hosted-zone-id")
+                .arg(&self.hosted_zone_id)
+                .arg("--change-batch")
+                .arg(change_batch(records).to_string()),
+            &CommandOptions::new().with_timeout(ROUTE53_TIMEOUT),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl Route53DnsProvider {
+    pub fn new(hosted_zone_id: &str) -> Self {
+        Self {
+            hosted_zone_id: hosted_zone_id.to_string(),
+        }
+    }
+}
+
+/// All the records in a single change batch, route53 applies it atomically
+fn change_batch(records: &[DnsRecord]) -> serde_json::Value {
+    json!({
+        "Comment": "opruaas deployment hostnames",
+        "Changes": records
+            .iter()
+            .map(|record| json!({
+                "Action": "UPSERT",
+                "ResourceRecordSet": {
+                    "Name": record.name,
+                    "Type": record.kind.to_string(),
+                    "TTL": record.ttl,
+                    "ResourceRecords": [{ "Value": record.value }],
+                },
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::DnsRecordKind;
+
+    #[test]
+    fn upserts_every_record_in_one_batch() {
+        let batch = change_batch(&[DnsRecord {
+            name: "rpc.example.com".into(),
+            kind: DnsRecordKind::Cname,
+            value: "a1b2.us-east-2.elb.amazonaws.com".into(),
+            ttl: 300,
+        }]);
+
+        assert_eq!(
+            batch["Changes"],
+            json!([{
+                "Action": "UPSERT",
+                "ResourceRecordSet": {
+                    "Name": "rpc.example.com",
+                    "Type": "CNAME",
+                    "TTL": 300,
+                    "ResourceRecords": [{ "Value": "a1b2.us-east-2.elb.amazonaws.com" }],
+                },
+            }])
+        );
+    }
+}
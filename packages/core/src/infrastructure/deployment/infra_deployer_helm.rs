@@ -0,0 +1,66 @@
+use super::HelmDeploymentRunner;
+use crate::{
+    domain::{
+        Deployment, DeploymentError, DeploymentOptions, InfraOutputs, Project, TDeploymentArtifactsRepository,
+        TDeploymentRunner, TInfraDeployerProvider,
+    },
+    infrastructure::kubernetes::TKubernetesCluster,
+    system::OutputHandler,
+};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Installs the chart in the cluster of the current kube context instead of provisioning one
+pub struct HelmDeployer {
+    runner: HelmDeploymentRunner,
+}
+
+#[async_trait::async_trait]
+impl TInfraDeployerProvider for HelmDeployer {
+    async fn deploy(
+        &self,
+        project: &Project,
+        deployment: &mut Deployment,
+        opts: &DeploymentOptions,
+    ) -> Result<(), DeploymentError> {
+        self.runner.run(project, deployment, opts).await?;
+
+        // save it in the deployment repository
+        deployment.infra_base_url = Some(opts.host.clone());
+        deployment.infra_outputs = Some(InfraOutputs {
+            load_balancer_hostname: self.runner.ingress_address().await?,
+            ..Default::default()
+        });
+
+        Ok(())
+    }
+}
+
+impl HelmDeployer {
+    pub fn new(
+        deployment_artifact_repository: Box<dyn TDeploymentArtifactsRepository>,
+        cluster: Box<dyn TKubernetesCluster>,
+    ) -> Self {
+        Self {
+            runner: HelmDeploymentRunner::new(deployment_artifact_repository, cluster),
+        }
+    }
+
+    /// How long to wait for each release to be ready after installing it
+    pub fn with_rollout_timeout(mut self, rollout_timeout: Duration) -> Self {
+        self.runner = self.runner.with_rollout_timeout(rollout_timeout);
+        self
+    }
+
+    /// Streams helm output to `on_output` instead of the debug logs
+    pub fn with_output(mut self, on_output: OutputHandler) -> Self {
+        self.runner = self.runner.with_output(on_output);
+        self
+    }
+
+    /// Kills the running helm command once `cancel` turns true
+    pub fn with_cancel(mut self, cancel: watch::Receiver<bool>) -> Self {
+        self.runner = self.runner.with_cancel(cancel);
+        self
+    }
+}
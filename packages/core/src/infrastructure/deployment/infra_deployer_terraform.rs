@@ -1,18 +1,69 @@
 use crate::{
     domain::{
-        Deployment, DeploymentKind, DeploymentOptions, Project, TDeploymentArtifactsRepository, TInfraDeployerProvider,
+        Deployment, DeploymentError, DeploymentKind, DeploymentOptions, InfraOutputs, InfraPlan, Project,
+        TDeploymentArtifactsRepository, TInfraDeployerProvider, TInfraPlanner,
     },
-    system,
+    system::{self, CommandOptions, OutputHandler},
 };
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
-    fs::{self, File},
-    io::Write,
-    path::Path,
-    process::Command,
+    collections::HashMap,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
 };
+use tokio::{process::Command, sync::watch};
+
+const TERRAFORM_INIT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const TERRAFORM_PLAN_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+/// Creating a cluster from scratch takes a while
+const TERRAFORM_APPLY_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// Workspace switches and outputs only read the state
+const TERRAFORM_STATE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// Written next to the terraform sources, overrides their backend
+const BACKEND_OVERRIDE_FILE: &str = "backend_override.tf.json";
+
+/// Where terraform keeps the state of each deployment workspace, the project directory when unset
+#[derive(Clone, Debug)]
+pub enum TerraformBackend {
+    /// Workspace states are kept under `dir`, which must outlive the project
+    Local { dir: PathBuf },
+    /// Workspace states are kept in `bucket`, locked in the `lock_table` dynamodb table if any
+    S3 {
+        bucket: String,
+        region: String,
+        lock_table: Option<String>,
+    },
+}
+
+/// Entry of `terraform output -json`
+#[derive(Deserialize)]
+struct TerraformOutput {
+    value: serde_json::Value,
+}
+
+/// Saved next to a plan file, what the plan was made from
+#[derive(Serialize, Deserialize)]
+struct PlanInputs {
+    workspace: String,
+    digest: String,
+}
+
+/// Chart with its values, artifacts and addresses written to .tmp, ready for terraform to install
+struct PreparedChart {
+    vars: Vec<String>,
+    /// Changes whenever anything terraform reads from the chart or the variables does
+    digest: String,
+}
 
 pub struct TerraformDeployer {
     deployment_artifact_repository: Box<dyn TDeploymentArtifactsRepository>,
+    on_output: Option<OutputHandler>,
+    cancel: Option<watch::Receiver<bool>>,
+    backend: Option<TerraformBackend>,
 }
 
 #[async_trait::async_trait]
@@ -22,7 +73,162 @@ impl TInfraDeployerProvider for TerraformDeployer {
         project: &Project,
         deployment: &mut Deployment,
         opts: &DeploymentOptions,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
+        let chart = self.prepare_chart(project, deployment, opts).await?;
+
+        // deploy using terraform init, plan and apply
+
+        self.init(project, deployment).await?;
+
+        system::run_command(
+            Command::new("terraform")
+                .arg("plan")
+                .args(&chart.vars)
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_PLAN_TIMEOUT),
+        )
+        .await?;
+
+        system::run_command(
+            Command::new("terraform")
+                .arg("apply")
+                .arg("-auto-approve")
+                .args(&chart.vars)
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_APPLY_TIMEOUT),
+        )
+        .await?;
+
+        self.save_outputs(project, deployment, opts).await
+    }
+}
+
+#[async_trait::async_trait]
+impl TInfraPlanner for TerraformDeployer {
+    async fn plan(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        plan_file: &Path,
+    ) -> Result<InfraPlan, DeploymentError> {
+        // terraform runs from the infra folder, relative paths would land there
+        let plan_file = &std::path::absolute(plan_file)?;
+        let chart = self.prepare_chart(project, deployment, opts).await?;
+
+        self.init(project, deployment).await?;
+
+        let mut out = OsString::from("-out=");
+        out.push(plan_file);
+        system::run_command(
+            Command::new("terraform")
+                .arg("plan")
+                .arg(out)
+                .args(&chart.vars)
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_PLAN_TIMEOUT),
+        )
+        .await?;
+
+        let summary = system::run_command(
+            Command::new("terraform")
+                .arg("show")
+                .arg("-no-color")
+                .arg(plan_file)
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_STATE_TIMEOUT).silent(),
+        )
+        .await?;
+
+        let summary_file = sidecar(plan_file, "txt");
+        system::write_file(&summary_file, &summary)?;
+        system::write_file(
+            sidecar(plan_file, "json"),
+            serde_json::to_string_pretty(&PlanInputs {
+                workspace: deployment.id.clone(),
+                digest: chart.digest,
+            })?,
+        )?;
+
+        Ok(InfraPlan {
+            plan_file: plan_file.to_path_buf(),
+            summary_file,
+            summary,
+        })
+    }
+
+    async fn apply_plan(
+        &self,
+        project: &Project,
+        deployment: &mut Deployment,
+        opts: &DeploymentOptions,
+        plan_file: &Path,
+    ) -> Result<(), DeploymentError> {
+        let plan_file = &std::path::absolute(plan_file)?;
+        let inputs: PlanInputs = serde_json::from_str(&fs::read_to_string(sidecar(plan_file, "json"))?)?;
+
+        // terraform itself rejects plans made against an older state
+        let chart = self.prepare_chart(project, deployment, opts).await?;
+        if inputs.workspace != deployment.id || inputs.digest != chart.digest {
+            return Err(DeploymentError::PlanOutdated(plan_file.to_path_buf()));
+        }
+
+        self.init(project, deployment).await?;
+
+        system::run_command(
+            Command::new("terraform")
+                .arg("apply")
+                .arg(plan_file)
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_APPLY_TIMEOUT),
+        )
+        .await?;
+
+        self.save_outputs(project, deployment, opts).await
+    }
+}
+
+impl TerraformDeployer {
+    pub fn new(deployment_artifact_repository: Box<dyn TDeploymentArtifactsRepository>) -> Self {
+        Self {
+            deployment_artifact_repository,
+            on_output: None,
+            cancel: None,
+            backend: None,
+        }
+    }
+
+    /// Streams terraform output to `on_output` instead of the debug logs
+    pub fn with_output(mut self, on_output: OutputHandler) -> Self {
+        self.on_output = Some(on_output);
+        self
+    }
+
+    /// Kills the running terraform command once `cancel` turns true
+    pub fn with_cancel(mut self, cancel: watch::Receiver<bool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Keeps the state out of the project, for projects generated in temporary directories
+    pub fn with_backend(mut self, backend: TerraformBackend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    fn command_options(&self, timeout: Duration) -> CommandOptions {
+        CommandOptions::new()
+            .with_timeout(timeout)
+            .with_output(self.on_output.clone())
+            .with_cancel(self.cancel.clone())
+    }
+
+    async fn prepare_chart(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+    ) -> Result<PreparedChart, DeploymentError> {
         let chart_root: &Path = match opts.kind {
             DeploymentKind::Replica => project.infrastructure.helm.replica.as_ref(),
             DeploymentKind::Sequencer => project.infrastructure.helm.sequencer.as_ref(),
@@ -39,72 +245,275 @@ impl TInfraDeployerProvider for TerraformDeployer {
             DeploymentKind::Replica => deployment.build_replica_values_yaml(opts),
             DeploymentKind::Sequencer => deployment.build_sequencer_values_yaml(opts),
         }?;
-        fs::write(&values_file, values_yaml)?;
+        system::write_file(&values_file, &values_yaml)?;
 
         // create artifacts.zip and addresses.json in helm so it can be loaded by it
         let deployment_artifacts = self
             .deployment_artifact_repository
             .find_one(deployment)
             .await?
-            .ok_or("No deployment artifacts found")?;
-        File::create(helm_tmp_folder.join("artifacts.zip"))?.write_all(&deployment_artifacts)?;
-        fs::write(
-            helm_tmp_folder.join("addresses.json"),
-            deployment
-                .contracts_addresses
-                .as_ref()
-                .ok_or("No deployment addresses found")?,
-        )?;
+            .ok_or(DeploymentError::ContractsNotDeployed)?;
+        system::write_file(helm_tmp_folder.join("artifacts.zip"), &deployment_artifacts)?;
+        let addresses = deployment
+            .contracts_addresses
+            .as_ref()
+            .ok_or(DeploymentError::ContractsNotDeployed)?;
+        system::write_file(helm_tmp_folder.join("addresses.json"), addresses)?;
 
-        // deploy using terraform init, plan and apply
+        let vars = vec![
+            format!("-var=values_path={}", values_file.to_str().unwrap()),
+            format!("-var=chart_path={}", chart_root.to_str().unwrap()),
+            format!("-var=namespace={}", opts.release_namespace),
+            format!("-var=name={}", deployment.id),
+        ];
 
-        system::execute_command(
+        // hashed from memory, dry runs don't write the .tmp files
+        let mut hasher = Sha256::new();
+        for var in &vars {
+            hasher.update(var);
+        }
+        hasher.update(&values_yaml);
+        hasher.update(&deployment_artifacts);
+        hasher.update(addresses);
+        for file in chart_files(chart_root, chart_root)? {
+            hasher.update(
+                file.strip_prefix(chart_root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .as_bytes(),
+            );
+            hasher.update(fs::read(&file)?);
+        }
+
+        Ok(PreparedChart {
+            vars,
+            digest: hex::encode(hasher.finalize()),
+        })
+    }
+
+    /// Initializes the providers and switches to the deployment workspace
+    async fn init(&self, project: &Project, deployment: &Deployment) -> Result<(), DeploymentError> {
+        if let Some(backend) = &self.backend {
+            system::write_file(
+                project.infrastructure.aws.join(BACKEND_OVERRIDE_FILE),
+                serde_json::to_string_pretty(&backend.to_override())?,
+            )?;
+        }
+
+        system::run_command(
             Command::new("terraform")
                 .arg("init")
                 .current_dir(&project.infrastructure.aws),
-            false,
-        )?;
+            &self.command_options(TERRAFORM_INIT_TIMEOUT),
+        )
+        .await?;
 
-        system::execute_command(
+        // one workspace per deployment, each with its own state
+        if !self.has_workspace(project, &deployment.id).await? && self.default_workspace_in_use(project).await? {
+            return Err(DeploymentError::DefaultWorkspaceInUse {
+                dir: project.infrastructure.aws.clone(),
+                deployment_id: deployment.id.clone(),
+            });
+        }
+        system::run_command(
             Command::new("terraform")
-                .arg("plan")
-                .arg(format!(
-                    "-var=values_path={}",
-                    values_file.to_str().unwrap()
-                ))
-                .arg(format!("-var=chart_path={}", chart_root.to_str().unwrap()))
-                .arg(format!("-var=namespace={}", opts.release_namespace))
-                .arg(format!("-var=name={}", deployment.id))
+                .arg("workspace")
+                .arg("select")
+                .arg("-or-create=true")
+                .arg(&deployment.id)
                 .current_dir(&project.infrastructure.aws),
-            false,
-        )?;
+            &self.command_options(TERRAFORM_STATE_TIMEOUT),
+        )
+        .await?;
+
+        Ok(())
+    }
 
-        system::execute_command(
+    async fn has_workspace(&self, project: &Project, workspace: &str) -> Result<bool, DeploymentError> {
+        let workspaces = system::run_command(
             Command::new("terraform")
-                .arg("apply")
-                .arg("-auto-approve")
-                .arg(format!(
-                    "-var=values_path={}",
-                    values_file.to_str().unwrap()
-                ))
-                .arg(format!("-var=chart_path={}", chart_root.to_str().unwrap()))
-                .arg(format!("-var=namespace={}", opts.release_namespace))
-                .arg(format!("-var=name={}", deployment.id))
+                .arg("workspace")
+                .arg("list")
                 .current_dir(&project.infrastructure.aws),
-            false,
-        )?;
+            &self.command_options(TERRAFORM_STATE_TIMEOUT).silent(),
+        )
+        .await?;
+
+        // the selected one is marked with `*`
+        Ok(workspaces
+            .lines()
+            .any(|line| line.trim_start_matches('*').trim() == workspace))
+    }
+
+    /// Older versions kept the state of every deployment in the `default` workspace
+    async fn default_workspace_in_use(&self, project: &Project) -> Result<bool, DeploymentError> {
+        let resources = system::run_command(
+            Command::new("terraform")
+                .arg("state")
+                .arg("list")
+                .env("TF_WORKSPACE", "default")
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_STATE_TIMEOUT).silent(),
+        )
+        .await?;
+
+        Ok(!resources.trim().is_empty())
+    }
+
+    async fn save_outputs(
+        &self,
+        project: &Project,
+        deployment: &mut Deployment,
+        opts: &DeploymentOptions,
+    ) -> Result<(), DeploymentError> {
+        let outputs = system::run_command(
+            Command::new("terraform")
+                .arg("output")
+                .arg("-json")
+                .current_dir(&project.infrastructure.aws),
+            &self.command_options(TERRAFORM_STATE_TIMEOUT).silent(),
+        )
+        .await?;
 
         // save it in the deployment repository
         deployment.infra_base_url = Some(opts.host.clone());
+        if !system::is_dry_run() {
+            deployment.infra_outputs = Some(parse_outputs(&outputs)?);
+        }
 
         Ok(())
     }
 }
 
-impl TerraformDeployer {
-    pub fn new(deployment_artifact_repository: Box<dyn TDeploymentArtifactsRepository>) -> Self {
-        Self {
-            deployment_artifact_repository,
+/// `infra.tfplan` -> `infra.tfplan.{extension}`
+fn sidecar(plan_file: &Path, extension: &str) -> PathBuf {
+    let mut path = plan_file.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+
+    PathBuf::from(path)
+}
+
+/// Chart files terraform installs, sorted, without the generated .tmp folder
+fn chart_files(root: &Path, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path == root.join(".tmp") {
+            continue;
         }
+        if path.is_dir() {
+            files.extend(chart_files(root, &path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+impl TerraformBackend {
+    /// Terraform override file setting this backend
+    fn to_override(&self) -> serde_json::Value {
+        let backend = match self {
+            Self::Local { dir } => serde_json::json!({
+                "local": {
+                    "path": dir.join("terraform.tfstate"),
+                    "workspace_dir": dir,
+                }
+            }),
+            Self::S3 {
+                bucket,
+                region,
+                lock_table,
+            } => {
+                let mut s3 = serde_json::json!({
+                    "bucket": bucket,
+                    "region": region,
+                    "key": "terraform.tfstate",
+                    "workspace_key_prefix": "deployments",
+                });
+                if let Some(lock_table) = lock_table {
+                    s3["dynamodb_table"] = lock_table.as_str().into();
+                }
+                serde_json::json!({ "s3": s3 })
+            }
+        };
+
+        serde_json::json!({ "terraform": { "backend": backend } })
+    }
+}
+
+fn parse_outputs(json: &str) -> Result<InfraOutputs, DeploymentError> {
+    let outputs: HashMap<String, TerraformOutput> = serde_json::from_str(json)?;
+    let output = |name: &str| {
+        outputs
+            .get(name)
+            .and_then(|output| output.value.as_str())
+            .map(String::from)
+    };
+
+    Ok(InfraOutputs {
+        cluster_name: output("cluster_name"),
+        region: output("region"),
+        load_balancer_hostname: output("elb_dnsname"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_terraform_outputs() {
+        let outputs = parse_outputs(
+            r#"{
+                "cluster_name": {"sensitive": false, "type": "string", "value": "testnet-cluster"},
+                "cluster_security_group_id": {"sensitive": false, "type": "string", "value": "sg-0123"},
+                "elb_dnsname": {"sensitive": false, "type": "string", "value": "a1b2.us-east-2.elb.amazonaws.com"},
+                "region": {"sensitive": false, "type": "string", "value": "us-east-2"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            outputs,
+            InfraOutputs {
+                cluster_name: Some("testnet-cluster".into()),
+                region: Some("us-east-2".into()),
+                load_balancer_hostname: Some("a1b2.us-east-2.elb.amazonaws.com".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn missing_outputs_are_left_empty() {
+        assert_eq!(parse_outputs("{}").unwrap(), InfraOutputs::default());
+    }
+
+    #[test]
+    fn overrides_backend() {
+        let backend = TerraformBackend::S3 {
+            bucket: "states".into(),
+            region: "us-east-2".into(),
+            lock_table: None,
+        };
+
+        assert_eq!(
+            backend.to_override(),
+            serde_json::json!({
+                "terraform": {
+                    "backend": {
+                        "s3": {
+                            "bucket": "states",
+                            "region": "us-east-2",
+                            "key": "terraform.tfstate",
+                            "workspace_key_prefix": "deployments",
+                        }
+                    }
+                }
+            })
+        );
     }
 }
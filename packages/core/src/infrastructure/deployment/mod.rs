@@ -1,13 +1,21 @@
 pub mod contracts_deployer_docker;
+pub mod dns_provider_route53;
+pub mod infra_deployer_helm;
 pub mod infra_deployer_terraform;
 pub mod monitor_docker;
+pub mod repo_artifacts_http;
 pub mod repo_artifacts_inmemory;
+pub mod repo_http;
 pub mod repo_inmemory;
 pub mod runner_helm;
 
 pub use contracts_deployer_docker::*;
+pub use dns_provider_route53::*;
+pub use infra_deployer_helm::*;
 pub use infra_deployer_terraform::*;
 pub use monitor_docker::*;
+pub use repo_artifacts_http::*;
 pub use repo_artifacts_inmemory::*;
+pub use repo_http::*;
 pub use repo_inmemory::*;
 pub use runner_helm::*;
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, vec};
 
 use crate::domain::{
-    self, Deployment, DeploymentMonitorOptions, MonitorKind, Project, Release, ReleaseRunnerOptions,
+    self, Deployment, DeploymentError, DeploymentMonitorOptions, MonitorKind, Project, Release, ReleaseRunnerOptions,
     TDeploymentMonitorRunner,
 };
 
@@ -33,7 +33,7 @@ impl TDeploymentMonitorRunner for DockerMonitorRunner {
         project: &Project,
         deployment: &Deployment,
         opts: &DeploymentMonitorOptions,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DeploymentError> {
         let (cmd, monitor_release) = match opts.kind {
             MonitorKind::Balances
             | MonitorKind::Multisig
@@ -62,11 +62,7 @@ impl TDeploymentMonitorRunner for DockerMonitorRunner {
 
         let l1_rpc = match deployment.id.as_str() {
             "dev" => "http://host.docker.internal:8545".to_string(),
-            _ => deployment
-                .network_config
-                .l1_rpc_url
-                .clone()
-                .ok_or("L1 RPC URL is required")?,
+            _ => deployment.l1_rpc_url()?.to_string(),
         };
 
         let l2_rpc = match deployment.id.as_str() {
@@ -76,12 +72,16 @@ impl TDeploymentMonitorRunner for DockerMonitorRunner {
                 deployment
                     .infra_base_url
                     .as_ref()
-                    .ok_or("Infra base URL is required")?
+                    .ok_or(DeploymentError::Missing("infra_base_url"))?
             ),
         };
 
-        let deployment_addresses: serde_json::Value =
-            serde_json::from_str(&deployment.contracts_addresses.clone().unwrap()).unwrap();
+        let deployment_addresses: serde_json::Value = serde_json::from_str(
+            deployment
+                .contracts_addresses
+                .as_ref()
+                .ok_or(DeploymentError::ContractsNotDeployed)?,
+        )?;
 
         // prefills
         let args: Vec<String> = match opts.kind {
@@ -151,7 +151,7 @@ impl TDeploymentMonitorRunner for DockerMonitorRunner {
         Ok(())
     }
 
-    fn stop(&self) -> Result<(), Box<dyn std::error::Error>> {
+    fn stop(&self) -> Result<(), DeploymentError> {
         self.release_runner.stop(CONTAINER_NAME)?;
 
         Ok(())
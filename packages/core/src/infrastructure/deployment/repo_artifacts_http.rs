@@ -0,0 +1,163 @@
+use super::repo_http::ensure_success;
+use crate::domain::{self, Deployment, DeploymentArtifact, DeploymentError};
+use reqwest::{
+    multipart::{Form, Part},
+    Client, RequestBuilder, StatusCode,
+};
+use sha2::{Digest, Sha256};
+
+/// Sha256 of the stored artifact as reported by the console, hex encoded
+pub const CHECKSUM_HEADER: &str = "x-checksum-sha256";
+
+/// Deployment artifacts repository backed by the console api
+pub struct HttpDeploymentArtifactsRepository {
+    client: Client,
+    base_url: String,
+    token: String,
+}
+
+impl HttpDeploymentArtifactsRepository {
+    pub fn new<T>(base_url: T, token: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+        }
+    }
+
+    fn artifact_url(&self, deployment: &Deployment) -> String {
+        format!("{}/deployments/{}/artifact", self.base_url, deployment.id)
+    }
+
+    fn request(&self, request: RequestBuilder) -> RequestBuilder {
+        request.bearer_auth(&self.token)
+    }
+}
+
+#[async_trait::async_trait]
+impl domain::deployment::TDeploymentArtifactsRepository for HttpDeploymentArtifactsRepository {
+    /// the download is verified against the checksum reported by the console
+    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError> {
+        let response = self
+            .request(self.client.get(self.artifact_url(deployment)))
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = ensure_success(response, &deployment.id).await?;
+        let checksum = response
+            .headers()
+            .get(CHECKSUM_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_lowercase());
+        let artifact = response
+            .bytes()
+            .await
+            .map_err(DeploymentError::storage)?
+            .to_vec();
+
+        // artifacts uploaded before checksums were recorded come without one
+        if let Some(expected) = checksum {
+            verify_checksum(&artifact, &expected)?;
+        }
+
+        Ok(Some(artifact))
+    }
+
+    async fn exists(&self, deployment: &Deployment) -> Result<bool, DeploymentError> {
+        let response = self
+            .request(self.client.head(self.artifact_url(deployment)))
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        ensure_success(response, &deployment.id).await?;
+
+        Ok(true)
+    }
+
+    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), DeploymentError> {
+        let expected = hex::encode(Sha256::digest(&artifact));
+        let form = Form::new().part(
+            "file",
+            Part::bytes(artifact)
+                .file_name("artifact.zip")
+                .mime_str("application/zip")
+                .map_err(DeploymentError::storage)?,
+        );
+
+        let response = self
+            .request(self.client.put(self.artifact_url(deployment)))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        let response = ensure_success(response, &deployment.id).await?;
+
+        match response
+            .headers()
+            .get(CHECKSUM_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(actual) => verify_checksum_value(&expected, &actual.to_lowercase()),
+            None => Ok(()),
+        }
+    }
+
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        let response = self
+            .request(self.client.delete(self.artifact_url(deployment)))
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        ensure_success(response, &deployment.id).await?;
+
+        Ok(())
+    }
+}
+
+fn verify_checksum(artifact: &[u8], expected: &str) -> Result<(), DeploymentError> {
+    verify_checksum_value(expected, &hex::encode(Sha256::digest(artifact)))
+}
+
+fn verify_checksum_value(expected: &str, actual: &str) -> Result<(), DeploymentError> {
+    if actual != expected {
+        return Err(DeploymentError::storage(format!(
+            "Artifact checksum mismatch, expected {} but got {}",
+            expected, actual
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_checksum_accepts_matching_artifacts() {
+        let artifact = b"artifact".to_vec();
+        let checksum = hex::encode(Sha256::digest(&artifact));
+
+        assert!(verify_checksum(&artifact, &checksum).is_ok());
+    }
+
+    #[test]
+    fn verify_checksum_rejects_tampered_artifacts() {
+        let checksum = hex::encode(Sha256::digest(b"artifact"));
+
+        assert!(matches!(
+            verify_checksum(b"tampered", &checksum),
+            Err(DeploymentError::Storage(_))
+        ));
+    }
+}
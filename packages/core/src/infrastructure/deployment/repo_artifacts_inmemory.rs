@@ -1,5 +1,8 @@
-use crate::domain::{self, Deployment, DeploymentArtifact};
-use std::{error::Error, fs, path::PathBuf};
+use crate::{
+    domain::{self, Deployment, DeploymentArtifact, DeploymentError},
+    system,
+};
+use std::{fs, path::PathBuf};
 
 pub struct InMemoryDeploymentArtifactsRepository {
     root: PathBuf,
@@ -22,32 +25,36 @@ impl InMemoryDeploymentArtifactsRepository {
 
 #[async_trait::async_trait]
 impl domain::deployment::TDeploymentArtifactsRepository for InMemoryDeploymentArtifactsRepository {
-    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, Box<dyn Error>> {
+    async fn find_one(&self, deployment: &Deployment) -> Result<Option<DeploymentArtifact>, DeploymentError> {
         let depl_path = self.root.join(&deployment.id).join("artifact.zip");
         let exists = std::fs::exists(&depl_path).unwrap_or(false);
         if !exists {
             return Ok(None);
         }
 
-        let deserialized = fs::read(&depl_path).unwrap();
+        let deserialized = fs::read(&depl_path)?;
         Ok(Some(deserialized))
     }
 
-    async fn exists(&self, deployment: &Deployment) -> Result<bool, Box<dyn Error>> {
+    async fn exists(&self, deployment: &Deployment) -> Result<bool, DeploymentError> {
         let depl_path = self.root.join(&deployment.id).join("artifact.zip");
 
         Ok(std::fs::exists(&depl_path).unwrap_or(false))
     }
 
-    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), Box<dyn Error>> {
+    async fn save(&self, deployment: &Deployment, artifact: DeploymentArtifact) -> Result<(), DeploymentError> {
         let depl_path = self.root.join(&deployment.id).join("artifact.zip");
-        fs::write(depl_path, artifact).unwrap();
+        system::write_file(depl_path, artifact)?;
         Ok(())
     }
 
-    async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn Error>> {
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
         let depl_path = self.root.join(&deployment.id).join("artifact.zip");
-        fs::remove_file(depl_path).unwrap();
+        if !std::fs::exists(&depl_path).unwrap_or(false) {
+            return Err(DeploymentError::NotFound(deployment.id.clone()));
+        }
+
+        fs::remove_file(depl_path)?;
         Ok(())
     }
 }
@@ -0,0 +1,239 @@
+use crate::domain::{self, Deployment, DeploymentError};
+use reqwest::{
+    header::{ETAG, IF_MATCH},
+    Client, RequestBuilder, Response, StatusCode,
+};
+
+/// Deployments repository backed by the console api
+pub struct HttpDeploymentRepository {
+    client: Client,
+    base_url: String,
+    token: String,
+}
+
+impl HttpDeploymentRepository {
+    pub fn new<T>(base_url: T, token: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+        }
+    }
+
+    fn request(&self, request: RequestBuilder) -> RequestBuilder {
+        request.bearer_auth(&self.token)
+    }
+
+    /// The deployment with the ETag of its current console revision
+    pub async fn find_with_etag(&self, id: &str) -> Result<Option<(Deployment, Option<String>)>, DeploymentError> {
+        let response = self
+            .request(
+                self.client
+                    .get(format!("{}/deployments/{}", self.base_url, id)),
+            )
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = ensure_success(response, id).await?;
+        let etag = response_etag(&response);
+        let deployment = response.json().await.map_err(DeploymentError::storage)?;
+
+        Ok(Some((deployment, etag)))
+    }
+
+    /// Updates the console revision `etag` was read at, creating the deployment when there's no etag.
+    /// Returns the ETag of the saved revision. Fails with a conflict if the console has other changes
+    pub async fn save_if_match(
+        &self,
+        deployment: &Deployment,
+        etag: Option<&str>,
+    ) -> Result<Option<String>, DeploymentError> {
+        if let Some(etag) = etag {
+            let response = self
+                .request(
+                    self.client
+                        .put(format!("{}/deployments/{}", self.base_url, deployment.id)),
+                )
+                .header(IF_MATCH, etag)
+                .json(deployment)
+                .send()
+                .await
+                .map_err(DeploymentError::storage)?;
+
+            match response.status() {
+                StatusCode::PRECONDITION_FAILED => {
+                    return Err(DeploymentError::Conflict(format!(
+                        "Deployment {} was modified in the console since it was pulled, please pull it again",
+                        deployment.id
+                    )))
+                }
+                // deleted from the console meanwhile, create it again
+                StatusCode::NOT_FOUND => {}
+                _ => {
+                    return Ok(response_etag(
+                        &ensure_success(response, &deployment.id).await?,
+                    ))
+                }
+            }
+        }
+
+        let response = self
+            .request(self.client.post(format!("{}/deployments", self.base_url)))
+            .json(deployment)
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        if response.status() == StatusCode::CONFLICT {
+            return Err(DeploymentError::Conflict(format!(
+                "Deployment {} already exists in the console, please pull it first",
+                deployment.id
+            )));
+        }
+
+        Ok(response_etag(
+            &ensure_success(response, &deployment.id).await?,
+        ))
+    }
+}
+
+fn response_etag(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(String::from)
+}
+
+/// Turns non successful console responses into errors, mapping back the statuses the console maps deployment errors to
+pub(crate) async fn ensure_success(response: Response, deployment_id: &str) -> Result<Response, DeploymentError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    match status {
+        StatusCode::UNAUTHORIZED => Err(DeploymentError::storage(
+            "Console session is missing or expired, please run `login` again",
+        )),
+        StatusCode::NOT_FOUND => Err(DeploymentError::NotFound(deployment_id.to_string())),
+        StatusCode::CONFLICT => Err(DeploymentError::Conflict(
+            response.text().await.unwrap_or_default(),
+        )),
+        status => Err(DeploymentError::storage(format!(
+            "Console request failed with status {}. {}",
+            status,
+            response.text().await.unwrap_or_default()
+        ))),
+    }
+}
+
+#[async_trait::async_trait]
+impl domain::deployment::TDeploymentRepository for HttpDeploymentRepository {
+    async fn find_by_id(&self, id: &str) -> Result<Option<Deployment>, DeploymentError> {
+        Ok(self
+            .find_with_etag(id)
+            .await?
+            .map(|(deployment, _)| deployment))
+    }
+
+    /// the console scopes deployments to the authenticated user, owner_id is ignored
+    async fn find_by_owner(&self, _owner_id: &str) -> Result<Vec<Deployment>, DeploymentError> {
+        let response = self
+            .request(self.client.get(format!("{}/deployments", self.base_url)))
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+
+        ensure_success(response, "")
+            .await?
+            .json()
+            .await
+            .map_err(DeploymentError::storage)
+    }
+
+    /// Only creates deployments, updates need the ETag they were read at, see `save_if_match`
+    async fn save(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        self.save_if_match(deployment, None).await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        let response = self
+            .request(
+                self.client
+                    .delete(format!("{}/deployments/{}", self.base_url, deployment.id)),
+            )
+            .send()
+            .await
+            .map_err(DeploymentError::storage)?;
+        ensure_success(response, &deployment.id).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn base_url_trailing_slash_is_ignored() {
+        let repository = HttpDeploymentRepository::new("https://console.example.com/", "token");
+
+        assert_eq!(repository.base_url, "https://console.example.com");
+    }
+
+    /// Answers a single request with `response`, returns the base url and the request received
+    fn console(response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0; 64 * 1024];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        (base_url, handle)
+    }
+
+    #[tokio::test]
+    async fn outdated_revisions_are_conflicts() {
+        let (base_url, request) = console("HTTP/1.1 412 Precondition Failed\r\ncontent-length: 0\r\n\r\n");
+        let repository = HttpDeploymentRepository::new(base_url.as_str(), "token");
+
+        let deployment = Deployment::new(
+            "testnet",
+            "Testnet",
+            "owner",
+            "v1.0.0",
+            "wakeuplabs",
+            crate::config::NetworkConfig::null(),
+            crate::config::AccountsConfig::null(),
+        )
+        .unwrap();
+
+        let err = repository
+            .save_if_match(&deployment, Some("\"3\""))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, DeploymentError::Conflict(_)));
+        assert!(request
+            .join()
+            .unwrap()
+            .to_lowercase()
+            .contains("if-match: \"3\""));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::domain::{self, Deployment};
+use crate::{
+    domain::{self, Deployment, DeploymentError},
+    system,
+};
 use std::{fs, path::PathBuf};
 
 pub struct InMemoryDeploymentRepository {
@@ -22,7 +25,7 @@ impl InMemoryDeploymentRepository {
 
 #[async_trait::async_trait]
 impl domain::deployment::TDeploymentRepository for InMemoryDeploymentRepository {
-    async fn find_by_id(&self, id: &str) -> Result<Option<domain::Deployment>, Box<dyn std::error::Error>> {
+    async fn find_by_id(&self, id: &str) -> Result<Option<domain::Deployment>, DeploymentError> {
         let depl_path = self.root.join(id).join("deployment.json");
         let exists = std::fs::exists(&depl_path).unwrap_or(false);
         if !exists {
@@ -35,7 +38,7 @@ impl domain::deployment::TDeploymentRepository for InMemoryDeploymentRepository
         Ok(Some(deployment))
     }
 
-    async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, Box<dyn std::error::Error>> {
+    async fn find_by_owner(&self, owner_id: &str) -> Result<Vec<Deployment>, DeploymentError> {
         let mut deployments = Vec::new();
 
         for entry in fs::read_dir(&self.root)? {
@@ -53,19 +56,24 @@ impl domain::deployment::TDeploymentRepository for InMemoryDeploymentRepository
         Ok(deployments)
     }
 
-    async fn save(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
+    async fn save(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
         let serialized = serde_json::to_string(&deployment)?;
 
         let depl_path = self.root.join(&deployment.id).join("deployment.json");
 
-        std::fs::create_dir_all(depl_path.parent().unwrap()).expect("Can't create deployments directory");
-        fs::write(depl_path, serialized)?;
+        std::fs::create_dir_all(depl_path.parent().unwrap())?;
+        system::write_file(depl_path, serialized)?;
 
         Ok(())
     }
 
-    async fn delete(&self, deployment: &Deployment) -> Result<(), Box<dyn std::error::Error>> {
-        fs::remove_file(self.root.join(&deployment.id).join("deployment.json"))?;
+    async fn delete(&self, deployment: &Deployment) -> Result<(), DeploymentError> {
+        let depl_path = self.root.join(&deployment.id).join("deployment.json");
+        if !std::fs::exists(&depl_path).unwrap_or(false) {
+            return Err(DeploymentError::NotFound(deployment.id.clone()));
+        }
+
+        fs::remove_file(depl_path)?;
         Ok(())
     }
 }
@@ -1,17 +1,33 @@
 use crate::{
-    domain::{Deployment, DeploymentKind, DeploymentOptions, Project, TDeploymentRunner},
-    system,
+    domain::{
+        Deployment, DeploymentError, DeploymentKind, DeploymentOptions, Project, TDeploymentRenderer, TDeploymentRunner,
+    },
+    infrastructure::kubernetes::TKubernetesCluster,
+    system::{self, CommandOptions, OutputHandler},
 };
 use log::info;
 use std::{
-    fs::{self, File},
-    io::Write,
-    path::Path,
-    process::Command,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
 };
+use tokio::sync::watch;
+
+/// Repo and dependency management, only talks to chart repositories
+const HELM_REPO_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const HELM_INSTALL_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+const HELM_UNINSTALL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+pub const DEFAULT_ROLLOUT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Service of the ingress-nginx prerequisite, every deployment hostname points to it
+const INGRESS_NAMESPACE: &str = "ingress-nginx";
+const INGRESS_SERVICE: &str = "ingress-nginx-controller";
 
 pub struct HelmDeploymentRunner {
     deployment_artifact_repository: Box<dyn crate::domain::TDeploymentArtifactsRepository>,
+    cluster: Box<dyn TKubernetesCluster>,
+    rollout_timeout: Duration,
+    on_output: Option<OutputHandler>,
+    cancel: Option<watch::Receiver<bool>>,
 }
 
 #[async_trait::async_trait]
@@ -21,56 +37,19 @@ impl TDeploymentRunner for HelmDeploymentRunner {
         project: &Project,
         deployment: &Deployment,
         opts: &DeploymentOptions,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let chart_root = match opts.kind {
-            DeploymentKind::Replica => project.infrastructure.helm.replica.as_ref(),
-            DeploymentKind::Sequencer => project.infrastructure.helm.sequencer.as_ref(),
-        };
-
+    ) -> Result<(), DeploymentError> {
         // add repos, install pre-requisites and build dependencies
-        self.build_dependencies(chart_root)?;
-
-        // create .tmp folder
-        let helm_tmp_folder = chart_root.join(".tmp");
-        let _ = fs::remove_dir_all(&helm_tmp_folder);
-        fs::create_dir_all(&helm_tmp_folder)?;
-
-        // create values file from stack.
-        let values_file = match opts.values_path.as_ref() {
-            Some(path) => path.clone(),
-            None => {
-                let values_file = helm_tmp_folder.join("values.yaml");
-                let values_yaml = match opts.kind {
-                    DeploymentKind::Replica => deployment.build_replica_values_yaml(opts),
-                    DeploymentKind::Sequencer => deployment.build_sequencer_values_yaml(opts),
-                }?;
-                fs::write(&values_file, values_yaml)?;
-                values_file
-            }
-        };
-
-        // create artifacts.zip and addresses.json in helm so it can be loaded by it
-        let deployment_artifacts = self
-            .deployment_artifact_repository
-            .find_one(deployment)
-            .await?
-            .ok_or("No deployment artifacts found")?;
-
-        File::create(helm_tmp_folder.join("artifacts.zip"))?.write_all(&deployment_artifacts)?;
-
-        fs::write(
-            helm_tmp_folder.join("addresses.json"),
-            deployment
-                .contracts_addresses
-                .as_ref()
-                .ok_or("No deployment addresses found")?,
-        )?;
+        self.add_repositories().await?;
+        self.install_prerequisites().await?;
+        let (chart_root, values_file) = self.prepare_chart(project, deployment, opts).await?;
 
         // install core infrastructure
 
-        system::execute_command(
-            Command::new("helm")
-                .arg("install")
+        // upgrades in place when deploying again to the same cluster
+        system::run_command(
+            tokio::process::Command::new("helm")
+                .arg("upgrade")
+                .arg("--install")
                 .arg(&opts.release_tag)
                 .arg("-f")
                 .arg(values_file.to_str().unwrap())
@@ -78,48 +57,116 @@ impl TDeploymentRunner for HelmDeploymentRunner {
                 .arg(&opts.release_namespace)
                 .arg("--create-namespace")
                 .arg(chart_root.to_str().unwrap()),
-            false,
-        )?;
+            &self.command_options(HELM_INSTALL_TIMEOUT),
+        )
+        .await?;
 
-        self.wait_for_running_release(&opts.release_namespace)?;
+        self.wait_for_running_release(&opts.release_namespace)
+            .await?;
 
         Ok(())
     }
 
-    fn stop(&self, release_tag: &str, release_namespace: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let running_releases = system::execute_command(
-            Command::new("helm")
-                .arg("list")
-                .arg("--no-headers")
-                .arg("--namespace")
-                .arg(release_namespace),
-            true,
-        )?;
-        if !running_releases.contains(release_tag) {
-            return Ok(());
-        }
-
-        system::execute_command(
-            Command::new("helm")
+    async fn stop(&self, release_tag: &str, release_namespace: &str) -> Result<(), DeploymentError> {
+        // not cancellable, it usually runs because of a cancellation
+        system::run_command(
+            tokio::process::Command::new("helm")
                 .arg("uninstall")
                 .arg(release_tag)
                 .arg("--namespace")
-                .arg(release_namespace),
-            false,
-        )?;
+                .arg(release_namespace)
+                .arg("--ignore-not-found"),
+            &CommandOptions::new()
+                .with_timeout(HELM_UNINSTALL_TIMEOUT)
+                .with_output(self.on_output.clone()),
+        )
+        .await?;
 
         Ok(())
     }
 }
 
+#[async_trait::async_trait]
+impl TDeploymentRenderer for HelmDeploymentRunner {
+    async fn render(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>, DeploymentError> {
+        // pre-requisites live in the cluster, only the chart dependencies are needed to render. they're
+        // fetched from the chart repositories (Chart.lock is behind Chart.yaml) and the chart reads the
+        // contracts artifacts and addresses, so rendering needs network access and deployed contracts
+        self.add_repositories().await?;
+        let (chart_root, values_file) = self.prepare_chart(project, deployment, opts).await?;
+
+        system::run_command(
+            tokio::process::Command::new("helm")
+                .arg("template")
+                .arg(&opts.release_tag)
+                .arg(chart_root.to_str().unwrap())
+                .arg("-f")
+                .arg(values_file.to_str().unwrap())
+                .arg("--namespace")
+                .arg(&opts.release_namespace)
+                .arg("--output-dir")
+                .arg(output_dir.to_str().unwrap()),
+            &self.command_options(HELM_REPO_TIMEOUT),
+        )
+        .await?;
+
+        if system::is_dry_run() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = list_files(output_dir)?;
+        files.sort();
+
+        Ok(files)
+    }
+}
+
 impl HelmDeploymentRunner {
-    pub fn new(deployment_artifact_repository: Box<dyn crate::domain::TDeploymentArtifactsRepository>) -> Self {
+    pub fn new(
+        deployment_artifact_repository: Box<dyn crate::domain::TDeploymentArtifactsRepository>,
+        cluster: Box<dyn TKubernetesCluster>,
+    ) -> Self {
         Self {
             deployment_artifact_repository,
+            cluster,
+            rollout_timeout: DEFAULT_ROLLOUT_TIMEOUT,
+            on_output: None,
+            cancel: None,
         }
     }
 
-    fn build_dependencies(&self, root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    /// How long to wait for each release to be ready after installing it
+    pub fn with_rollout_timeout(mut self, rollout_timeout: Duration) -> Self {
+        self.rollout_timeout = rollout_timeout;
+        self
+    }
+
+    /// Streams helm output to `on_output` instead of the debug logs
+    pub fn with_output(mut self, on_output: OutputHandler) -> Self {
+        self.on_output = Some(on_output);
+        self
+    }
+
+    /// Kills the running helm command once `cancel` turns true
+    pub fn with_cancel(mut self, cancel: watch::Receiver<bool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn command_options(&self, timeout: Duration) -> CommandOptions {
+        CommandOptions::new()
+            .with_timeout(timeout)
+            .with_output(self.on_output.clone())
+            .with_cancel(self.cancel.clone())
+    }
+
+    async fn add_repositories(&self) -> Result<(), DeploymentError> {
         let repo_dependencies = [
             (
                 "ingress-nginx",
@@ -134,19 +181,29 @@ impl HelmDeploymentRunner {
         ];
 
         for (repo, url) in repo_dependencies {
-            system::execute_command(
-                Command::new("helm")
+            system::run_command(
+                tokio::process::Command::new("helm")
                     .arg("repo")
                     .arg("add")
                     .arg(repo)
                     .arg(url),
-                false,
-            )?;
+                &self.command_options(HELM_REPO_TIMEOUT),
+            )
+            .await?;
         }
-        system::execute_command(Command::new("helm").arg("repo").arg("update"), false)?;
+        system::run_command(
+            tokio::process::Command::new("helm")
+                .arg("repo")
+                .arg("update"),
+            &self.command_options(HELM_REPO_TIMEOUT),
+        )
+        .await?;
 
-        // install pre-requisites, without these helm won't be capable of understanding out chart
+        Ok(())
+    }
 
+    /// Without these helm won't be capable of understanding out chart
+    async fn install_prerequisites(&self) -> Result<(), DeploymentError> {
         let pre_requisites = [
             (
                 "ingress-nginx",
@@ -167,63 +224,145 @@ impl HelmDeploymentRunner {
 
         for (name, repo, args) in pre_requisites {
             // if already installed skip
-            if system::execute_command(Command::new("helm").args(["list", "-n", name]), true)?.contains(name) {
+            if self.is_installed(name, name).await? {
                 continue;
             }
 
             info!("Installing {} from {}", name, repo);
-            system::execute_command(
-                Command::new("helm")
+            system::run_command(
+                tokio::process::Command::new("helm")
                     .args(["install", name, repo, "-n", name, "--create-namespace"])
                     .args(args),
-                false,
-            )?;
+                &self.command_options(HELM_INSTALL_TIMEOUT),
+            )
+            .await?;
 
-            self.wait_for_running_release(name)?;
+            self.wait_for_running_release(name).await?;
         }
 
-        // build dependencies
+        Ok(())
+    }
+
+    /// Builds the chart dependencies and writes the values, artifacts and addresses it loads to its .tmp folder
+    async fn prepare_chart(
+        &self,
+        project: &Project,
+        deployment: &Deployment,
+        opts: &DeploymentOptions,
+    ) -> Result<(PathBuf, PathBuf), DeploymentError> {
+        let chart_root = match opts.kind {
+            DeploymentKind::Replica => project.infrastructure.helm.replica.clone(),
+            DeploymentKind::Sequencer => project.infrastructure.helm.sequencer.clone(),
+        };
 
-        system::execute_command(
-            Command::new("helm")
+        system::run_command(
+            tokio::process::Command::new("helm")
                 .arg("dependency")
                 .arg("update")
-                .current_dir(root),
-            false,
-        )?;
+                .current_dir(&chart_root),
+            &self.command_options(HELM_REPO_TIMEOUT),
+        )
+        .await?;
 
-        system::execute_command(
-            Command::new("helm")
+        system::run_command(
+            tokio::process::Command::new("helm")
                 .arg("dependency")
                 .arg("build")
-                .current_dir(root),
-            false,
+                .current_dir(&chart_root),
+            &self.command_options(HELM_REPO_TIMEOUT),
+        )
+        .await?;
+
+        // create .tmp folder
+        let helm_tmp_folder = chart_root.join(".tmp");
+        let _ = fs::remove_dir_all(&helm_tmp_folder);
+        fs::create_dir_all(&helm_tmp_folder)?;
+
+        // create values file from stack.
+        let values_file = match opts.values_path.as_ref() {
+            Some(path) => path.clone(),
+            None => {
+                let values_file = helm_tmp_folder.join("values.yaml");
+                let values_yaml = match opts.kind {
+                    DeploymentKind::Replica => deployment.build_replica_values_yaml(opts),
+                    DeploymentKind::Sequencer => deployment.build_sequencer_values_yaml(opts),
+                }?;
+                system::write_file(&values_file, values_yaml)?;
+                values_file
+            }
+        };
+
+        // create artifacts.zip and addresses.json in helm so it can be loaded by it
+        let deployment_artifacts = match self
+            .deployment_artifact_repository
+            .find_one(deployment)
+            .await?
+        {
+            Some(deployment_artifacts) => deployment_artifacts,
+            // contracts "deployed" earlier in the same dry run were never saved
+            None if system::is_dry_run() => Vec::new(),
+            None => return Err(DeploymentError::ContractsNotDeployed),
+        };
+
+        system::write_file(helm_tmp_folder.join("artifacts.zip"), deployment_artifacts)?;
+
+        system::write_file(
+            helm_tmp_folder.join("addresses.json"),
+            deployment
+                .contracts_addresses
+                .as_ref()
+                .ok_or(DeploymentError::ContractsNotDeployed)?,
         )?;
 
-        Ok(())
+        Ok((chart_root, values_file))
     }
 
-    fn wait_for_running_release(&self, namespace: &str) -> Result<(), Box<dyn std::error::Error>> {
-        info!("Waiting for release {} to be ready", namespace);
+    /// Dry runs don't look at the cluster, everything is installed as if it was empty
+    async fn is_installed(&self, namespace: &str, release: &str) -> Result<bool, DeploymentError> {
+        if system::is_dry_run() {
+            return Ok(false);
+        }
 
-        loop {
-            let pods = system::execute_command(
-                Command::new("kubectl")
-                    .arg("get")
-                    .arg("pods")
-                    .arg("-n")
-                    .arg(namespace)
-                    .arg("--no-headers"),
-                true,
-            )?;
-
-            if !pods.contains("Pending") && !pods.contains("CrashLoopBackOff") && !pods.contains("Err") {
-                break;
-            }
+        Ok(self.cluster.has_release(namespace, release).await?)
+    }
+
+    /// Address of the ingress load balancer, none in dry runs or while the cloud provider assigns it
+    pub(crate) async fn ingress_address(&self) -> Result<Option<String>, DeploymentError> {
+        if system::is_dry_run() {
+            return Ok(None);
+        }
+
+        Ok(self
+            .cluster
+            .load_balancer_address(INGRESS_NAMESPACE, INGRESS_SERVICE)
+            .await?)
+    }
 
-            std::thread::sleep(std::time::Duration::from_secs(4));
+    async fn wait_for_running_release(&self, namespace: &str) -> Result<(), DeploymentError> {
+        if system::is_dry_run() {
+            return Ok(());
         }
 
+        info!("Waiting for release {} to be ready", namespace);
+        self.cluster
+            .wait_for_rollout(namespace, self.rollout_timeout)
+            .await?;
+
         Ok(())
     }
 }
+
+/// Every file under `dir`, helm nests the rendered templates by chart and subchart
+fn list_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(list_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
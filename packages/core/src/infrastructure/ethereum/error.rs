@@ -0,0 +1,48 @@
+use crate::system::{exit_status, CommandError};
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum EthereumError {
+    #[error("Unsupported chain id {0}")]
+    UnsupportedChain(u32),
+
+    #[error("Timeout reached: Node did not respond within {0} seconds.")]
+    Timeout(u64),
+
+    #[error("Rpc request failed. {0}")]
+    Rpc(#[from] reqwest::Error),
+
+    #[error("Unexpected rpc response. {0}")]
+    InvalidResponse(String),
+
+    #[error("Invalid private key")]
+    InvalidPrivateKey,
+
+    #[error("Could not sign message. {0}")]
+    Signing(#[from] k256::ecdsa::Error),
+
+    #[error("{tool} failed with {}. {stderr}", exit_status(.exit_code))]
+    ExternalToolFailed {
+        tool: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+}
+
+// implementations ================================================
+
+impl From<CommandError> for EthereumError {
+    fn from(err: CommandError) -> Self {
+        Self::ExternalToolFailed {
+            tool: err.tool().to_string(),
+            exit_code: err.exit_code(),
+            stderr: err.stderr(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for EthereumError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::InvalidResponse(err.to_string())
+    }
+}
@@ -1,3 +1,6 @@
+pub mod error;
+pub use error::*;
+
 pub mod rpc;
 pub mod rpc_json;
 pub use rpc::*;
@@ -7,3 +10,11 @@ pub mod node;
 pub mod node_geth;
 pub use node::*;
 pub use node_geth::*;
+
+pub mod wallet;
+pub mod wallet_local;
+pub use wallet::*;
+pub use wallet_local::*;
+
+pub mod siwe;
+pub use siwe::*;
@@ -1,5 +1,5 @@
-use super::{EthRpc, JsonRpc, TTestnetNode};
-use crate::system::execute_command;
+use super::{EthRpc, EthereumError, JsonRpc, TTestnetNode};
+use crate::system::{execute_command, is_dry_run};
 use serde_json::json;
 use std::{process::Command, thread, time};
 
@@ -28,9 +28,9 @@ impl GethTestnetNode {
 }
 
 impl TTestnetNode for GethTestnetNode {
-    fn start(&self, chain_id: u32, port: u64) -> Result<(), Box<dyn std::error::Error>> {
+    fn start(&self, chain_id: u32, port: u64) -> Result<(), EthereumError> {
         if chain_id != 1337 {
-            return Err("Unsupported chain id".into());
+            return Err(EthereumError::UnsupportedChain(chain_id));
         }
 
         execute_command(Command::new("docker").args(["pull", DOCKER_IMAGE]), false)?;
@@ -62,6 +62,11 @@ impl TTestnetNode for GethTestnetNode {
             true,
         )?;
 
+        // nothing to wait for or fund in a dry run
+        if is_dry_run() {
+            return Ok(());
+        }
+
         // Wait for node to start
         let timeout_duration = time::Duration::from_secs(MAX_TIMEOUT);
         let start_time = time::Instant::now();
@@ -71,11 +76,7 @@ impl TTestnetNode for GethTestnetNode {
 
         loop {
             if start_time.elapsed() >= timeout_duration {
-                return Err(format!(
-                    "Timeout reached: Node did not respond within {} seconds.",
-                    MAX_TIMEOUT
-                )
-                .into());
+                return Err(EthereumError::Timeout(MAX_TIMEOUT));
             }
 
             match self
@@ -97,9 +98,10 @@ impl TTestnetNode for GethTestnetNode {
         let send_from = accounts
             .get("result")
             .and_then(|v| v.get(0))
-            .unwrap()
-            .as_str()
-            .unwrap();
+            .and_then(|v| v.as_str())
+            .ok_or(EthereumError::InvalidResponse(
+                "eth_accounts returned no accounts".into(),
+            ))?;
 
         // fund dev accounts with 1000 eth each
 
@@ -151,7 +153,7 @@ impl TTestnetNode for GethTestnetNode {
         Ok(())
     }
 
-    fn stop(&self) -> Result<(), Box<dyn std::error::Error>> {
+    fn stop(&self) -> Result<(), EthereumError> {
         let running_containers = execute_command(Command::new("docker").arg("ps"), true)?;
         if !running_containers.contains(CONTAINER_NAME) {
             return Ok(());
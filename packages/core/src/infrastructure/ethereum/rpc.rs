@@ -1,5 +1,5 @@
+use super::EthereumError;
 use serde_json::Value;
-use std::error::Error;
 
 pub trait EthRpc {
     fn send_rpc_request(
@@ -8,5 +8,5 @@ pub trait EthRpc {
         iden: u64,
         method: &str,
         params: Vec<Value>,
-    ) -> Result<Value, Box<dyn Error>>;
+    ) -> Result<Value, EthereumError>;
 }
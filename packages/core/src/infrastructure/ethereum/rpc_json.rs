@@ -1,4 +1,4 @@
-use super::EthRpc;
+use super::{EthRpc, EthereumError};
 use reqwest::{blocking::Client, header::CONTENT_TYPE};
 use serde_json::{json, Value};
 
@@ -25,7 +25,7 @@ impl EthRpc for JsonRpc {
         iden: u64,
         method: &str,
         params: Vec<serde_json::Value>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    ) -> Result<serde_json::Value, EthereumError> {
         let client = Client::new();
 
         let body = json!({
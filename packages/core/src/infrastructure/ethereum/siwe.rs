@@ -0,0 +1,70 @@
+use std::{fmt, time::Duration};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// EIP-4361 Sign-In with Ethereum message
+#[derive(Debug, Clone)]
+pub struct SiweMessage {
+    pub domain: String,
+    pub address: String,
+    pub statement: String,
+    pub uri: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: OffsetDateTime,
+    pub expiration_time: Option<OffsetDateTime>,
+}
+
+// implementations ================================================
+
+impl SiweMessage {
+    /// Builds a message for the given server issued nonce, valid for `ttl`
+    pub fn new<T>(domain: T, address: T, statement: T, uri: T, nonce: T, ttl: Duration) -> Self
+    where
+        T: Into<String>,
+    {
+        let issued_at = OffsetDateTime::now_utc();
+
+        Self {
+            domain: domain.into(),
+            address: address.into(),
+            statement: statement.into(),
+            uri: uri.into(),
+            chain_id: 1,
+            nonce: nonce.into(),
+            issued_at,
+            expiration_time: Some(issued_at + ttl),
+        }
+    }
+}
+
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let issued_at = self.issued_at.format(&Rfc3339).map_err(|_| fmt::Error)?;
+
+        write!(
+            f,
+            "{domain} wants you to sign in with your Ethereum account:\n\
+            {address}\n\n\
+            {statement}\n\n\
+            URI: {uri}\n\
+            Version: 1\n\
+            Chain ID: {chain_id}\n\
+            Nonce: {nonce}\n\
+            Issued At: {issued_at}",
+            domain = self.domain,
+            address = self.address,
+            statement = self.statement,
+            uri = self.uri,
+            chain_id = self.chain_id,
+            nonce = self.nonce,
+            issued_at = issued_at,
+        )?;
+
+        if let Some(expiration_time) = self.expiration_time {
+            let expiration_time = expiration_time.format(&Rfc3339).map_err(|_| fmt::Error)?;
+            write!(f, "\nExpiration Time: {}", expiration_time)?;
+        }
+
+        Ok(())
+    }
+}
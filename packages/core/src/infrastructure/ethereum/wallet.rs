@@ -0,0 +1,6 @@
+use super::EthereumError;
+
+pub trait TWallet: Send + Sync {
+    fn address(&self) -> String;
+    fn sign_message(&self, message: &str) -> Result<String, EthereumError>;
+}
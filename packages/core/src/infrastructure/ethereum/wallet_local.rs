@@ -0,0 +1,108 @@
+use super::{EthereumError, TWallet};
+use k256::ecdsa::SigningKey;
+use sha3::{Digest, Keccak256};
+
+pub struct LocalWallet {
+    signing_key: SigningKey,
+}
+
+// implementations ================================================
+
+impl LocalWallet {
+    pub fn from_private_key(private_key: &str) -> Result<Self, EthereumError> {
+        let bytes =
+            hex::decode(private_key.trim().trim_start_matches("0x")).map_err(|_| EthereumError::InvalidPrivateKey)?;
+        let signing_key = SigningKey::from_slice(&bytes).map_err(|_| EthereumError::InvalidPrivateKey)?;
+
+        Ok(Self { signing_key })
+    }
+
+    /// EIP-55 mixed-case checksum encoding of a 20 bytes address
+    fn checksum(address: &[u8]) -> String {
+        let lower = hex::encode(address);
+        let hash = Keccak256::digest(lower.as_bytes());
+
+        let checksummed: String = lower
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+                if c.is_ascii_alphabetic() && nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+
+        format!("0x{}", checksummed)
+    }
+}
+
+impl TWallet for LocalWallet {
+    fn address(&self) -> String {
+        let public_key = self.signing_key.verifying_key().to_encoded_point(false);
+        let hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+
+        Self::checksum(&hash[12..])
+    }
+
+    /// EIP-191 personal_sign, returns the 65 bytes signature hex encoded
+    fn sign_message(&self, message: &str) -> Result<String, EthereumError> {
+        let digest = Keccak256::new_with_prefix(format!(
+            "\x19Ethereum Signed Message:\n{}{}",
+            message.len(),
+            message
+        ));
+        let (signature, recovery_id) = self.signing_key.sign_digest_recoverable(digest)?;
+
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte() + 27);
+
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LocalWallet;
+    use crate::infrastructure::ethereum::TWallet;
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+    use sha3::{Digest, Keccak256};
+
+    const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn derives_checksummed_address() {
+        let wallet = LocalWallet::from_private_key(PRIVATE_KEY).unwrap();
+
+        assert_eq!(
+            wallet.address(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+    }
+
+    #[test]
+    fn signs_recoverable_personal_message() {
+        let wallet = LocalWallet::from_private_key(PRIVATE_KEY).unwrap();
+
+        let signature = hex::decode(
+            wallet
+                .sign_message("hello")
+                .unwrap()
+                .trim_start_matches("0x"),
+        )
+        .unwrap();
+        assert_eq!(signature.len(), 65);
+
+        let digest = Keccak256::new_with_prefix("\x19Ethereum Signed Message:\n5hello");
+        let recovered = VerifyingKey::recover_from_digest(
+            digest,
+            &Signature::from_slice(&signature[..64]).unwrap(),
+            RecoveryId::from_byte(signature[64] - 27).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(&recovered, wallet.signing_key.verifying_key());
+    }
+}
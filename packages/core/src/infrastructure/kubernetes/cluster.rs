@@ -0,0 +1,202 @@
+use super::KubernetesError;
+use log::info;
+use std::{fmt, time::Duration};
+use tokio::time::Instant;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Rollout progress of a deployment or statefulset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkloadStatus {
+    /// `Deployment` or `StatefulSet`
+    pub kind: String,
+    pub name: String,
+    pub desired: i32,
+    pub updated: i32,
+    pub ready: i32,
+    /// The controller has seen the latest spec, counts are stale until it does
+    pub observed: bool,
+}
+
+/// A container that isn't running and why, or a pod that can't be scheduled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodFailure {
+    pub pod: String,
+    /// None if the pod has no containers running yet
+    pub container: Option<String>,
+    /// e.g. CrashLoopBackOff, ImagePullBackOff, Unschedulable
+    pub reason: String,
+    pub message: Option<String>,
+    /// How the previous run ended, set for containers that keep crashing
+    pub last_termination: Option<String>,
+    /// Latest warning event on the pod
+    pub event: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait TKubernetesCluster: Send + Sync {
+    /// Deployments and statefulsets in the namespace
+    async fn workloads(&self, namespace: &str) -> Result<Vec<WorkloadStatus>, KubernetesError>;
+
+    /// Pods in the namespace with containers that are stuck or crashed
+    async fn failing_pods(&self, namespace: &str) -> Result<Vec<PodFailure>, KubernetesError>;
+
+    /// True if helm has a deployed release by that name in the namespace
+    async fn has_release(&self, namespace: &str, release: &str) -> Result<bool, KubernetesError>;
+
+    /// Hostname or ip the cloud provider assigned to a LoadBalancer service, none until it does
+    async fn load_balancer_address(&self, namespace: &str, service: &str) -> Result<Option<String>, KubernetesError>;
+
+    /// Waits for every workload in the namespace to roll out, reports the failing pods if they don't in time
+    async fn wait_for_rollout(&self, namespace: &str, timeout: Duration) -> Result<(), KubernetesError> {
+        let started = Instant::now();
+
+        loop {
+            let pending: Vec<String> = self
+                .workloads(namespace)
+                .await?
+                .iter()
+                .filter(|workload| !workload.is_ready())
+                .map(WorkloadStatus::to_string)
+                .collect();
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return Err(KubernetesError::RolloutTimeout {
+                    namespace: namespace.to_string(),
+                    timeout,
+                    pending,
+                    failures: self.failing_pods(namespace).await?,
+                });
+            }
+
+            info!("Waiting on {}", pending.join(", "));
+            tokio::time::sleep(POLL_INTERVAL.min(timeout - elapsed)).await;
+        }
+    }
+}
+
+// implementations ================================================
+
+impl WorkloadStatus {
+    pub fn is_ready(&self) -> bool {
+        self.observed && self.updated >= self.desired && self.ready >= self.desired
+    }
+}
+
+impl fmt::Display for WorkloadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} {}/{} ready",
+            self.kind, self.name, self.ready, self.desired
+        )
+    }
+}
+
+impl fmt::Display for PodFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.container {
+            Some(container) => write!(f, "{}/{} {}", self.pod, container, self.reason)?,
+            None => write!(f, "{} {}", self.pod, self.reason)?,
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        if let Some(last_termination) = &self.last_termination {
+            write!(f, ", last terminated with {}", last_termination)?;
+        }
+        if let Some(event) = &self.event {
+            write!(f, " ({})", event)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Rolls out after `ready_after` polls
+    struct FakeCluster {
+        ready_after: usize,
+        polls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl TKubernetesCluster for FakeCluster {
+        async fn workloads(&self, _namespace: &str) -> Result<Vec<WorkloadStatus>, KubernetesError> {
+            let polls = self.polls.fetch_add(1, Ordering::SeqCst);
+
+            Ok(vec![WorkloadStatus {
+                kind: "Deployment".into(),
+                name: "op-node".into(),
+                desired: 1,
+                updated: 1,
+                ready: (polls >= self.ready_after) as i32,
+                observed: true,
+            }])
+        }
+
+        async fn failing_pods(&self, _namespace: &str) -> Result<Vec<PodFailure>, KubernetesError> {
+            Ok(vec![PodFailure {
+                pod: "op-node-0".into(),
+                container: Some("op-node".into()),
+                reason: "CrashLoopBackOff".into(),
+                message: None,
+                last_termination: Some("Error (exit code 1)".into()),
+                event: None,
+            }])
+        }
+
+        async fn has_release(&self, _namespace: &str, _release: &str) -> Result<bool, KubernetesError> {
+            Ok(true)
+        }
+
+        async fn load_balancer_address(
+            &self,
+            _namespace: &str,
+            _service: &str,
+        ) -> Result<Option<String>, KubernetesError> {
+            Ok(None)
+        }
+    }
+
+    fn cluster(ready_after: usize) -> FakeCluster {
+        FakeCluster {
+            ready_after,
+            polls: AtomicUsize::new(0),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_until_workloads_are_ready() {
+        let cluster = cluster(3);
+
+        cluster
+            .wait_for_rollout("opruaas", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert_eq!(cluster.polls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_failing_pods_on_timeout() {
+        let err = cluster(usize::MAX)
+            .wait_for_rollout("opruaas", Duration::from_secs(60))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Release in opruaas not ready after 60s, waiting on Deployment/op-node 0/1 ready. \
+            Failing: op-node-0/op-node CrashLoopBackOff, last terminated with Error (exit code 1)"
+        );
+    }
+}
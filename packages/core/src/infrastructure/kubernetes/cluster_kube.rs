@@ -0,0 +1,342 @@
+use super::{KubernetesError, PodFailure, TKubernetesCluster, WorkloadStatus};
+use k8s_openapi::api::{
+    apps::v1::{Deployment, StatefulSet},
+    core::v1::{Event, Pod, Secret, Service},
+};
+use kube::{api::ListParams, Api, Client, ResourceExt};
+use tokio::sync::OnceCell;
+
+/// Cluster of the current kubeconfig context, or the one the process runs in
+pub struct KubeCluster {
+    client: OnceCell<Client>,
+}
+
+/// Waiting reasons of containers that are still starting, not failing
+const STARTING_REASONS: [&str; 2] = ["ContainerCreating", "PodInitializing"];
+
+// implementations ================================================
+
+impl Default for KubeCluster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KubeCluster {
+    pub fn new() -> Self {
+        Self {
+            client: OnceCell::new(),
+        }
+    }
+
+    /// Connects on first use, so commands that don't need the cluster don't require one
+    async fn client(&self) -> Result<Client, KubernetesError> {
+        let client = self.client.get_or_try_init(Client::try_default).await?;
+
+        Ok(client.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl TKubernetesCluster for KubeCluster {
+    async fn workloads(&self, namespace: &str) -> Result<Vec<WorkloadStatus>, KubernetesError> {
+        let client = self.client().await?;
+        let deployments = Api::<Deployment>::namespaced(client.clone(), namespace)
+            .list(&ListParams::default())
+            .await?;
+        let statefulsets = Api::<StatefulSet>::namespaced(client, namespace)
+            .list(&ListParams::default())
+            .await?;
+
+        Ok(deployments
+            .items
+            .iter()
+            .map(WorkloadStatus::from)
+            .chain(statefulsets.items.iter().map(WorkloadStatus::from))
+            .collect())
+    }
+
+    async fn failing_pods(&self, namespace: &str) -> Result<Vec<PodFailure>, KubernetesError> {
+        let client = self.client().await?;
+        let pods = Api::<Pod>::namespaced(client.clone(), namespace)
+            .list(&ListParams::default())
+            .await?;
+        let events = Api::<Event>::namespaced(client, namespace)
+            .list(&ListParams::default().fields("type=Warning,involvedObject.kind=Pod"))
+            .await?;
+
+        Ok(pods
+            .items
+            .iter()
+            .flat_map(|pod| {
+                let event = latest_event(&events.items, &pod.name_any());
+                pod_failures(pod)
+                    .into_iter()
+                    .map(move |failure| PodFailure {
+                        event: event.clone(),
+                        ..failure
+                    })
+            })
+            .collect())
+    }
+
+    async fn has_release(&self, namespace: &str, release: &str) -> Result<bool, KubernetesError> {
+        // helm keeps a secret per release revision, labeled with the release name and status
+        let secrets = Api::<Secret>::namespaced(self.client().await?, namespace)
+            .list(&ListParams::default().labels(&format!("owner=helm,name={},status=deployed", release)))
+            .await?;
+
+        Ok(!secrets.items.is_empty())
+    }
+
+    async fn load_balancer_address(&self, namespace: &str, service: &str) -> Result<Option<String>, KubernetesError> {
+        let service = Api::<Service>::namespaced(self.client().await?, namespace)
+            .get_opt(service)
+            .await?;
+
+        Ok(service
+            .and_then(|service| service.status)
+            .and_then(|status| status.load_balancer)
+            .and_then(|load_balancer| load_balancer.ingress)
+            .and_then(|ingress| ingress.into_iter().next())
+            .and_then(|ingress| ingress.hostname.or(ingress.ip)))
+    }
+}
+
+impl From<&Deployment> for WorkloadStatus {
+    fn from(deployment: &Deployment) -> Self {
+        let status = deployment.status.clone().unwrap_or_default();
+
+        Self {
+            kind: "Deployment".to_string(),
+            name: deployment.name_any(),
+            desired: deployment
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.replicas)
+                .unwrap_or(1),
+            updated: status.updated_replicas.unwrap_or(0),
+            ready: status.available_replicas.unwrap_or(0),
+            observed: status.observed_generation.unwrap_or(0) >= deployment.metadata.generation.unwrap_or(0),
+        }
+    }
+}
+
+impl From<&StatefulSet> for WorkloadStatus {
+    fn from(statefulset: &StatefulSet) -> Self {
+        let status = statefulset.status.clone().unwrap_or_default();
+
+        Self {
+            kind: "StatefulSet".to_string(),
+            name: statefulset.name_any(),
+            desired: statefulset
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.replicas)
+                .unwrap_or(1),
+            updated: status.updated_replicas.unwrap_or(0),
+            ready: status.ready_replicas.unwrap_or(0),
+            observed: status.observed_generation.unwrap_or(0) >= statefulset.metadata.generation.unwrap_or(0),
+        }
+    }
+}
+
+/// Containers that are stuck waiting or exited with an error, or why the pod isn't scheduled
+fn pod_failures(pod: &Pod) -> Vec<PodFailure> {
+    let Some(status) = pod.status.as_ref() else {
+        return vec![];
+    };
+
+    let unschedulable = status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|condition| condition.type_ == "PodScheduled" && condition.status == "False");
+    if let Some(condition) = unschedulable {
+        return vec![PodFailure {
+            pod: pod.name_any(),
+            container: None,
+            reason: condition
+                .reason
+                .clone()
+                .unwrap_or("Unschedulable".to_string()),
+            message: condition.message.clone(),
+            last_termination: None,
+            event: None,
+        }];
+    }
+
+    status
+        .init_container_statuses
+        .iter()
+        .flatten()
+        .chain(status.container_statuses.iter().flatten())
+        .filter_map(|container| {
+            let state = container.state.as_ref()?;
+            let (reason, message) = match (&state.waiting, &state.terminated) {
+                (Some(waiting), _) => (waiting.reason.clone()?, waiting.message.clone()),
+                (_, Some(terminated)) if terminated.exit_code != 0 => (
+                    terminated.reason.clone().unwrap_or("Error".to_string()),
+                    terminated.message.clone(),
+                ),
+                _ => return None,
+            };
+            if STARTING_REASONS.contains(&reason.as_str()) {
+                return None;
+            }
+
+            Some(PodFailure {
+                pod: pod.name_any(),
+                container: Some(container.name.clone()),
+                reason,
+                message,
+                last_termination: container
+                    .last_state
+                    .as_ref()
+                    .and_then(|state| state.terminated.as_ref())
+                    .map(|terminated| {
+                        format!(
+                            "{} (exit code {})",
+                            terminated.reason.as_deref().unwrap_or("Error"),
+                            terminated.exit_code
+                        )
+                    }),
+                event: None,
+            })
+        })
+        .collect()
+}
+
+/// Most recent warning about the pod, as `reason: message`
+fn latest_event(events: &[Event], pod: &str) -> Option<String> {
+    events
+        .iter()
+        .filter(|event| event.involved_object.name.as_deref() == Some(pod))
+        .max_by_key(|event| event.last_timestamp.clone())
+        .map(|event| {
+            format!(
+                "{}: {}",
+                event.reason.as_deref().unwrap_or("Warning"),
+                event.message.as_deref().unwrap_or_default()
+            )
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k8s_openapi::{
+        api::{
+            apps::v1::{DeploymentSpec, DeploymentStatus},
+            core::v1::{
+                ContainerState, ContainerStateTerminated, ContainerStateWaiting, ContainerStatus, PodCondition,
+                PodStatus,
+            },
+        },
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    };
+
+    fn pod(status: PodStatus) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some("op-geth-0".to_string()),
+                ..Default::default()
+            },
+            status: Some(status),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deployment_waits_for_the_latest_generation() {
+        let mut deployment = Deployment {
+            metadata: ObjectMeta {
+                name: Some("op-node".to_string()),
+                generation: Some(2),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(1),
+                ..Default::default()
+            }),
+            status: Some(DeploymentStatus {
+                observed_generation: Some(1),
+                updated_replicas: Some(1),
+                available_replicas: Some(1),
+                ..Default::default()
+            }),
+        };
+        assert!(!WorkloadStatus::from(&deployment).is_ready());
+
+        deployment.status.as_mut().unwrap().observed_generation = Some(2);
+        assert!(WorkloadStatus::from(&deployment).is_ready());
+    }
+
+    #[test]
+    fn reports_crashing_containers() {
+        let failures = pod_failures(&pod(PodStatus {
+            container_statuses: Some(vec![
+                ContainerStatus {
+                    name: "op-geth".to_string(),
+                    state: Some(ContainerState {
+                        waiting: Some(ContainerStateWaiting {
+                            reason: Some("CrashLoopBackOff".to_string()),
+                            message: Some("back-off 5m0s restarting failed container".to_string()),
+                        }),
+                        ..Default::default()
+                    }),
+                    last_state: Some(ContainerState {
+                        terminated: Some(ContainerStateTerminated {
+                            reason: Some("Error".to_string()),
+                            exit_code: 1,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                ContainerStatus {
+                    name: "init".to_string(),
+                    state: Some(ContainerState {
+                        waiting: Some(ContainerStateWaiting {
+                            reason: Some("ContainerCreating".to_string()),
+                            message: None,
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            failures
+                .iter()
+                .map(PodFailure::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "op-geth-0/op-geth CrashLoopBackOff: back-off 5m0s restarting failed container, \
+                last terminated with Error (exit code 1)"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unschedulable_pods() {
+        let failures = pod_failures(&pod(PodStatus {
+            conditions: Some(vec![PodCondition {
+                type_: "PodScheduled".to_string(),
+                status: "False".to_string(),
+                reason: Some("Unschedulable".to_string()),
+                message: Some("0/1 nodes are available: 1 Insufficient memory.".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }));
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].container, None);
+        assert_eq!(failures[0].reason, "Unschedulable");
+    }
+}
@@ -0,0 +1,47 @@
+use super::PodFailure;
+use crate::domain::DeploymentError;
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum KubernetesError {
+    #[error("Kubernetes request failed. {0}")]
+    Client(#[from] kube::Error),
+
+    #[error(
+        "Release in {namespace} not ready after {}s, waiting on {}.{}",
+        .timeout.as_secs(),
+        .pending.join(", "),
+        describe_failures(.failures)
+    )]
+    RolloutTimeout {
+        namespace: String,
+        timeout: Duration,
+        /// Workloads that didn't finish rolling out
+        pending: Vec<String>,
+        failures: Vec<PodFailure>,
+    },
+}
+
+// implementations ================================================
+
+impl From<KubernetesError> for DeploymentError {
+    fn from(err: KubernetesError) -> Self {
+        Self::Cluster(Box::new(err))
+    }
+}
+
+fn describe_failures(failures: &[PodFailure]) -> String {
+    if failures.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        " Failing: {}",
+        failures
+            .iter()
+            .map(PodFailure::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    )
+}
@@ -0,0 +1,7 @@
+pub mod error;
+pub use error::*;
+
+pub mod cluster;
+pub mod cluster_kube;
+pub use cluster::*;
+pub use cluster_kube::*;
@@ -1,5 +1,6 @@
 pub mod artifact;
 pub mod deployment;
 pub mod ethereum;
+pub mod kubernetes;
 pub mod project;
 pub mod release;
@@ -1,6 +1,6 @@
 use crate::{
     config::artifacts::{INFRA_SOURCE_REPO, INFRA_SOURCE_REPO_VERSION},
-    domain::{Project, TProjectInfraRepository},
+    domain::{Project, ProjectError, TProjectInfraRepository},
     git,
 };
 
@@ -21,7 +21,7 @@ impl InMemoryProjectInfraRepository {
 }
 
 impl TProjectInfraRepository for InMemoryProjectInfraRepository {
-    fn pull(&self, project: &Project) -> Result<(), Box<dyn std::error::Error>> {
+    fn pull(&self, project: &Project) -> Result<(), ProjectError> {
         if !project.infrastructure.helm.root.exists() {
             git::download_zipped_asset(
                 INFRA_SOURCE_REPO,
@@ -1,8 +1,8 @@
 use std::path::Path;
 
 use crate::{
-    config::CoreConfig,
-    domain::{self, Dockerfiles, HelmCharts, Infrastructure, Project, Src},
+    config::{ConfigError, CoreConfig},
+    domain::{self, Dockerfiles, HelmCharts, Infrastructure, Project, ProjectError, Src},
 };
 
 pub struct InMemoryProjectRepository;
@@ -20,12 +20,15 @@ impl InMemoryProjectRepository {
 }
 
 impl domain::project::TProjectRepository for InMemoryProjectRepository {
-    fn create(&self, root: &Path, config: &CoreConfig) -> Result<Project, Box<dyn std::error::Error>> {
+    fn create(&self, root: &Path, config: &CoreConfig) -> Result<Project, ProjectError> {
         std::fs::write(root.join("README.md"), README)?;
         std::fs::write(root.join(".gitignore"), GITIGNORE)?;
         std::fs::write(root.join(".env"), ENV_FILE)?;
         std::fs::write(root.join(".env.sample"), ENV_FILE)?;
-        std::fs::write(root.join("config.toml"), toml::to_string(config).unwrap())?;
+        std::fs::write(
+            root.join("config.toml"),
+            toml::to_string(config).map_err(ConfigError::from)?,
+        )?;
 
         Ok(Project {
             root: root.to_path_buf(),
@@ -77,10 +80,10 @@ impl domain::project::TProjectRepository for InMemoryProjectRepository {
         filepath.starts_with(&project.root) && filepath.exists()
     }
 
-    fn write(&self, project: &Project, filepath: &Path, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fn write(&self, project: &Project, filepath: &Path, content: &str) -> Result<(), ProjectError> {
         // ensure filepath is a subpath of the project root
         if !filepath.starts_with(&project.root) {
-            return Err("File path is not a subpath of the project root".into());
+            return Err(ProjectError::OutsideRoot(filepath.to_path_buf()));
         }
 
         // Creates all missing directories in the path
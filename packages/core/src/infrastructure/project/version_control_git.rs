@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use crate::domain::TProjectVersionControl;
+use crate::domain::{ProjectError, TProjectVersionControl};
 use git2::{Commit, Oid, Repository, Tree};
 
 pub struct GitVersionControl;
@@ -18,13 +18,13 @@ impl GitVersionControl {
 }
 
 impl TProjectVersionControl for GitVersionControl {
-    fn init(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fn init(&self, path: &Path) -> Result<(), ProjectError> {
         Repository::init(path)?;
 
         Ok(())
     }
 
-    fn stage(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fn stage(&self, path: &Path) -> Result<(), ProjectError> {
         let repo = Repository::open(path)?;
 
         let mut index = repo.index()?;
@@ -34,9 +34,9 @@ impl TProjectVersionControl for GitVersionControl {
         Ok(())
     }
 
-    fn commit(&self, path: &Path, message: &str, initial_commit: bool) -> Result<(), Box<dyn std::error::Error>> {
+    fn commit(&self, path: &Path, message: &str, initial_commit: bool) -> Result<(), ProjectError> {
         let repo = Repository::open(path)?;
-        let signature = repo.signature().unwrap();
+        let signature = repo.signature()?;
         let mut index = repo.index()?;
 
         let oid: Oid;
@@ -72,11 +72,11 @@ impl TProjectVersionControl for GitVersionControl {
         Ok(())
     }
 
-    fn tag(&self, root: &Path, tag: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fn tag(&self, root: &Path, tag: &str) -> Result<(), ProjectError> {
         let repo = Repository::open(root)?;
 
         let head = repo.head()?.peel_to_commit()?;
-        let signature = repo.signature().unwrap();
+        let signature = repo.signature()?;
 
         repo.tag(
             tag,
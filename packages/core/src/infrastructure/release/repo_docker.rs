@@ -1,5 +1,5 @@
 use crate::{
-    domain::{self, Release},
+    domain::{self, Release, ReleaseError},
     system,
 };
 use log::warn;
@@ -34,7 +34,7 @@ impl DockerReleaseRepository {
 }
 
 impl domain::TReleaseRepository for DockerReleaseRepository {
-    fn pull(&self, release: &Release) -> Result<(), Box<dyn std::error::Error>> {
+    fn pull(&self, release: &Release) -> Result<(), ReleaseError> {
         system::execute_command(Command::new("docker").arg("pull").arg(release.uri()), false)?;
 
         Ok(())
@@ -45,7 +45,7 @@ impl domain::TReleaseRepository for DockerReleaseRepository {
         artifact: &domain::Artifact,
         release_name: &str,
         registry_url: &str,
-    ) -> Result<Release, Box<dyn std::error::Error>> {
+    ) -> Result<Release, ReleaseError> {
         let use_buildx = match system::execute_command(Command::new("docker").arg("buildx").arg("version"), true) {
             Ok(_) => true,
             Err(_) => {
@@ -75,7 +75,7 @@ impl domain::TReleaseRepository for DockerReleaseRepository {
             )?;
         } else {
             if !self.exists(artifact) {
-                return Err(format!("Artifact {} not found", artifact.name()).into());
+                return Err(ReleaseError::ArtifactNotFound(artifact.name().to_string()));
             }
 
             system::execute_command(
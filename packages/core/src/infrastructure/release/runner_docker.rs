@@ -1,5 +1,5 @@
 use crate::{
-    domain::{Release, ReleaseRunnerOptions, TReleaseRunner},
+    domain::{Release, ReleaseError, ReleaseRunnerOptions, TReleaseRunner},
     system::execute_command,
 };
 use std::process::Command;
@@ -21,10 +21,12 @@ impl DockerReleaseRunner {
 }
 
 impl TReleaseRunner for DockerReleaseRunner {
-    fn run(&self, release: &Release, opts: ReleaseRunnerOptions) -> Result<(), Box<dyn std::error::Error>> {
-        let env_args: Vec<Vec<String>> = opts
-            .env
-            .iter()
+    fn run(&self, release: &Release, opts: ReleaseRunnerOptions) -> Result<(), ReleaseError> {
+        // sorted so the same options always make the same command
+        let mut env: Vec<_> = opts.env.iter().collect();
+        env.sort();
+        let env_args: Vec<Vec<String>> = env
+            .into_iter()
             .map(|(key, value)| vec!["-e".to_string(), format!("{}={}", key, value)])
             .collect();
 
@@ -45,7 +47,7 @@ impl TReleaseRunner for DockerReleaseRunner {
         Ok(())
     }
 
-    fn stop(&self, container_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fn stop(&self, container_name: &str) -> Result<(), ReleaseError> {
         let running_containers = execute_command(Command::new("docker").arg("ps"), true)?;
         if !running_containers.contains(container_name) {
             return Ok(());
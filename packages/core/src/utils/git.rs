@@ -3,8 +3,25 @@ use std::io::Cursor;
 use std::path::Path;
 
 use git2::{ObjectType, Repository};
+use thiserror::Error as ThisError;
 
-pub fn clone_tag<T, U>(source_repo: U, source_tag: U, dst_path: T) -> Result<(), Box<dyn std::error::Error>>
+/// Failures cloning sources or downloading release assets from github
+#[derive(Debug, ThisError)]
+pub enum GitError {
+    #[error("Git operation failed. {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("Download failed. {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Could not extract downloaded asset. {0}")]
+    Extract(#[from] zip_extract::ZipExtractError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub fn clone_tag<T, U>(source_repo: U, source_tag: U, dst_path: T) -> Result<(), GitError>
 where
     T: AsRef<std::path::Path>,
     U: AsRef<str>,
@@ -31,12 +48,7 @@ where
     Ok(())
 }
 
-pub fn download_release_asset<T, U>(
-    release_repo: T,
-    release_tag: T,
-    asset_path: T,
-    dst_path: U,
-) -> Result<(), Box<dyn std::error::Error>>
+pub fn download_release_asset<T, U>(release_repo: T, release_tag: T, asset_path: T, dst_path: U) -> Result<(), GitError>
 where
     T: AsRef<str>,
     U: AsRef<Path>,
@@ -60,12 +72,7 @@ where
     Ok(())
 }
 
-pub fn download_zipped_asset<T, U>(
-    release_repo: T,
-    release_tag: T,
-    asset: T,
-    dst_path: U,
-) -> Result<(), Box<dyn std::error::Error>>
+pub fn download_zipped_asset<T, U>(release_repo: T, release_tag: T, asset: T, dst_path: U) -> Result<(), GitError>
 where
     T: AsRef<str>,
     U: AsRef<Path>,
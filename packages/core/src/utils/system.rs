@@ -1,12 +1,343 @@
-use log::info;
+use log::{debug, info};
 use std::{
-    fs,
+    fmt, fs,
+    future::pending,
     io::{self},
-    path::Path,
-    process::{Command, Stdio},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::Duration,
 };
+use thiserror::Error as ThisError;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    sync::watch,
+};
+
+/// An external tool (docker, helm, terraform...) could not be run, exited with a non-zero status or was stopped
+#[derive(Debug, ThisError)]
+pub enum CommandError {
+    #[error("Failed to execute {tool}. {source}")]
+    Spawn {
+        tool: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{tool} failed with {}. {stderr}", exit_status(.exit_code))]
+    Failed {
+        tool: String,
+        /// None if the tool was killed by a signal
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+
+    #[error("{tool} timed out after {}s. {stderr}", .timeout.as_secs())]
+    TimedOut {
+        tool: String,
+        timeout: Duration,
+        stdout: String,
+        stderr: String,
+    },
+
+    #[error("{tool} was cancelled. {stderr}")]
+    Cancelled {
+        tool: String,
+        stdout: String,
+        stderr: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Receives the output of a running command line by line
+pub type OutputHandler = Arc<dyn Fn(OutputStream, &str) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct CommandOptions {
+    /// Kills the command if it runs for longer
+    pub timeout: Option<Duration>,
+    /// Where output lines go, debug logs if none
+    pub on_output: Option<OutputHandler>,
+    /// Don't forward output anywhere, it's only captured
+    pub silent: bool,
+    /// Kills the command once it turns true, e.g. on Ctrl-C. Signals are left to the application
+    pub cancel: Option<watch::Receiver<bool>>,
+}
+
+enum Outcome {
+    Exited(io::Result<ExitStatus>),
+    TimedOut(Duration),
+    Cancelled,
+}
+
+impl CommandError {
+    pub fn tool(&self) -> &str {
+        match self {
+            Self::Spawn { tool, .. }
+            | Self::Failed { tool, .. }
+            | Self::TimedOut { tool, .. }
+            | Self::Cancelled { tool, .. } => tool,
+        }
+    }
+
+    /// None unless the tool exited by itself
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            Self::Failed { exit_code, .. } => *exit_code,
+            _ => None,
+        }
+    }
+
+    /// What the tool printed to stderr, prefixed with the reason it was stopped if it didn't exit by itself
+    pub fn stderr(&self) -> String {
+        match self {
+            Self::Spawn { source, .. } => format!("Failed to execute command: {}", source),
+            Self::Failed { stderr, .. } => stderr.clone(),
+            Self::TimedOut {
+                timeout, stderr, ..
+            } => format!("Timed out after {}s. {}", timeout.as_secs(), stderr),
+            Self::Cancelled { stderr, .. } => format!("Cancelled. {}", stderr),
+        }
+    }
+}
+
+impl CommandOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_output(mut self, on_output: Option<OutputHandler>) -> Self {
+        self.on_output = on_output;
+        self
+    }
+
+    pub fn silent(mut self) -> Self {
+        self.silent = true;
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: Option<watch::Receiver<bool>>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
+/// Runs external tools and writes the files handed to them, see `set_command_runner`
+#[async_trait::async_trait]
+pub trait TCommandRunner: Send + Sync {
+    fn execute(&self, command: &mut Command, silent: bool) -> Result<String, CommandError>;
+    async fn run(&self, command: &mut tokio::process::Command, opts: &CommandOptions) -> Result<String, CommandError>;
+    fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    /// Nothing is actually run, callers can't rely on commands outputs
+    fn is_dry_run(&self) -> bool;
+}
+
+/// Spawns the commands as child processes
+pub struct ProcessCommandRunner;
+
+/// Runs nothing, keeps the ordered list of commands and file writes instead
+pub struct RecordingCommandRunner {
+    steps: Mutex<Vec<RecordedStep>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedStep {
+    Command {
+        program: String,
+        args: Vec<String>,
+        current_dir: Option<PathBuf>,
+    },
+    WriteFile {
+        path: PathBuf,
+        size: usize,
+    },
+}
+
+static COMMAND_RUNNER: RwLock<Option<Arc<dyn TCommandRunner>>> = RwLock::new(None);
+
+/// Replaces the runner behind `execute_command`, `run_command` and `write_file` for the whole process
+pub fn set_command_runner(runner: Arc<dyn TCommandRunner>) {
+    *COMMAND_RUNNER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(runner);
+}
+
+fn command_runner() -> Arc<dyn TCommandRunner> {
+    COMMAND_RUNNER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| Arc::new(ProcessCommandRunner))
+}
+
+pub fn is_dry_run() -> bool {
+    command_runner().is_dry_run()
+}
+
+pub fn execute_command(command: &mut Command, silent: bool) -> Result<String, CommandError> {
+    command_runner().execute(command, silent)
+}
+
+/// Runs a command without blocking the runtime, returns its stdout.
+/// Output is forwarded line by line as it's produced. The command runs in its own process group,
+/// which is killed on timeout or cancellation so that tools spawned by it don't outlive it.
+pub async fn run_command(command: &mut tokio::process::Command, opts: &CommandOptions) -> Result<String, CommandError> {
+    command_runner().run(command, opts).await
+}
+
+/// Writes a file meant for an external tool (values.yaml, deploy-config.json...), recorded in dry runs
+pub fn write_file<P, C>(path: P, contents: C) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    command_runner().write_file(path.as_ref(), contents.as_ref())
+}
+
+// implementations =============================================
+
+impl Default for ProcessCommandRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessCommandRunner {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl TCommandRunner for ProcessCommandRunner {
+    fn execute(&self, command: &mut Command, silent: bool) -> Result<String, CommandError> {
+        execute_process(command, silent)
+    }
+
+    async fn run(&self, command: &mut tokio::process::Command, opts: &CommandOptions) -> Result<String, CommandError> {
+        run_process(command, opts).await
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+}
+
+impl Default for RecordingCommandRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecordingCommandRunner {
+    pub fn new() -> Self {
+        Self {
+            steps: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Everything recorded so far, in order
+    pub fn transcript(&self) -> Vec<RecordedStep> {
+        self.steps
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn record(&self, step: RecordedStep) {
+        info!("Dry run: {}", step);
+        self.steps
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(step);
+    }
+
+    fn record_command(&self, command: &Command) {
+        self.record(RecordedStep::Command {
+            program: command.get_program().to_string_lossy().to_string(),
+            args: command
+                .get_args()
+                .map(|arg| redact(&arg.to_string_lossy()))
+                .collect(),
+            current_dir: command.get_current_dir().map(Path::to_path_buf),
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl TCommandRunner for RecordingCommandRunner {
+    fn execute(&self, command: &mut Command, _silent: bool) -> Result<String, CommandError> {
+        self.record_command(command);
+        Ok(String::new())
+    }
 
-pub fn execute_command(command: &mut Command, silent: bool) -> Result<String, String> {
+    async fn run(&self, command: &mut tokio::process::Command, _opts: &CommandOptions) -> Result<String, CommandError> {
+        self.record_command(command.as_std());
+        Ok(String::new())
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.record(RecordedStep::WriteFile {
+            path: path.to_path_buf(),
+            size: contents.len(),
+        });
+        Ok(())
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+impl fmt::Display for RecordedStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordedStep::Command {
+                program,
+                args,
+                current_dir,
+            } => {
+                write!(f, "$ {}", program)?;
+                for arg in args {
+                    match arg.contains(char::is_whitespace) || arg.is_empty() {
+                        true => write!(f, " '{}'", arg)?,
+                        false => write!(f, " {}", arg)?,
+                    }
+                }
+                if let Some(current_dir) = current_dir {
+                    write!(f, "  (in {})", current_dir.display())?;
+                }
+                Ok(())
+            }
+            RecordedStep::WriteFile { path, size } => write!(f, "write {} ({} bytes)", path.display(), size),
+        }
+    }
+}
+
+/// Hides secrets passed as `NAME=value` arguments, e.g. docker `-e DEPLOYER_PRIVATE_KEY=...`
+fn redact(arg: &str) -> String {
+    match arg.split_once('=') {
+        Some((name, _)) if name.to_uppercase().contains("PRIVATE_KEY") => format!("{}=<redacted>", name),
+        _ => arg.to_string(),
+    }
+}
+
+fn execute_process(command: &mut Command, silent: bool) -> Result<String, CommandError> {
     info!("Executing command: {:?}", command);
 
     if !silent && log::log_enabled!(log::Level::Debug) {
@@ -14,9 +345,11 @@ pub fn execute_command(command: &mut Command, silent: bool) -> Result<String, St
         command.stderr(Stdio::inherit());
     }
 
-    let output = command
-        .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
+    let tool = command.get_program().to_string_lossy().to_string();
+    let output = command.output().map_err(|source| CommandError::Spawn {
+        tool: tool.clone(),
+        source,
+    })?;
 
     let result = String::from_utf8_lossy(&output.stdout).to_string();
     let error = String::from_utf8_lossy(&output.stderr).to_string();
@@ -25,10 +358,163 @@ pub fn execute_command(command: &mut Command, silent: bool) -> Result<String, St
     if status.success() {
         Ok(result)
     } else {
-        Err(format!(
-            "Command exited with non-zero status: {}. {}",
-            status, error
-        ))
+        Err(CommandError::Failed {
+            tool,
+            exit_code: status.code(),
+            stdout: result,
+            stderr: error,
+        })
+    }
+}
+
+async fn run_process(command: &mut tokio::process::Command, opts: &CommandOptions) -> Result<String, CommandError> {
+    info!("Executing command: {:?}", command.as_std());
+
+    let tool = command.as_std().get_program().to_string_lossy().to_string();
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let mut child = command.spawn().map_err(|source| CommandError::Spawn {
+        tool: tool.clone(),
+        source,
+    })?;
+    let child_stdout = child.stdout.take();
+    let child_stderr = child.stderr.take();
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    let outcome = {
+        let run = async {
+            let (_, _, status) = tokio::join!(
+                forward_output(child_stdout, OutputStream::Stdout, &mut stdout, &tool, opts),
+                forward_output(child_stderr, OutputStream::Stderr, &mut stderr, &tool, opts),
+                child.wait()
+            );
+            status
+        };
+        let timeout = async {
+            match opts.timeout {
+                Some(timeout) => {
+                    tokio::time::sleep(timeout).await;
+                    timeout
+                }
+                None => pending().await,
+            }
+        };
+        let cancelled = async {
+            match opts.cancel.clone() {
+                // a dropped sender can't cancel anymore
+                Some(mut cancel) => {
+                    if cancel.wait_for(|cancelled| *cancelled).await.is_err() {
+                        pending::<()>().await
+                    }
+                }
+                None => pending().await,
+            }
+        };
+
+        tokio::select! {
+            status = run => Outcome::Exited(status),
+            timeout = timeout => Outcome::TimedOut(timeout),
+            () = cancelled => Outcome::Cancelled,
+        }
+    };
+
+    let status = match outcome {
+        Outcome::Exited(status) => status,
+        Outcome::TimedOut(timeout) => {
+            kill_process_group(&mut child).await;
+            return Err(CommandError::TimedOut {
+                tool,
+                timeout,
+                stdout,
+                stderr,
+            });
+        }
+        Outcome::Cancelled => {
+            kill_process_group(&mut child).await;
+            return Err(CommandError::Cancelled {
+                tool,
+                stdout,
+                stderr,
+            });
+        }
+    };
+
+    match status {
+        Ok(status) if status.success() => Ok(stdout),
+        Ok(status) => Err(CommandError::Failed {
+            tool,
+            exit_code: status.code(),
+            stdout,
+            stderr,
+        }),
+        Err(source) => Err(CommandError::Spawn { tool, source }),
+    }
+}
+
+/// Reads `reader` to the end, capturing it in `captured` and forwarding every line
+async fn forward_output<R>(
+    reader: Option<R>,
+    stream: OutputStream,
+    captured: &mut String,
+    tool: &str,
+    opts: &CommandOptions,
+) where
+    R: AsyncRead + Unpin,
+{
+    let Some(reader) = reader else {
+        return;
+    };
+
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        let line = String::from_utf8_lossy(&buf);
+        captured.push_str(&line);
+
+        if opts.silent {
+            continue;
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        match &opts.on_output {
+            Some(on_output) => on_output(stream, line),
+            None => debug!("{} > {}", tool, line),
+        }
+    }
+}
+
+/// Kills the child and everything it spawned, then reaps it
+async fn kill_process_group(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // the child leads its own group, a negative pid signals the whole group
+        unsafe {
+            libc::kill(-(pid as i32), libc::SIGKILL);
+        }
+    }
+
+    let _ = child.start_kill();
+    let _ = child.wait().await;
+}
+
+/// Formats an exit code for messages, `exit code 1` or `no exit code`
+pub fn exit_status(exit_code: &Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!("exit code {}", code),
+        None => "no exit code".to_string(),
     }
 }
 
@@ -58,3 +544,161 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn reports_failing_tool() {
+        let err = execute_command(
+            Command::new("sh").args(["-c", "echo oops >&2; exit 3"]),
+            true,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.tool(), "sh");
+        assert_eq!(err.exit_code(), Some(3));
+        assert_eq!(err.stderr().trim(), "oops");
+    }
+
+    #[test]
+    fn reports_missing_tool() {
+        let err = execute_command(&mut Command::new("opruaas-missing-tool"), true).unwrap_err();
+
+        assert!(matches!(err, CommandError::Spawn { .. }));
+        assert_eq!(err.exit_code(), None);
+    }
+
+    #[tokio::test]
+    async fn streams_output_lines() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let opts = CommandOptions::new().with_output(Some(Arc::new(move |stream, line: &str| {
+            sink.lock().unwrap().push((stream, line.to_string()));
+        })));
+
+        let stdout = run_command(
+            tokio::process::Command::new("sh").args(["-c", "echo one; echo two >&2; echo three"]),
+            &opts,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(stdout, "one\nthree\n");
+        let lines = lines.lock().unwrap();
+        assert!(lines.contains(&(OutputStream::Stdout, "one".to_string())));
+        assert!(lines.contains(&(OutputStream::Stderr, "two".to_string())));
+        assert!(lines.contains(&(OutputStream::Stdout, "three".to_string())));
+    }
+
+    #[tokio::test]
+    async fn captures_output_of_failing_tool() {
+        let err = run_command(
+            tokio::process::Command::new("sh").args(["-c", "echo partial; echo oops >&2; exit 3"]),
+            &CommandOptions::new().silent(),
+        )
+        .await
+        .unwrap_err();
+
+        match err {
+            CommandError::Failed {
+                exit_code,
+                stdout,
+                stderr,
+                ..
+            } => {
+                assert_eq!(exit_code, Some(3));
+                assert_eq!(stdout, "partial\n");
+                assert_eq!(stderr, "oops\n");
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn kills_process_group_on_timeout() {
+        let started = std::time::Instant::now();
+        let err = run_command(
+            // the background sleep would keep the pipes open if only sh was killed
+            tokio::process::Command::new("sh").args(["-c", "echo started; sleep 30 & sleep 30"]),
+            &CommandOptions::new()
+                .silent()
+                .with_timeout(Duration::from_millis(300)),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        match err {
+            CommandError::TimedOut { stdout, .. } => assert_eq!(stdout, "started\n"),
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn kills_process_group_when_cancelled() {
+        let (cancel, cancelled) = watch::channel(false);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            cancel.send(true).unwrap();
+        });
+
+        let started = std::time::Instant::now();
+        let err = run_command(
+            tokio::process::Command::new("sh").args(["-c", "echo started; sleep 30 & sleep 30"]),
+            &CommandOptions::new().silent().with_cancel(Some(cancelled)),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        match err {
+            CommandError::Cancelled { stdout, .. } => assert_eq!(stdout, "started\n"),
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn recording_runner_runs_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = RecordingCommandRunner::new();
+
+        runner
+            .execute(
+                Command::new("docker").args(["run", "-e", "DEPLOYER_PRIVATE_KEY=0xabc", "image"]),
+                false,
+            )
+            .unwrap();
+        runner
+            .run(
+                tokio::process::Command::new("terraform")
+                    .arg("apply")
+                    .current_dir(dir.path()),
+                &CommandOptions::new(),
+            )
+            .await
+            .unwrap();
+        runner
+            .write_file(&dir.path().join("values.yaml"), b"a: b")
+            .unwrap();
+
+        assert!(!dir.path().join("values.yaml").exists());
+        assert_eq!(
+            runner
+                .transcript()
+                .iter()
+                .map(RecordedStep::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "$ docker run -e DEPLOYER_PRIVATE_KEY=<redacted> image".to_string(),
+                format!("$ terraform apply  (in {})", dir.path().display()),
+                format!(
+                    "write {} (4 bytes)",
+                    dir.path().join("values.yaml").display()
+                ),
+            ]
+        );
+    }
+}
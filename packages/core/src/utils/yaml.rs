@@ -2,8 +2,21 @@ use serde_yaml::{to_writer, Mapping, Value};
 use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
+use thiserror::Error as ThisError;
 
-pub fn rewrite_yaml_to<T, U>(from: T, to: U, updates: &HashMap<&str, Value>) -> Result<(), Box<dyn std::error::Error>>
+#[derive(Debug, ThisError)]
+pub enum YamlError {
+    #[error("Failed to resolve self-reference: {0}")]
+    UnresolvedReference(String),
+
+    #[error("Invalid yaml. {0}")]
+    Parse(#[from] serde_yaml::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub fn rewrite_yaml_to<T, U>(from: T, to: U, updates: &HashMap<&str, Value>) -> Result<(), YamlError>
 where
     T: AsRef<Path>,
     U: AsRef<Path>,
@@ -46,14 +59,14 @@ where
     Ok(())
 }
 
-pub fn resolve_self_references(value: &mut Value, context: &Mapping) -> Result<(), Box<dyn std::error::Error>> {
+pub fn resolve_self_references(value: &mut Value, context: &Mapping) -> Result<(), YamlError> {
     match value {
         Value::String(s) if s.starts_with("self.") => {
             // Handle `self.` references by extracting the key path
             if let Some(resolved_value) = resolve_path(context, &s[5..]) {
                 *value = resolved_value.clone();
             } else {
-                return Err(format!("Failed to resolve self-reference: {}", s).into());
+                return Err(YamlError::UnresolvedReference(s.clone()));
             }
         }
         Value::Mapping(map) => {
@@ -0,0 +1,472 @@
+use opraas_core::{
+    application::deployment::{
+        deploy_contracts::ContractsDeployerService, deploy_infra::InfraDeployerService, run::DeploymentRunnerService,
+        template::DeploymentTemplateService,
+    },
+    config::{AccountsConfig, NetworkConfig},
+    domain::{Deployment, DeploymentError, DeploymentKind, DeploymentOptions, DeploymentTls, Project},
+    infrastructure::{
+        deployment::{
+            DockerContractsDeployer, HelmDeployer, HelmDeploymentRunner, InMemoryDeploymentArtifactsRepository,
+            InMemoryDeploymentRepository, TerraformDeployer,
+        },
+        kubernetes::KubeCluster,
+        project::InMemoryProjectInfraRepository,
+        release::{DockerReleaseRepository, DockerReleaseRunner},
+    },
+    system::{set_command_runner, CommandError, CommandOptions, RecordedStep, RecordingCommandRunner, TCommandRunner},
+};
+use std::{fs, io, path::Path, process::Command, sync::Arc};
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+/// The command runner is process wide, tests recording it can't overlap
+static RECORDING: Mutex<()> = Mutex::const_new(());
+
+fn project() -> (TempDir, Project) {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "").unwrap();
+    fs::create_dir_all(dir.path().join("infra/helm/sequencer")).unwrap();
+    fs::create_dir_all(dir.path().join("infra/aws")).unwrap();
+    let project = Project::try_from(dir.path().to_path_buf()).unwrap();
+
+    (dir, project)
+}
+
+fn deployment() -> Deployment {
+    let mut deployment = Deployment::new(
+        "testnet",
+        "Testnet",
+        "owner",
+        "v1.0.0",
+        "wakeuplabs",
+        NetworkConfig::null(),
+        AccountsConfig::null(),
+    )
+    .unwrap();
+    deployment.network_config.l1_rpc_url = Some("http://localhost:8545".into());
+
+    deployment
+}
+
+fn options() -> DeploymentOptions {
+    DeploymentOptions {
+        host: "example.com".into(),
+        kind: DeploymentKind::Sequencer,
+        monitoring: false,
+        explorer: false,
+        release_tag: "opruaas".into(),
+        release_namespace: "opruaas".into(),
+        storage_class_name: "gp2".into(),
+        sequencer_url: None,
+        values_path: None,
+        tls: DeploymentTls::default(),
+    }
+}
+
+/// Commands as `program subcommand`, written files relative to the project root
+fn summary(transcript: &[RecordedStep], root: &Path) -> Vec<String> {
+    transcript
+        .iter()
+        .map(|step| match step {
+            RecordedStep::Command { program, args, .. } => format!("{} {}", program, args[0]),
+            RecordedStep::WriteFile { path, .. } => match path.strip_prefix(root) {
+                Ok(path) => format!("write {}", path.display()),
+                Err(_) => format!("write {}", path.file_name().unwrap().to_string_lossy()),
+            },
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn deploy_contracts_and_infra_are_recorded() {
+    let _recording = RECORDING.lock().await;
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+
+    ContractsDeployerService::new(
+        InMemoryDeploymentRepository::new(&project.root),
+        InMemoryDeploymentArtifactsRepository::new(&project.root),
+        DockerContractsDeployer::new(
+            Box::new(DockerReleaseRepository::new()),
+            Box::new(DockerReleaseRunner::new()),
+        ),
+    )
+    .deploy(&project, &mut deployment, false, true)
+    .await
+    .unwrap();
+
+    // the infra deployer reads the artifact the dry run didn't save
+    fs::create_dir_all(dir.path().join("deployments/testnet")).unwrap();
+    fs::write(dir.path().join("deployments/testnet/artifact.zip"), "zip").unwrap();
+
+    InfraDeployerService::new(
+        TerraformDeployer::new(Box::new(InMemoryDeploymentArtifactsRepository::new(
+            &project.root,
+        ))),
+        InMemoryDeploymentRepository::new(&project.root),
+        InMemoryProjectInfraRepository::new(),
+    )
+    .deploy(&project, &mut deployment, &options())
+    .await
+    .unwrap();
+
+    let transcript = recorder.transcript();
+    assert_eq!(
+        summary(&transcript, dir.path()),
+        vec![
+            "write deploy-config.json",
+            "docker pull",
+            "docker run",
+            "write deployments/testnet/deployment.json",
+            "write deployments/testnet/artifact.zip",
+            "write infra/helm/sequencer/.tmp/values.yaml",
+            "write infra/helm/sequencer/.tmp/artifacts.zip",
+            "write infra/helm/sequencer/.tmp/addresses.json",
+            "terraform init",
+            "terraform workspace",
+            "terraform state",
+            "terraform workspace",
+            "terraform plan",
+            "terraform apply",
+            "terraform output",
+            "write deployments/testnet/deployment.json",
+        ]
+    );
+
+    // secrets passed to the contracts deployer don't end up in the transcript
+    let docker_run = transcript[2].to_string();
+    assert!(docker_run.contains("DEPLOYER_PRIVATE_KEY=<redacted>"));
+    assert!(!docker_run.contains(
+        AccountsConfig::null()
+            .deployer_private_key
+            .unwrap()
+            .as_str()
+    ));
+
+    // each deployment gets its own state
+    assert!(transcript[11]
+        .to_string()
+        .contains("terraform workspace select -or-create=true testnet"));
+
+    // terraform runs from the project infra and nothing was written
+    assert!(transcript[13]
+        .to_string()
+        .ends_with(&format!("(in {})", project.infrastructure.aws.display())));
+    assert!(!dir
+        .path()
+        .join("infra/helm/sequencer/.tmp/values.yaml")
+        .exists());
+    assert!(!dir
+        .path()
+        .join("deployments/testnet/deployment.json")
+        .exists());
+}
+
+#[tokio::test]
+async fn infra_plan_is_saved_and_outdated_plans_are_refused() {
+    let _recording = RECORDING.lock().await;
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+    deployment.contracts_addresses = Some("{}".into());
+    fs::create_dir_all(dir.path().join("deployments/testnet")).unwrap();
+    fs::write(dir.path().join("deployments/testnet/artifact.zip"), "zip").unwrap();
+    let plan_file = dir.path().join("deployments/testnet/infra.tfplan");
+
+    let infra_deployer = InfraDeployerService::new(
+        TerraformDeployer::new(Box::new(InMemoryDeploymentArtifactsRepository::new(
+            &project.root,
+        ))),
+        InMemoryDeploymentRepository::new(&project.root),
+        InMemoryProjectInfraRepository::new(),
+    );
+    let plan = infra_deployer
+        .plan(&project, &deployment, &options(), &plan_file)
+        .await
+        .unwrap();
+    assert_eq!(
+        plan.summary_file,
+        dir.path().join("deployments/testnet/infra.tfplan.txt")
+    );
+
+    let transcript = recorder.transcript();
+    assert_eq!(
+        summary(&transcript, dir.path())[3..],
+        [
+            "terraform init",
+            "terraform workspace",
+            "terraform state",
+            "terraform workspace",
+            "terraform plan",
+            "terraform show",
+            "write deployments/testnet/infra.tfplan.txt",
+            "write deployments/testnet/infra.tfplan.json",
+        ]
+    );
+    assert!(transcript[7]
+        .to_string()
+        .contains(&format!("-out={}", plan_file.display())));
+
+    // a plan made with other inputs is never applied
+    fs::write(
+        dir.path().join("deployments/testnet/infra.tfplan.json"),
+        r#"{"workspace": "testnet", "digest": "planned with other values"}"#,
+    )
+    .unwrap();
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let err = infra_deployer
+        .apply_plan(&project, &mut deployment, &options(), &plan_file)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DeploymentError::PlanOutdated(_)));
+    assert!(!summary(&recorder.transcript(), dir.path())
+        .iter()
+        .any(|step| step.starts_with("terraform")));
+}
+
+#[tokio::test]
+async fn relative_plan_files_are_passed_to_terraform_as_absolute_paths() {
+    let _recording = RECORDING.lock().await;
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+    deployment.contracts_addresses = Some("{}".into());
+    fs::create_dir_all(dir.path().join("deployments/testnet")).unwrap();
+    fs::write(dir.path().join("deployments/testnet/artifact.zip"), "zip").unwrap();
+
+    // terraform runs from infra/aws, where a relative plan file would be looked up
+    let plan_file = Path::new("infra.tfplan");
+    let expected = std::env::current_dir().unwrap().join(plan_file);
+
+    let infra_deployer = InfraDeployerService::new(
+        TerraformDeployer::new(Box::new(InMemoryDeploymentArtifactsRepository::new(
+            &project.root,
+        ))),
+        InMemoryDeploymentRepository::new(&project.root),
+        InMemoryProjectInfraRepository::new(),
+    );
+    let plan = infra_deployer
+        .plan(&project, &deployment, &options(), plan_file)
+        .await
+        .unwrap();
+    assert_eq!(plan.plan_file, expected);
+
+    let transcript = recorder.transcript();
+    let terraform_args = |subcommand: &str| {
+        transcript
+            .iter()
+            .find_map(|step| match step {
+                RecordedStep::Command { program, args, .. } if program == "terraform" && args[0] == subcommand => {
+                    Some(args.clone())
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+    assert!(terraform_args("plan").contains(&format!("-out={}", expected.display())));
+    assert!(terraform_args("show").contains(&expected.display().to_string()));
+}
+
+/// A terraform project whose `default` workspace has resources and whose only other workspace is `existing`
+struct DefaultStateRunner(RecordingCommandRunner);
+
+#[async_trait::async_trait]
+impl TCommandRunner for DefaultStateRunner {
+    fn execute(&self, command: &mut Command, silent: bool) -> Result<String, CommandError> {
+        self.0.execute(command, silent)
+    }
+
+    async fn run(&self, command: &mut tokio::process::Command, opts: &CommandOptions) -> Result<String, CommandError> {
+        let args: Vec<String> = command
+            .as_std()
+            .get_args()
+            .take(2)
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        self.0.run(command, opts).await?;
+        match args.join(" ").as_str() {
+            "workspace list" => Ok("* default\n  existing\n".into()),
+            "state list" => Ok("module.eks.aws_eks_cluster.this[0]\n".into()),
+            _ => Ok(String::new()),
+        }
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.0.write_file(path, contents)
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+#[tokio::test]
+async fn state_left_in_the_default_workspace_is_not_adopted() {
+    let _recording = RECORDING.lock().await;
+    set_command_runner(Arc::new(DefaultStateRunner(RecordingCommandRunner::new())));
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+    deployment.contracts_addresses = Some("{}".into());
+    for id in ["testnet", "existing"] {
+        fs::create_dir_all(dir.path().join("deployments").join(id)).unwrap();
+        fs::write(
+            dir.path().join("deployments").join(id).join("artifact.zip"),
+            "zip",
+        )
+        .unwrap();
+    }
+    let infra_deployer = InfraDeployerService::new(
+        TerraformDeployer::new(Box::new(InMemoryDeploymentArtifactsRepository::new(
+            &project.root,
+        ))),
+        InMemoryDeploymentRepository::new(&project.root),
+        InMemoryProjectInfraRepository::new(),
+    );
+
+    // a new workspace could be handed someone else's resources
+    let err = infra_deployer
+        .plan(&project, &deployment, &options(), Path::new("infra.tfplan"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DeploymentError::DefaultWorkspaceInUse { .. }));
+
+    // deployments that already have a workspace aren't affected
+    deployment.id = "existing".into();
+    infra_deployer
+        .plan(&project, &deployment, &options(), Path::new("infra.tfplan"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn helm_run_is_recorded() {
+    let _recording = RECORDING.lock().await;
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+    deployment.contracts_addresses = Some("{}".into());
+
+    DeploymentRunnerService::new(
+        HelmDeploymentRunner::new(
+            Box::new(InMemoryDeploymentArtifactsRepository::new(&project.root)),
+            Box::new(KubeCluster::new()),
+        ),
+        InMemoryProjectInfraRepository::new(),
+    )
+    .run(&project, &deployment, &options())
+    .await
+    .unwrap();
+
+    let summary = summary(&recorder.transcript(), dir.path());
+    assert_eq!(summary.iter().filter(|s| *s == "helm repo").count(), 5);
+    assert_eq!(
+        summary[5..],
+        [
+            "helm install",
+            "helm install",
+            "helm dependency",
+            "helm dependency",
+            "write infra/helm/sequencer/.tmp/values.yaml",
+            "write infra/helm/sequencer/.tmp/artifacts.zip",
+            "write infra/helm/sequencer/.tmp/addresses.json",
+            "helm upgrade",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn deploy_infra_with_helm_is_recorded() {
+    let _recording = RECORDING.lock().await;
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+    deployment.contracts_addresses = Some("{}".into());
+
+    InfraDeployerService::new(
+        HelmDeployer::new(
+            Box::new(InMemoryDeploymentArtifactsRepository::new(&project.root)),
+            Box::new(KubeCluster::new()),
+        ),
+        InMemoryDeploymentRepository::new(&project.root),
+        InMemoryProjectInfraRepository::new(),
+    )
+    .deploy(&project, &mut deployment, &options())
+    .await
+    .unwrap();
+
+    // installs into the current context, no cluster is provisioned
+    let summary = summary(&recorder.transcript(), dir.path());
+    assert!(!summary.iter().any(|step| step.starts_with("terraform")));
+    assert_eq!(
+        summary[summary.len() - 2..],
+        ["helm upgrade", "write deployments/testnet/deployment.json"]
+    );
+    assert_eq!(deployment.infra_base_url, Some("example.com".into()));
+}
+
+#[tokio::test]
+async fn helm_template_is_recorded() {
+    let _recording = RECORDING.lock().await;
+    let recorder = Arc::new(RecordingCommandRunner::new());
+    set_command_runner(recorder.clone());
+
+    let (dir, project) = project();
+    let mut deployment = deployment();
+    deployment.contracts_addresses = Some("{}".into());
+    let output_dir = dir.path().join("manifests");
+
+    let files = DeploymentTemplateService::new(
+        HelmDeploymentRunner::new(
+            Box::new(InMemoryDeploymentArtifactsRepository::new(&project.root)),
+            Box::new(KubeCluster::new()),
+        ),
+        InMemoryProjectInfraRepository::new(),
+    )
+    .render(&project, &deployment, &options(), &output_dir)
+    .await
+    .unwrap();
+    assert!(files.is_empty());
+
+    // nothing is installed, not even the pre-requisites
+    let transcript = recorder.transcript();
+    let summary = summary(&transcript, dir.path());
+    assert!(!summary
+        .iter()
+        .any(|step| step == "helm install" || step == "helm upgrade"));
+    // chart dependencies come from the helm repositories, `template` documents it needs the network
+    assert_eq!(
+        summary[..5],
+        ["helm repo", "helm repo", "helm repo", "helm repo", "helm repo"]
+    );
+    assert_eq!(
+        summary[5..],
+        [
+            "helm dependency",
+            "helm dependency",
+            "write infra/helm/sequencer/.tmp/values.yaml",
+            "write infra/helm/sequencer/.tmp/artifacts.zip",
+            "write infra/helm/sequencer/.tmp/addresses.json",
+            "helm template",
+        ]
+    );
+    assert!(transcript
+        .last()
+        .unwrap()
+        .to_string()
+        .contains(&format!("--output-dir {}", output_dir.display())));
+}
@@ -0,0 +1,79 @@
+use std::{fs, path::Path, process::Command};
+use tempfile::TempDir;
+
+/// Renders a chart template alone, with the given values. None if helm isn't installed
+fn render(chart: &str, template: &str, values: &str) -> Option<String> {
+    if Command::new("helm").arg("version").output().is_err() {
+        eprintln!("helm not found, skipping");
+        return None;
+    }
+
+    // a chart with just the template, the real ones need their dependencies built
+    let dir = TempDir::new().unwrap();
+    let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../infrastructure/helm")
+        .join(chart)
+        .join("templates")
+        .join(template);
+    fs::create_dir_all(dir.path().join("templates")).unwrap();
+    fs::copy(source, dir.path().join("templates").join(template)).unwrap();
+    fs::write(
+        dir.path().join("Chart.yaml"),
+        format!("apiVersion: v2\nname: {}\nversion: 0.1.0\n", chart),
+    )
+    .unwrap();
+    fs::write(dir.path().join("values.yaml"), values).unwrap();
+
+    let output = Command::new("helm")
+        .arg("template")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn cluster_issuer_solves_through_nginx() {
+    let values = r#"
+global:
+  tls:
+    enabled: true
+    acme:
+      enabled: true
+      issuer: letsencrypt-testnet
+      server: https://acme-staging-v02.api.letsencrypt.org/directory
+      email: ops@example.com
+"#;
+
+    for chart in ["sequencer", "replica"] {
+        let Some(rendered) = render(chart, "issuer.yaml", values) else {
+            return;
+        };
+        let issuer: serde_yaml::Value = serde_yaml::from_str(&rendered).unwrap();
+
+        assert_eq!(issuer["kind"], "ClusterIssuer");
+        assert_eq!(issuer["metadata"]["name"], "letsencrypt-testnet");
+        // `class` is understood by every cert-manager version, `ingressClassName` only from v1.12
+        let ingress = &issuer["spec"]["acme"]["solvers"][0]["http01"]["ingress"];
+        assert_eq!(ingress["class"], "nginx");
+        assert!(ingress.get("ingressClassName").is_none());
+    }
+}
+
+#[test]
+fn cluster_issuer_is_skipped_without_acme() {
+    let values = "global:\n  tls:\n    enabled: true\n    acme:\n      enabled: false\n";
+
+    for chart in ["sequencer", "replica"] {
+        let Some(rendered) = render(chart, "issuer.yaml", values) else {
+            return;
+        };
+        assert!(!rendered.contains("ClusterIssuer"));
+    }
+}
@@ -32,6 +32,7 @@ terraform {
     }
   }
 
-  required_version = "~> 1.3"
+  # workspaces are selected with `-or-create`, added in 1.4
+  required_version = "~> 1.4"
 }
 